@@ -1,5 +1,10 @@
+#[cfg(feature = "web-socket")]
+use std::collections::VecDeque;
 use std::{
-    io::{self, BufRead, Read, Write},
+    fmt,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    str::FromStr,
     thread,
 };
 
@@ -55,6 +60,246 @@ pub fn with_stdio_transport(
     Ok(())
 }
 
+/// The address to listen on for LSP clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    /// Exchanges messages with the `Content-Length` framing, e.g.
+    /// `tcp://127.0.0.1:8080`.
+    Tcp(String),
+    /// Exchanges one message per text frame, e.g. `ws://127.0.0.1:8080`.
+    #[cfg(feature = "web-socket")]
+    WebSocket(String),
+}
+
+impl ListenAddr {
+    /// The socket address to bind.
+    pub fn addr(&self) -> &str {
+        match self {
+            Self::Tcp(addr) => addr,
+            #[cfg(feature = "web-socket")]
+            Self::WebSocket(addr) => addr,
+        }
+    }
+}
+
+impl FromStr for ListenAddr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, addr) = s.split_once("://").unwrap_or(("tcp", s));
+        match scheme {
+            "tcp" => Ok(Self::Tcp(addr.to_owned())),
+            #[cfg(feature = "web-socket")]
+            "ws" => Ok(Self::WebSocket(addr.to_owned())),
+            _ => Err(format!("unsupported transport: {scheme}")),
+        }
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "tcp://{addr}"),
+            #[cfg(feature = "web-socket")]
+            Self::WebSocket(addr) => write!(f, "ws://{addr}"),
+        }
+    }
+}
+
+/// Listens on the address and serves the connected clients one after another.
+///
+/// The function is called once per client, and the next client is accepted
+/// after the previous one disconnects.
+pub fn with_listen_transport(
+    listen: &ListenAddr,
+    mut f: impl FnMut(Connection) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(listen.addr())?;
+    log::info!("listening for clients on {listen}");
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!("failed to accept client: {err}");
+                continue;
+            }
+        };
+        let peer = match stream.peer_addr() {
+            Ok(peer) => peer,
+            Err(err) => {
+                log::warn!("failed to get the address of client: {err}");
+                continue;
+            }
+        };
+        log::info!("client {peer} connected");
+
+        let res = match listen {
+            ListenAddr::Tcp(..) => serve_tcp(stream, &mut f),
+            #[cfg(feature = "web-socket")]
+            ListenAddr::WebSocket(..) => serve_web_socket(stream, &mut f),
+        };
+        match res {
+            Ok(()) => log::info!("client {peer} disconnected"),
+            Err(err) => log::error!("client {peer} disconnected with error: {err:#}"),
+        }
+    }
+
+    Ok(())
+}
+
+fn serve_tcp(
+    stream: TcpStream,
+    f: &mut impl FnMut(Connection) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let inp = stream.try_clone()?;
+    let out = stream.try_clone()?;
+    let (sender, receiver, io_threads) = io_transport(move || BufReader::new(inp), move || out);
+
+    let res = f(Connection { sender, receiver });
+    let written = io_threads.join_write();
+    // Unblocks the reader if the client is still connected.
+    let _ = stream.shutdown(Shutdown::Both);
+    res?;
+    written?;
+
+    Ok(())
+}
+
+#[cfg(feature = "web-socket")]
+fn serve_web_socket(
+    stream: TcpStream,
+    f: &mut impl FnMut(Connection) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    use crossbeam_channel::{select, unbounded};
+    use tungstenite::protocol::Role;
+    use tungstenite::{Error, Message as WsMessage, WebSocket};
+
+    tungstenite::accept(stream.try_clone()?)?;
+
+    // A single thread owns the WebSocket, so that the control frames replied
+    // by it, e.g. pongs, never interleave with the data frames. The bytes from
+    // the client are read by another thread and fed to it, so that it waits
+    // for the incoming bytes and the outgoing messages at the same time.
+    let (bytes_sender, bytes) = unbounded::<Vec<u8>>();
+    let mut inp = stream.try_clone()?;
+    let reader = thread::spawn(move || -> io::Result<()> {
+        let mut buf = [0; 4096];
+        loop {
+            let len = inp.read(&mut buf)?;
+            if len == 0 || bytes_sender.send(buf[..len].to_vec()).is_err() {
+                return Ok(());
+            }
+        }
+    });
+
+    let (sender, outgoing) = unbounded::<Message>();
+    let (incoming, receiver) = unbounded::<Message>();
+    let out = stream.try_clone()?;
+    let socket_thread = thread::spawn(move || -> io::Result<()> {
+        let stream = FedStream {
+            inp: VecDeque::new(),
+            out,
+        };
+        let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+        loop {
+            select! {
+                recv(outgoing) -> msg => {
+                    let Ok(msg) = msg else {
+                        let _ = socket.close(None);
+                        let _ = socket.flush();
+                        return Ok(());
+                    };
+                    let text = serde_json::to_string(&msg)?;
+                    socket
+                        .send(WsMessage::text(text))
+                        .map_err(io::Error::other)?;
+                }
+                recv(bytes) -> chunk => {
+                    // The client closed the connection without a close frame.
+                    let Ok(chunk) = chunk else {
+                        drop(incoming);
+                        outgoing.iter().for_each(drop);
+                        return Ok(());
+                    };
+                    socket.get_mut().inp.extend(chunk);
+
+                    loop {
+                        match socket.read() {
+                            Ok(WsMessage::Text(text)) => {
+                                let msg = serde_json::from_str(&text)?;
+                                log::trace!("sending message {:#?}", msg);
+                                incoming
+                                    .send(msg)
+                                    .expect("receiver was dropped, failed to send a message");
+                            }
+                            Ok(WsMessage::Close(..))
+                            | Err(Error::ConnectionClosed | Error::AlreadyClosed) => {
+                                // Replies the close frame, and drops the outgoing messages
+                                // until the server stops.
+                                let _ = socket.flush();
+                                drop(incoming);
+                                outgoing.iter().for_each(drop);
+                                return Ok(());
+                            }
+                            Ok(..) => {}
+                            Err(Error::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => break,
+                            Err(err) => return Err(io::Error::other(err)),
+                        }
+                    }
+                    // Sends the replied control frames.
+                    socket.flush().map_err(io::Error::other)?;
+                }
+            }
+        }
+    });
+
+    let res = f(Connection { sender, receiver });
+    let written = match socket_thread.join() {
+        Ok(written) => written,
+        Err(err) => {
+            eprintln!("socket thread panicked!");
+            std::panic::panic_any(err)
+        }
+    };
+    // Unblocks the reader if the client is still connected.
+    let _ = stream.shutdown(Shutdown::Both);
+    let _ = reader.join();
+    res?;
+    written?;
+
+    Ok(())
+}
+
+/// A stream that reads the bytes fed to it and writes to the TCP stream. It
+/// would block on reading if all the fed bytes are read.
+#[cfg(feature = "web-socket")]
+struct FedStream {
+    inp: VecDeque<u8>,
+    out: TcpStream,
+}
+
+#[cfg(feature = "web-socket")]
+impl Read for FedStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.inp.is_empty() {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        self.inp.read(buf)
+    }
+}
+
+#[cfg(feature = "web-socket")]
+impl Write for FedStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
 /// Creates an LSP connection via io.
 ///
 /// # Example
@@ -77,7 +322,7 @@ pub fn io_transport<I: BufRead, O: Write>(
         let mut out = out();
         let res = writer_receiver
             .into_iter()
-            .try_for_each(|it| it.write(&mut out));
+            .try_for_each(|msg| msg.write(&mut out));
 
         log::info!("writer thread finished");
         res
@@ -4,6 +4,8 @@ mod bib;
 use std::path::Path;
 
 pub(crate) use bib::*;
+mod imports;
+pub(crate) use imports::*;
 pub mod call;
 pub use call::*;
 pub mod completion;
@@ -16,6 +18,8 @@ pub mod doc_highlight;
 pub use doc_highlight::*;
 pub mod link_expr;
 pub use link_expr::*;
+pub mod linter;
+pub use linter::*;
 pub mod stats;
 pub use stats::*;
 pub mod definition;
@@ -293,6 +297,26 @@ mod module_tests {
     }
 }
 
+#[cfg(test)]
+mod lint_tests {
+    use crate::lint_sources_diagnostics;
+    use crate::prelude::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("lint", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let mut diagnostics = lint_sources_diagnostics(ctx, [source]);
+            let result = diagnostics
+                .remove(&path_to_url(&path).unwrap())
+                .unwrap_or_default();
+            assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
+        });
+    }
+}
+
 #[cfg(test)]
 mod type_check_tests {
 
@@ -1,9 +1,17 @@
 //! Provides code actions for the document.
 
+use ecow::eco_format;
 use regex::Regex;
 
+use rustc_hash::FxHashSet;
+
+use super::{unused_decls, UnusedKind};
+use crate::find_references;
 use crate::prelude::*;
-use crate::syntax::{interpret_mode_at, InterpretMode};
+use crate::syntax::{interpret_mode_at, DeclExpr, Expr, InterpretMode, SyntaxClass};
+
+mod fix;
+mod imports;
 
 /// Analyzes the document and provides code actions.
 pub struct CodeActionWorker<'a> {
@@ -56,7 +64,11 @@ impl<'a> CodeActionWorker<'a> {
         let mut heading_resolved = false;
         let mut equation_resolved = false;
 
-        self.wrap_actions(node, range);
+        self.wrap_actions(node, range.clone());
+        self.extract_actions(&root, range);
+        self.inline_actions(&root, cursor);
+        self.organize_imports(&root);
+        self.expand_wildcard(&root, cursor);
 
         loop {
             match node.kind() {
@@ -111,6 +123,502 @@ impl<'a> CodeActionWorker<'a> {
         Some(())
     }
 
+    /// Provides quick fixes for the unused declarations in the range.
+    pub fn unused_actions(&mut self, root: &LinkedNode, range: Range<usize>) {
+        for unused in unused_decls(self.ctx, &self.source) {
+            let Some(node) = root.find(unused.decl.span()) else {
+                continue;
+            };
+            let decl_range = node.range();
+            if decl_range.end < range.start || decl_range.start > range.end {
+                continue;
+            }
+
+            let name = unused.decl.name();
+            let removal = match unused.kind {
+                UnusedKind::Variable | UnusedKind::Function => binding_range(&node),
+                UnusedKind::Import => import_item_range(&node),
+                UnusedKind::Parameter => None,
+            };
+            if let Some(removal) = removal {
+                let removal = line_range(self.source.text(), removal);
+                let title = match unused.kind {
+                    UnusedKind::Import => format!("Remove unused import `{name}`"),
+                    _ => format!("Remove unused binding `{name}`"),
+                };
+                self.quick_fix(title, removal, "");
+            }
+
+            // Renaming a named parameter changes the signature of the function.
+            let is_named_param = node.parent_kind() == Some(SyntaxKind::Named);
+            if unused.kind != UnusedKind::Import && !is_named_param {
+                let title = format!("Prefix `{name}` with an underscore");
+                self.quick_fix(title, decl_range.start..decl_range.start, "_");
+            }
+        }
+    }
+
+    fn quick_fix(&mut self, title: String, range: Range<usize>, new_text: &str) -> Option<()> {
+        let edit = self.local_edit(TextEdit {
+            range: self.ctx.to_lsp_range(range, &self.source),
+            new_text: new_text.to_owned(),
+        })?;
+
+        let action = CodeActionOrCommand::CodeAction(CodeAction {
+            title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            edit: Some(edit),
+            ..CodeAction::default()
+        });
+        self.actions.push(action);
+
+        Some(())
+    }
+
+    fn refactor(
+        &mut self,
+        title: String,
+        kind: CodeActionKind,
+        edits: Vec<TextEdit>,
+    ) -> Option<()> {
+        let action = CodeActionOrCommand::CodeAction(CodeAction {
+            title,
+            kind: Some(kind),
+            edit: Some(self.local_edits(edits)?),
+            ..CodeAction::default()
+        });
+        self.actions.push(action);
+
+        Some(())
+    }
+
+    fn extract_actions(&mut self, root: &LinkedNode, range: Range<usize>) -> Option<()> {
+        let range = trim_range(self.source.text(), range);
+        if range.is_empty() {
+            return None;
+        }
+
+        // The smallest node covering the selection.
+        let mut node = root.leaf_at_compat(range.start + 1)?;
+        while node.offset() > range.start || node.range().end < range.end {
+            node = node.parent()?.clone();
+        }
+
+        self.extract_variable(&node, range.clone());
+        self.extract_function(&node, range);
+
+        Some(())
+    }
+
+    /// Extracts an expression or a span of markup to a `let` binding.
+    fn extract_variable(&mut self, node: &LinkedNode, range: Range<usize>) -> Option<()> {
+        let source = self.source.clone();
+        let text = source.text();
+        let selected = &text[range.clone()];
+
+        let (value, stmt, is_markup) = if node.range() == range && is_code_expr(node) {
+            let scope = scope_of(node)?;
+            (selected.to_owned(), child_of(&scope, node)?, false)
+        } else {
+            // A span of markup, which is not necessarily a node, e.g. some words.
+            let (scope, children) = match node.kind() {
+                SyntaxKind::Markup => (node.clone(), covered_children(node, &range)?),
+                SyntaxKind::Text if node.parent_kind() == Some(SyntaxKind::Markup) => {
+                    (node.parent()?.clone(), vec![node.clone()])
+                }
+                _ if node.range() == range && node.parent_kind() == Some(SyntaxKind::Markup) => {
+                    (node.parent()?.clone(), vec![node.clone()])
+                }
+                _ => return None,
+            };
+            if children.iter().all(|child| child.kind().is_trivia()) {
+                return None;
+            }
+            let stmt = children.into_iter().next()?;
+            (format!("[{selected}]"), child_of(&scope, &stmt)?, true)
+        };
+
+        // The referred declarations must be visible where the binding is inserted.
+        let stmt_start = statement_range(&stmt).start;
+        let defs = self
+            .free_decls(&range)
+            .into_iter()
+            .map(|(_, def_range)| def_range)
+            .collect::<Vec<_>>();
+        if defs.iter().any(|def| def.start >= stmt_start) {
+            return None;
+        }
+
+        let name = self.fresh_name("extracted");
+        let in_markup = stmt.parent_kind() == Some(SyntaxKind::Markup);
+        let hash = if in_markup { "#" } else { "" };
+        let decl = format!("{hash}let {name} = {value}");
+        let call = if is_markup {
+            format!("#{name}{}", continuation(text, range.end))
+        } else {
+            name.to_string()
+        };
+
+        let edits = self.insert_before(&stmt, &decl, in_markup, &defs, range, call)?;
+        let title = format!("Extract to `{hash}let {name}`");
+        self.refactor(title, CodeActionKind::REFACTOR_EXTRACT, edits)
+    }
+
+    /// Extracts statements in code or markup to a function, whose parameters
+    /// are the local variables used by the statements.
+    fn extract_function(&mut self, node: &LinkedNode, range: Range<usize>) -> Option<()> {
+        let source = self.source.clone();
+        let text = source.text();
+
+        let (scope, children) = match node.kind() {
+            SyntaxKind::Markup | SyntaxKind::Code => {
+                (node.clone(), covered_children(node, &range)?)
+            }
+            _ if node.range() == range
+                && matches!(
+                    node.parent_kind(),
+                    Some(SyntaxKind::Markup | SyntaxKind::Code)
+                ) =>
+            {
+                (node.parent()?.clone(), vec![node.clone()])
+            }
+            _ => return None,
+        };
+        let stmts = children
+            .iter()
+            .filter(|child| !child.kind().is_trivia() && child.kind() != SyntaxKind::Semicolon);
+        if stmts.clone().count() == 0 || children.iter().any(has_control_flow) {
+            return None;
+        }
+        // A `set` or `show` rule applies to the content after it, which would
+        // be limited to the function body.
+        let mut kinds = stmts.clone().map(|child| child.kind());
+        if kinds.any(|kind| matches!(kind, SyntaxKind::SetRule | SyntaxKind::ShowRule)) {
+            return None;
+        }
+        let in_markup = scope.kind() == SyntaxKind::Markup;
+        let stmt = children.first()?;
+
+        // The bindings of the statements used later cannot be extracted.
+        let ei = self.ctx.expr_stage(&self.source);
+        for (span, r) in ei.resolves.iter() {
+            let Some(Expr::Decl(def)) = &r.step else {
+                continue;
+            };
+            let def_range = self.source.range(def.span());
+            let use_range = self.source.range(*span);
+            if let (Some(def_range), Some(use_range)) = (def_range, use_range) {
+                if contains(&range, &def_range) && !contains(&range, &use_range) {
+                    return None;
+                }
+            }
+        }
+
+        // The local variables are passed by the parameters. The module-level
+        // declarations are captured unless they are declared after the
+        // insertion.
+        let stmt_start = statement_range(stmt).start;
+        let line_start = self.line_start(&scope, stmt_start);
+        let root = LinkedNode::new(source.root());
+        let mut params = vec![];
+        let mut defs = vec![];
+        let mut frees = FxHashSet::default();
+        for (def, def_range) in self.free_decls(&range) {
+            let module_level = root
+                .find(def.span())
+                .map_or(false, |def_node| is_module_level(&def_node));
+            if module_level && def_range.start < line_start.unwrap_or(stmt_start) {
+                defs.push(def_range);
+            } else {
+                params.push(EcoString::from(def.name().as_ref()));
+            }
+            frees.insert(def);
+        }
+        // After extracted to a function, a mutated parameter is not seen by
+        // the caller, and a captured variable cannot be mutated at all.
+        if self.mutates_free_decls(&root, &range, &frees) {
+            return None;
+        }
+
+        let name = self.fresh_name("extracted-fn");
+        let params = params.iter().join(", ");
+        let selected = &text[range.clone()];
+        let base = line_start.unwrap_or(stmt_start);
+        let indent = &text[base..base + leading_indent(text, base)];
+        let body = if !selected.contains('\n') && stmts.count() == 1 && !in_markup {
+            selected.to_owned()
+        } else if !selected.contains('\n') {
+            if in_markup {
+                format!("[{selected}]")
+            } else {
+                format!("{{ {selected} }}")
+            }
+        } else {
+            let (open, close) = if in_markup { ("[", "]") } else { ("{", "}") };
+            let body = selected.replace('\n', "\n  ");
+            format!("{open}\n{indent}  {body}\n{indent}{close}")
+        };
+
+        let hash = if in_markup { "#" } else { "" };
+        let decl = format!("{hash}let {name}({params}) = {body}");
+        let call = format!("{hash}{name}({params})");
+        let call = if in_markup {
+            call + continuation(text, range.end)
+        } else {
+            call
+        };
+
+        let edits = self.insert_before(stmt, &decl, in_markup, &defs, range, call)?;
+        let title = format!("Extract to function `{name}`");
+        self.refactor(title, CodeActionKind::REFACTOR_EXTRACT, edits)
+    }
+
+    /// Gets the declarations in this file that are used in the range but
+    /// declared outside of it, in the order of their first uses.
+    fn free_decls(&mut self, range: &Range<usize>) -> Vec<(DeclExpr, Range<usize>)> {
+        let ei = self.ctx.expr_stage(&self.source);
+        let mut uses = vec![];
+        for (span, r) in ei.resolves.iter() {
+            let Some(Expr::Decl(def)) = &r.step else {
+                continue;
+            };
+            if *span == def.span() || !matches!(**def, Decl::Var(..) | Decl::Func(..)) {
+                continue;
+            }
+            if def.file_id() != Some(self.source.id()) {
+                continue;
+            }
+            let (Some(use_range), Some(def_range)) =
+                (self.source.range(*span), self.source.range(def.span()))
+            else {
+                continue;
+            };
+            if contains(range, &use_range) && !contains(range, &def_range) {
+                uses.push((use_range.start, def.clone(), def_range));
+            }
+        }
+
+        uses.sort_by_key(|(start, ..)| *start);
+        let mut seen = FxHashSet::default();
+        uses.into_iter()
+            .filter(|(_, def, _)| seen.insert(def.clone()))
+            .map(|(_, def, def_range)| (def, def_range))
+            .collect()
+    }
+
+    /// Checks whether the declarations are assigned or mutated by methods in
+    /// the range.
+    fn mutates_free_decls(
+        &mut self,
+        root: &LinkedNode,
+        range: &Range<usize>,
+        decls: &FxHashSet<DeclExpr>,
+    ) -> bool {
+        let ei = self.ctx.expr_stage(&self.source);
+        let mut stack = vec![root.clone()];
+        while let Some(node) = stack.pop() {
+            let node_range = node.range();
+            if node_range.end <= range.start || node_range.start >= range.end {
+                continue;
+            }
+
+            if node.kind() == SyntaxKind::Ident && is_mutated(&node) {
+                let resolved = ei.resolves.get(&node.span());
+                let step = resolved.and_then(|r| r.step.as_ref());
+                if matches!(step, Some(Expr::Decl(def)) if decls.contains(def)) {
+                    return true;
+                }
+            }
+
+            stack.extend(node.children());
+        }
+
+        false
+    }
+
+    /// Gets a name that is not used in this file.
+    fn fresh_name(&mut self, base: &str) -> EcoString {
+        let ei = self.ctx.expr_stage(&self.source);
+        let used = ei
+            .resolves
+            .values()
+            .map(|r| r.decl.name().as_ref())
+            .collect::<FxHashSet<_>>();
+
+        let mut name = EcoString::from(base);
+        let mut idx = 1;
+        while used.contains(name.as_str()) {
+            idx += 1;
+            name = eco_format!("{base}-{idx}");
+        }
+        name
+    }
+
+    /// Gets the start of the line of the statement if the line starts in the
+    /// scope.
+    fn line_start(&self, scope: &LinkedNode, stmt_start: usize) -> Option<usize> {
+        let text = self.source.text();
+        let line_start = text[..stmt_start].rfind('\n').map_or(0, |idx| idx + 1);
+        let indent_end = line_start + leading_indent(text, line_start);
+        (indent_end >= scope.offset() && indent_end <= stmt_start).then_some(line_start)
+    }
+
+    /// Inserts the declaration before the statement, and replaces the range
+    /// with the use of the declaration. The declaration is inserted on its own
+    /// line if the referred declarations are still visible.
+    fn insert_before(
+        &self,
+        stmt: &LinkedNode,
+        decl: &str,
+        in_markup: bool,
+        defs: &[Range<usize>],
+        range: Range<usize>,
+        replace: String,
+    ) -> Option<Vec<TextEdit>> {
+        let text = self.source.text();
+        let scope = stmt.parent()?;
+        let stmt_start = statement_range(stmt).start;
+
+        let line_start = self
+            .line_start(scope, stmt_start)
+            .filter(|line_start| defs.iter().all(|def| def.start < *line_start));
+        let (offset, new_text) = match line_start {
+            Some(line_start) => {
+                let indent = leading_indent(text, line_start);
+                let indent_str = &text[line_start..line_start + indent];
+                (line_start + indent, format!("{decl}\n{indent_str}"))
+            }
+            None if in_markup => (stmt_start, format!("{decl};")),
+            None => (stmt_start, format!("{decl}; ")),
+        };
+
+        if offset == range.start {
+            return Some(vec![TextEdit {
+                range: self.ctx.to_lsp_range(range, &self.source),
+                new_text: new_text + &replace,
+            }]);
+        }
+
+        Some(vec![
+            TextEdit {
+                range: self.ctx.to_lsp_range(offset..offset, &self.source),
+                new_text,
+            },
+            TextEdit {
+                range: self.ctx.to_lsp_range(range, &self.source),
+                new_text: replace,
+            },
+        ])
+    }
+
+    /// Inlines a `let` binding at the cursor or referred at the cursor, which
+    /// replaces all the uses with the bound expression and removes the binding.
+    fn inline_actions(&mut self, root: &LinkedNode, cursor: usize) -> Option<()> {
+        let source = self.source.clone();
+        let text = source.text();
+
+        let leaf = root.leaf_at_compat(cursor)?;
+        let syntax = classify_syntax(leaf, cursor)?;
+        if !matches!(syntax, SyntaxClass::VarAccess(..) | SyntaxClass::Callee(..)) {
+            return None;
+        }
+        let def = self.ctx.def_of_syntax(&source, None, syntax.clone())?;
+        if def.decl.file_id() != Some(source.id()) {
+            return None;
+        }
+        let ident = root.find(def.decl.span())?;
+        let binding = InlineBinding::new(&ident)?;
+        let binding_range = binding_range(&ident)?;
+
+        // All the uses must be in this file, since the binding is removed.
+        let mut uses = vec![];
+        for loc in find_references(self.ctx, &source, None, syntax)? {
+            if Some(&loc.uri) != self.local_url() {
+                return None;
+            }
+            let range = self.ctx.to_typst_range(loc.range, &source)?;
+            if range == ident.range() {
+                continue;
+            }
+            let node = root.leaf_at_compat(range.start + 1)?;
+            // The binding is recursive, mutated, or aliased by the references.
+            if contains(&binding_range, &range)
+                || node.range() != range
+                || node.text() != ident.text()
+                || is_mutated(&node)
+            {
+                return None;
+            }
+            uses.push(node);
+        }
+        if uses.is_empty() {
+            return None;
+        }
+
+        // The declarations referred by the binding must not be shadowed at the
+        // uses.
+        let value_range = binding.value.range();
+        let free_names = self
+            .free_decls(&value_range)
+            .into_iter()
+            .filter(|(_, def_range)| !contains(&binding_range, def_range))
+            .map(|(def, _)| def)
+            .collect::<Vec<_>>();
+        let ei = self.ctx.expr_stage(&source);
+        let shadowed = |at: usize| {
+            ei.resolves.iter().any(|(span, r)| {
+                let Some(range) = source.range(*span) else {
+                    return false;
+                };
+                *span == r.decl.span()
+                    && range.start > binding_range.end
+                    && range.start < at
+                    && free_names
+                        .iter()
+                        .any(|def| def.name() == r.decl.name() && *def != r.decl)
+            })
+        };
+        if uses.iter().any(|node| shadowed(node.offset())) {
+            return None;
+        }
+        // The declarations must not be changed before the uses either.
+        let last_use = uses.iter().map(|node| node.offset()).max()?;
+        let free_decls = free_names.into_iter().collect::<FxHashSet<_>>();
+        if self.mutates_free_decls(root, &(binding_range.end..last_use), &free_decls) {
+            return None;
+        }
+
+        let mut replaces = vec![];
+        for node in &uses {
+            replaces.push(binding.inline_at(self, node)?);
+        }
+        // The calls nested in the arguments of other calls are not inlined.
+        replaces.sort_by_key(|(range, _)| range.start);
+        let overlapped = replaces.windows(2).any(|w| w[0].0.end > w[1].0.start);
+        if overlapped {
+            return None;
+        }
+
+        let mut edits = vec![];
+        for (range, new_text) in replaces {
+            edits.push(TextEdit {
+                range: self.ctx.to_lsp_range(range, &source),
+                new_text,
+            });
+        }
+        let removal = line_range(text, binding_range);
+        edits.push(TextEdit {
+            range: self.ctx.to_lsp_range(removal, &source),
+            new_text: String::new(),
+        });
+
+        let title = match binding.params {
+            Some(_) => format!("Inline function `{}`", ident.text()),
+            None => format!("Inline variable `{}`", ident.text()),
+        };
+        self.refactor(title, CodeActionKind::REFACTOR_INLINE, edits)
+    }
+
     fn heading_actions(&mut self, node: &LinkedNode) -> Option<()> {
         let heading = node.cast::<ast::Heading>()?;
         let depth = heading.depth().get();
@@ -258,3 +766,468 @@ impl<'a> CodeActionWorker<'a> {
         Some(())
     }
 }
+
+/// A `let` binding that can be inlined, which binds either a variable or a
+/// closure with positional parameters only.
+struct InlineBinding<'a> {
+    /// The bound expression, or the body of the closure.
+    value: LinkedNode<'a>,
+    /// The parameters of the closure.
+    params: Option<Vec<LinkedNode<'a>>>,
+    /// The closure bound by the binding.
+    closure: Option<LinkedNode<'a>>,
+}
+
+impl<'a> InlineBinding<'a> {
+    fn new(ident: &LinkedNode<'a>) -> Option<Self> {
+        let parent = ident.parent()?;
+        match parent.kind() {
+            SyntaxKind::LetBinding => {
+                let binding = parent.cast::<ast::LetBinding>()?;
+                let ast::LetBindingKind::Normal(ast::Pattern::Normal(ast::Expr::Ident(name))) =
+                    binding.kind()
+                else {
+                    return None;
+                };
+                if name.span() != ident.span() {
+                    return None;
+                }
+                let value = parent.find(binding.init()?.span())?;
+                Some(Self {
+                    value,
+                    params: None,
+                    closure: None,
+                })
+            }
+            SyntaxKind::Closure if parent.parent_kind() == Some(SyntaxKind::LetBinding) => {
+                let closure = parent.cast::<ast::Closure>()?;
+                let mut params = vec![];
+                for param in closure.params().children() {
+                    let ast::Param::Pos(ast::Pattern::Normal(ast::Expr::Ident(param))) = param
+                    else {
+                        return None;
+                    };
+                    params.push(parent.find(param.span())?);
+                }
+                let value = parent.find(closure.body().span())?;
+                Some(Self {
+                    value,
+                    params: Some(params),
+                    closure: Some(parent.clone()),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the replacement of the use, which is either a reference or a call.
+    fn inline_at<'b>(
+        &self,
+        worker: &mut CodeActionWorker,
+        node: &LinkedNode<'b>,
+    ) -> Option<(Range<usize>, String)> {
+        let source = worker.source.clone();
+        let text = source.text();
+        let (Some(params), Some(closure)) = (&self.params, &self.closure) else {
+            let value = &text[self.value.range()];
+            return Some((node.range(), embed(node, &self.value, value)));
+        };
+
+        let call = node
+            .parent()
+            .and_then(|parent| parent.cast::<ast::FuncCall>().map(|call| (parent, call)))
+            .filter(|(_, call)| call.callee().span() == node.span());
+        let Some((call_node, call)) = call else {
+            // The function is referred as a value.
+            let params = closure
+                .children()
+                .find(|c| c.kind() == SyntaxKind::Params)?;
+            let value = format!("{} => {}", &text[params.range()], &text[self.value.range()]);
+            return Some((node.range(), embed(node, closure, &value)));
+        };
+        // A `return` in the body would leave the function around the call.
+        if has_control_flow(&self.value) {
+            return None;
+        }
+
+        let mut args = vec![];
+        for arg in call.args().items() {
+            let ast::Arg::Pos(arg) = arg else {
+                return None;
+            };
+            args.push(call_node.find(arg.span())?);
+        }
+        if args.len() != params.len() {
+            return None;
+        }
+
+        // The arguments must not be captured by the bindings in the body.
+        let body_range = self.value.range();
+        let ei = worker.ctx.expr_stage(&source);
+        let body_names = ei
+            .resolves
+            .iter()
+            .filter(|(span, r)| **span == r.decl.span())
+            .filter(|(span, _)| {
+                let range = source.range(**span);
+                range.is_some_and(|range| contains(&body_range, &range))
+            })
+            .map(|(_, r)| r.decl.name().clone())
+            .collect::<FxHashSet<_>>();
+        for arg in &args {
+            let captured = worker
+                .free_decls(&arg.range())
+                .iter()
+                .any(|(def, _)| body_names.contains(def.name()));
+            if captured {
+                return None;
+            }
+        }
+
+        // Substitutes the parameters in the body with the arguments.
+        let mut whole = None;
+        let mut substs = vec![];
+        for (span, r) in ei.resolves.iter() {
+            let Some(Expr::Decl(def)) = &r.step else {
+                continue;
+            };
+            let Some(idx) = params.iter().position(|param| param.span() == def.span()) else {
+                continue;
+            };
+            let Some(range) = source.range(*span) else {
+                continue;
+            };
+            if *span != def.span() && contains(&body_range, &range) {
+                let use_node = self.value.find(*span)?;
+                let arg = &args[idx];
+                if range == body_range {
+                    whole = Some(arg);
+                }
+                substs.push((range, embed(&use_node, arg, &text[arg.range()])));
+            }
+        }
+        substs.sort_by_key(|(range, _)| range.start);
+
+        let mut body = String::new();
+        let mut last = body_range.start;
+        for (range, arg) in substs {
+            body.push_str(&text[last..range.start]);
+            body.push_str(&arg);
+            last = range.end;
+        }
+        body.push_str(&text[last..body_range.end]);
+
+        let expr = whole.unwrap_or(&self.value);
+        Some((call_node.range(), embed(call_node, expr, &body)))
+    }
+}
+
+/// Embeds the expression at the place of the node, which is parenthesized if
+/// the expression is not atomic but the place requires it.
+fn embed(node: &LinkedNode, expr: &LinkedNode, text: &str) -> String {
+    let atomic = matches!(
+        expr.kind(),
+        SyntaxKind::Ident
+            | SyntaxKind::None
+            | SyntaxKind::Auto
+            | SyntaxKind::Bool
+            | SyntaxKind::Int
+            | SyntaxKind::Float
+            | SyntaxKind::Numeric
+            | SyntaxKind::Str
+            | SyntaxKind::FuncCall
+            | SyntaxKind::FieldAccess
+            | SyntaxKind::Parenthesized
+            | SyntaxKind::Array
+            | SyntaxKind::Dict
+            | SyntaxKind::CodeBlock
+            | SyntaxKind::ContentBlock
+    );
+    let embedded = node
+        .prev_leaf()
+        .is_some_and(|prev| prev.kind() == SyntaxKind::Hash)
+        && node.prev_leaf().map(|prev| prev.range().end) == Some(node.offset());
+    let in_math = node.kind() == SyntaxKind::MathIdent;
+    let strict = embedded
+        || in_math
+        || matches!(
+            node.parent_kind(),
+            Some(
+                SyntaxKind::Binary
+                    | SyntaxKind::Unary
+                    | SyntaxKind::FieldAccess
+                    | SyntaxKind::FuncCall
+                    | SyntaxKind::Spread
+            )
+        );
+
+    let text = if strict && !atomic {
+        format!("({text})")
+    } else {
+        text.to_owned()
+    };
+    if in_math {
+        format!("#{text}")
+    } else {
+        text
+    }
+}
+
+/// Extends the range of a statement to its whole line if it is the only
+/// statement on the line.
+fn line_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let line_start = text[..range.start].rfind('\n').map_or(0, |idx| idx + 1);
+    let rest = &text[range.end..];
+    let line_end = rest
+        .find('\n')
+        .map_or(text.len(), |idx| range.end + idx + 1);
+    let before = &text[line_start..range.start];
+    let after = &text[range.end..line_end];
+    if before.trim().is_empty()
+        && after
+            .trim_matches(|c: char| c.is_whitespace() || c == ';')
+            .is_empty()
+    {
+        line_start..line_end
+    } else {
+        range
+    }
+}
+
+/// Gets the range of a `let` statement that binds the identifier, if the
+/// statement binds nothing else.
+fn binding_range(ident: &LinkedNode) -> Option<Range<usize>> {
+    let parent = ident.parent()?;
+    let binding = match parent.kind() {
+        SyntaxKind::LetBinding => parent,
+        SyntaxKind::Closure if parent.parent_kind() == Some(SyntaxKind::LetBinding) => {
+            parent.parent()?
+        }
+        _ => return None,
+    };
+
+    Some(statement_range(binding))
+}
+
+/// Gets the range of an item in an import list, including its separator.
+fn import_item_range(ident: &LinkedNode) -> Option<Range<usize>> {
+    let mut item = ident.clone();
+    while item.parent_kind()? != SyntaxKind::ImportItems {
+        item = item.parent()?.clone();
+    }
+
+    if let Some(range) = separated_item_range(&item) {
+        return Some(range);
+    }
+
+    // The only item is removed along with the import.
+    let import = item.parent()?.parent()?;
+    (import.kind() == SyntaxKind::ModuleImport).then(|| statement_range(import))
+}
+
+/// Gets the range of an item in a comma-separated list, including its
+/// separator, if the list has other items.
+fn separated_item_range(item: &LinkedNode) -> Option<Range<usize>> {
+    let is_comma = |node: &LinkedNode| node.kind() == SyntaxKind::Comma;
+    if let Some(comma) = item.next_sibling().filter(is_comma) {
+        let end = comma
+            .next_sibling()
+            .map_or(comma.range().end, |next| next.offset());
+        return Some(item.offset()..end);
+    }
+    if let Some(comma) = item.prev_sibling().filter(is_comma) {
+        let start = comma
+            .prev_sibling()
+            .map_or(comma.offset(), |prev| prev.range().end);
+        return Some(start..item.range().end);
+    }
+
+    None
+}
+
+/// Gets the range of a statement, including the leading hash in markup.
+fn statement_range(node: &LinkedNode) -> Range<usize> {
+    let start = match node.prev_leaf() {
+        Some(hash) if hash.kind() == SyntaxKind::Hash => hash.offset(),
+        _ => node.offset(),
+    };
+    start..node.range().end
+}
+
+/// Trims the whitespace around the range.
+fn trim_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let Some(selected) = text.get(range.clone()) else {
+        return range.start..range.start;
+    };
+    let start = range.start + (selected.len() - selected.trim_start().len());
+    let end = range.end - (selected.len() - selected.trim_end().len());
+    start..end.max(start)
+}
+
+/// Checks whether the range covers the other range.
+fn contains(range: &Range<usize>, other: &Range<usize>) -> bool {
+    range.start <= other.start && other.end <= range.end
+}
+
+/// Gets the length of the indentation at the start of the line.
+fn leading_indent(text: &str, line_start: usize) -> usize {
+    let line = &text[line_start..];
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// Gets the separator after an embedded expression in markup, which is
+/// needed if the following text would continue the expression.
+fn continuation(text: &str, end: usize) -> &'static str {
+    match text[end..].chars().next() {
+        Some(c) if c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '(' | '[') => ";",
+        _ => "",
+    }
+}
+
+/// Gets the children of the node that are in the range, if the range covers
+/// all of them.
+fn covered_children<'a>(
+    node: &LinkedNode<'a>,
+    range: &Range<usize>,
+) -> Option<Vec<LinkedNode<'a>>> {
+    let children = node
+        .children()
+        .filter(|child| child.range().end > range.start && child.offset() < range.end)
+        .collect::<Vec<_>>();
+    let covered = children.iter().all(|child| contains(range, &child.range()));
+    (covered && !children.is_empty()).then_some(children)
+}
+
+/// Gets the nearest markup or code that contains the node.
+fn scope_of<'a>(node: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    let mut scope = node.parent()?.clone();
+    while !matches!(scope.kind(), SyntaxKind::Markup | SyntaxKind::Code) {
+        scope = scope.parent()?.clone();
+    }
+    Some(scope)
+}
+
+/// Gets the child of the scope that contains the node.
+fn child_of<'a>(scope: &LinkedNode, node: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    let mut child = node.clone();
+    while child.parent()?.span() != scope.span() {
+        child = child.parent()?.clone();
+    }
+    Some(child)
+}
+
+/// Checks whether the node is an expression evaluated in code, which can be
+/// extracted to a variable.
+fn is_code_expr(node: &LinkedNode) -> bool {
+    use SyntaxKind::*;
+
+    if node.cast::<ast::Expr>().is_none() {
+        return false;
+    }
+    match node.kind() {
+        LetBinding | SetRule | ShowRule | ModuleImport | ModuleInclude | LoopBreak
+        | LoopContinue | FuncReturn | DestructAssignment => return false,
+        Ident if is_decl_ident(node) => return false,
+        _ => {}
+    }
+    if node.prev_leaf().is_some_and(|prev| prev.kind() == Hash) {
+        return true;
+    }
+
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    !matches!(parent.kind(), Markup | Math)
+        && interpret_mode_at(Some(parent)) == InterpretMode::Code
+}
+
+/// Checks whether the identifier declares a name or is a key, rather than
+/// referring to a value.
+fn is_decl_ident(node: &LinkedNode) -> bool {
+    use SyntaxKind::*;
+
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let before = |kind: SyntaxKind| {
+        let mut prev = node.prev_sibling();
+        while let Some(sibling) = prev {
+            if sibling.kind() == kind {
+                return false;
+            }
+            prev = sibling.prev_sibling();
+        }
+        true
+    };
+    match parent.kind() {
+        Params | Destructuring | ImportItems | ImportItemPath | RenamedImportItem => true,
+        Spread => matches!(parent.parent_kind(), Some(Params | Destructuring)),
+        LetBinding => before(Eq),
+        ForLoop => before(In),
+        Closure => node.next_sibling_kind() == Some(Params),
+        Named => node.next_sibling_kind() == Some(Colon),
+        FieldAccess => node.prev_sibling_kind() == Some(Dot),
+        _ => false,
+    }
+}
+
+/// Checks whether the node leaves the statements by `return`, `break` or
+/// `continue`, which cannot be extracted to a function.
+fn has_control_flow(node: &LinkedNode) -> bool {
+    fn check(node: &LinkedNode, in_loop: bool) -> bool {
+        match node.kind() {
+            SyntaxKind::Closure => false,
+            SyntaxKind::FuncReturn => true,
+            SyntaxKind::LoopBreak | SyntaxKind::LoopContinue => !in_loop,
+            SyntaxKind::ForLoop | SyntaxKind::WhileLoop => {
+                node.children().any(|child| check(&child, true))
+            }
+            _ => node.children().any(|child| check(&child, in_loop)),
+        }
+    }
+
+    check(node, false)
+}
+
+/// Checks whether the declaration is in the top-level scope of the file.
+fn is_module_level(def: &LinkedNode) -> bool {
+    let mut node = def.clone();
+    while let Some(parent) = node.parent() {
+        match parent.kind() {
+            // The name of a closure is declared outside of the closure.
+            SyntaxKind::Closure if node.kind() == SyntaxKind::Ident => {}
+            SyntaxKind::Markup => return parent.parent().is_none(),
+            SyntaxKind::Code | SyntaxKind::Closure | SyntaxKind::ForLoop => return false,
+            _ => {}
+        }
+        node = parent.clone();
+    }
+
+    false
+}
+
+/// Checks whether the identifier is assigned or mutated by a method.
+fn is_mutated(ident: &LinkedNode) -> bool {
+    const MUTATING_METHODS: &[&str] = &["push", "pop", "insert", "remove"];
+
+    let Some(parent) = ident.parent() else {
+        return false;
+    };
+    if let Some(binary) = parent.cast::<ast::Binary>() {
+        return binary.lhs().span() == ident.span()
+            && matches!(
+                binary.op(),
+                ast::BinOp::Assign
+                    | ast::BinOp::AddAssign
+                    | ast::BinOp::SubAssign
+                    | ast::BinOp::MulAssign
+                    | ast::BinOp::DivAssign
+            );
+    }
+    if let Some(access) = parent.cast::<ast::FieldAccess>() {
+        return access.target().span() == ident.span()
+            && MUTATING_METHODS.contains(&access.field().as_str());
+    }
+
+    false
+}
@@ -0,0 +1,275 @@
+//! Provides quick fixes for the known errors in the diagnostics.
+
+use lsp_types::{CreateFile, DocumentChangeOperation, DocumentChanges, ResourceOp};
+use reflexo_typst::EntryReader;
+
+use super::*;
+use crate::analysis::{
+    analyze_signature, importable_modules, new_import, path_label, SignatureTarget,
+};
+use crate::syntax::Decl;
+use crate::ty::PathPreference;
+use crate::KnownError;
+
+/// The maximum number of the candidates offered for a fix.
+const MAX_CANDIDATES: usize = 3;
+
+impl CodeActionWorker<'_> {
+    /// Provides quick fixes for the known errors in the diagnostics.
+    pub fn diagnostic_actions(&mut self, root: &LinkedNode, diagnostics: &[Diagnostic]) {
+        for diag in diagnostics {
+            let known = diag.data.clone().map(serde_json::from_value::<KnownError>);
+            let Some(Ok(known)) = known else {
+                continue;
+            };
+            let Some(range) = self.ctx.to_typst_range(diag.range, &self.source) else {
+                continue;
+            };
+
+            match known {
+                KnownError::UnknownVariable { name } => self.import_fixes(root, diag, &name),
+                KnownError::FileNotFound { path } => self.path_fixes(root, diag, range, &path),
+                KnownError::UnexpectedArgument { name } => {
+                    self.argument_fixes(root, diag, range, &name)
+                }
+                KnownError::LabelNotFound { label } => self.label_fixes(root, diag, range, &label),
+            };
+        }
+    }
+
+    fn fix(&mut self, title: String, diag: &Diagnostic, edit: WorkspaceEdit) {
+        let action = CodeActionOrCommand::CodeAction(CodeAction {
+            title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diag.clone()]),
+            edit: Some(edit),
+            ..CodeAction::default()
+        });
+        self.actions.push(action);
+    }
+
+    fn fix_edit(&mut self, title: String, diag: &Diagnostic, range: Range<usize>, new_text: &str) {
+        let edit = self.local_edit(TextEdit {
+            range: self.ctx.to_lsp_range(range, &self.source),
+            new_text: new_text.to_owned(),
+        });
+        if let Some(edit) = edit {
+            self.fix(title, diag, edit);
+        }
+    }
+
+    /// Imports the variable from the workspace files or the packages exporting
+    /// it.
+    fn import_fixes(&mut self, root: &LinkedNode, diag: &Diagnostic, name: &str) -> Option<()> {
+        for module in importable_modules(self.ctx, self.source.id()) {
+            if !module.exports(name) {
+                continue;
+            }
+
+            let path = &module.path;
+            let (offset, new_text) = new_import(root, path, name);
+            let title = format!("Import `{name}` from \"{path}\"");
+            self.fix_edit(title, diag, offset..offset, &new_text);
+        }
+
+        Some(())
+    }
+
+    /// Creates the missing file, or replaces the path with a similar path to
+    /// an existing file.
+    fn path_fixes(
+        &mut self,
+        root: &LinkedNode,
+        diag: &Diagnostic,
+        range: Range<usize>,
+        path: &Path,
+    ) -> Option<()> {
+        let node = covering_node(root, range)?;
+        let str_node = find_leaf(&node, |leaf| leaf.kind() == SyntaxKind::Str)?;
+        let written = str_node.cast::<ast::Str>()?.get();
+
+        let in_workspace = self
+            .ctx
+            .world
+            .workspace_root()
+            .is_some_and(|root| path.starts_with(root));
+        if in_workspace {
+            if let Ok(uri) = path_to_url(path) {
+                let title = format!("Create file \"{written}\"");
+                let create = ResourceOp::Create(CreateFile {
+                    uri,
+                    options: None,
+                    annotation_id: None,
+                });
+                let edit = WorkspaceEdit {
+                    document_changes: Some(DocumentChanges::Operations(vec![
+                        DocumentChangeOperation::Op(create),
+                    ])),
+                    ..Default::default()
+                };
+                self.fix(title, diag, edit);
+            }
+        }
+
+        // The candidates are the paths completed in the string.
+        let id = self.source.id();
+        let has_root = written.starts_with('/');
+        let file_name = Path::new(written.as_str()).file_name();
+        let pref = PathPreference::from_ext(&written).unwrap_or(PathPreference::Special);
+        let files = self
+            .ctx
+            .completion_files(&pref)
+            .copied()
+            .collect::<Vec<_>>();
+        let candidates = files
+            .into_iter()
+            .filter(|fid| *fid != id)
+            .filter_map(|fid| {
+                let label = path_label(id, fid, has_root)?;
+                let moved = Path::new(label.as_str()).file_name() == file_name;
+                let dist = edit_distance(&label, &written);
+                (moved || is_similar(dist, &written)).then_some((dist, label))
+            });
+
+        for label in closest(candidates) {
+            let title = format!("Change path to \"{label}\"");
+            let new_text = format!("\"{label}\"");
+            self.fix_edit(title, diag, str_node.range(), &new_text);
+        }
+
+        Some(())
+    }
+
+    /// Removes the unexpected argument, or renames it to a similar parameter.
+    fn argument_fixes(
+        &mut self,
+        root: &LinkedNode,
+        diag: &Diagnostic,
+        range: Range<usize>,
+        name: &str,
+    ) -> Option<()> {
+        let mut named = root.leaf_at_compat(range.start + 1)?;
+        while named.kind() != SyntaxKind::Named {
+            named = named.parent()?.clone();
+        }
+        let removal = separated_item_range(&named).unwrap_or_else(|| named.range());
+        self.fix_edit(format!("Remove argument `{name}`"), diag, removal, "");
+
+        let call = named.parent()?.parent()?.cast::<ast::FuncCall>()?;
+        let key = named.cast::<ast::Named>()?.name();
+
+        let target = SignatureTarget::SyntaxFast(self.source.clone(), call.callee().span());
+        let sig = analyze_signature(self.ctx.shared(), target)?;
+        let candidates = sig.primary().named().iter().filter_map(|param| {
+            let dist = edit_distance(&param.name, name);
+            is_similar(dist, name).then(|| (dist, EcoString::from(param.name.as_ref())))
+        });
+        let key_range = named.find(key.span())?.range();
+        for param in closest(candidates) {
+            let title = format!("Rename argument to `{param}`");
+            self.fix_edit(title, diag, key_range.clone(), &param);
+        }
+
+        Some(())
+    }
+
+    /// Replaces the reference with a similar label in the workspace.
+    fn label_fixes(
+        &mut self,
+        root: &LinkedNode,
+        diag: &Diagnostic,
+        range: Range<usize>,
+        label: &str,
+    ) -> Option<()> {
+        let node = covering_node(root, range)?;
+        let marker = eco_format!("@{label}");
+        let written = eco_format!("<{label}>");
+        let leaf = find_leaf(&node, |leaf| match leaf.kind() {
+            SyntaxKind::RefMarker => leaf.text() == &marker,
+            SyntaxKind::Label => leaf.text() == &written,
+            _ => false,
+        })?;
+
+        let mut labels = FxHashSet::default();
+        for fid in self.ctx.source_files().clone() {
+            let Ok(source) = self.ctx.source_by_id(fid) else {
+                continue;
+            };
+            let ei = self.ctx.expr_stage(&source);
+            for (span, r) in ei.resolves.iter() {
+                if *span == r.decl.span() && matches!(*r.decl, Decl::Label(..)) {
+                    labels.insert(EcoString::from(r.decl.name().as_ref()));
+                }
+            }
+        }
+
+        let candidates = labels.into_iter().filter_map(|candidate| {
+            let dist = edit_distance(&candidate, label);
+            is_similar(dist, label).then_some((dist, candidate))
+        });
+        for candidate in closest(candidates) {
+            let new_text = match leaf.kind() {
+                SyntaxKind::RefMarker => format!("@{candidate}"),
+                _ => format!("<{candidate}>"),
+            };
+            let title = format!("Change label to `{candidate}`");
+            self.fix_edit(title, diag, leaf.range(), &new_text);
+        }
+
+        Some(())
+    }
+}
+
+/// Gets the smallest node covering the range.
+fn covering_node<'a>(root: &LinkedNode<'a>, range: Range<usize>) -> Option<LinkedNode<'a>> {
+    let mut node = root.leaf_at_compat(range.start + 1)?;
+    while node.offset() > range.start || node.range().end < range.end {
+        node = node.parent()?.clone();
+    }
+    Some(node)
+}
+
+/// Finds the first leaf in the node matching the predicate.
+fn find_leaf<'a>(
+    node: &LinkedNode<'a>,
+    pred: impl Fn(&LinkedNode<'a>) -> bool + Copy,
+) -> Option<LinkedNode<'a>> {
+    if pred(node) {
+        return Some(node.clone());
+    }
+    node.children().find_map(|child| find_leaf(&child, pred))
+}
+
+/// Gets the closest candidates by the distance.
+fn closest(candidates: impl Iterator<Item = (usize, EcoString)>) -> Vec<EcoString> {
+    let mut candidates = candidates.collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+    let candidates = candidates.into_iter().map(|(_, candidate)| candidate);
+    candidates.take(MAX_CANDIDATES).collect()
+}
+
+/// Checks whether a name is similar to the written one by the distance.
+fn is_similar(dist: usize, written: &str) -> bool {
+    dist > 0 && dist <= (written.chars().count() / 3).max(2)
+}
+
+/// Computes the Levenshtein distance between the two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                prev.min(cur).min(row[j]) + 1
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
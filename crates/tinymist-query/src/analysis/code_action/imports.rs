@@ -0,0 +1,243 @@
+//! Provides code actions organizing the imports of the document.
+
+use super::*;
+use crate::syntax::Decl;
+
+/// An import statement at the top level with a list of items.
+struct ItemImport<'a> {
+    /// The import statement.
+    node: LinkedNode<'a>,
+    /// The list of items.
+    items: LinkedNode<'a>,
+    /// The imported module and its alias, normalized for comparison.
+    key: String,
+}
+
+/// The imports from the same module, which are merged into the first one.
+#[derive(Default)]
+struct ImportGroup<'a> {
+    imports: Vec<ItemImport<'a>>,
+    /// The names bound at the top level after the first import. Merging an
+    /// import of them would move it above the bindings and change its meaning.
+    bound_after: FxHashSet<EcoString>,
+    /// Whether some unknown names are bound after the first import, e.g. by a
+    /// wildcard import.
+    closed: bool,
+}
+
+impl ImportGroup<'_> {
+    fn accepts(&self, import: &ItemImport) -> bool {
+        self.imports[0].key == import.key
+            && !self.closed
+            && !bound_names(&import.node)
+                .is_some_and(|names| names.iter().any(|name| self.bound_after.contains(name)))
+    }
+}
+
+impl CodeActionWorker<'_> {
+    /// Sorts and merges the items imported from the same module by the
+    /// imports at the top level, and removes the unused ones.
+    pub(super) fn organize_imports(&mut self, root: &LinkedNode) -> Option<()> {
+        let source = self.source.clone();
+        let text = source.text();
+
+        let unused = unused_decls(self.ctx, &source)
+            .into_iter()
+            .filter(|unused| unused.kind == UnusedKind::Import)
+            .map(|unused| unused.decl.span())
+            .collect::<FxHashSet<_>>();
+
+        let mut groups: Vec<ImportGroup> = vec![];
+        for node in root.children() {
+            let names = bound_names(&node);
+            let joined = match ItemImport::new(text, node) {
+                Some(import) => {
+                    let idx = match groups.iter().position(|group| group.accepts(&import)) {
+                        Some(idx) => idx,
+                        None => {
+                            groups.push(ImportGroup::default());
+                            groups.len() - 1
+                        }
+                    };
+                    groups[idx].imports.push(import);
+                    Some(idx)
+                }
+                None => None,
+            };
+
+            for (idx, group) in groups.iter_mut().enumerate() {
+                if Some(idx) == joined {
+                    continue;
+                }
+                match &names {
+                    Some(names) => group.bound_after.extend(names.iter().cloned()),
+                    None => group.closed = true,
+                }
+            }
+        }
+
+        let mut edits = vec![];
+        for ImportGroup { imports: group, .. } in groups {
+            let mut items = group
+                .iter()
+                .flat_map(|import| import.items.children())
+                .filter(|item| {
+                    matches!(
+                        item.kind(),
+                        SyntaxKind::ImportItemPath | SyntaxKind::RenamedImportItem
+                    )
+                })
+                .filter(|item| {
+                    let bound = item.children().filter(|c| c.kind() == SyntaxKind::Ident);
+                    bound
+                        .last()
+                        .map_or(true, |ident| !unused.contains(&ident.span()))
+                })
+                .map(|item| &text[item.range()])
+                .collect::<Vec<_>>();
+            items.sort();
+            items.dedup();
+
+            let (first, rest) = group.split_first()?;
+            if items.is_empty() {
+                let range = line_range(text, statement_range(&first.node));
+                edits.push(self.text_edit(range, String::new()));
+            } else {
+                let joined = items.join(", ");
+                if text[first.items.range()] != joined {
+                    edits.push(self.text_edit(first.items.range(), joined));
+                }
+            }
+            for import in rest {
+                let range = line_range(text, statement_range(&import.node));
+                edits.push(self.text_edit(range, String::new()));
+            }
+        }
+
+        if edits.is_empty() {
+            return None;
+        }
+
+        let action = CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Organize imports".to_string(),
+            kind: Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+            edit: Some(self.local_edits(edits)?),
+            ..CodeAction::default()
+        });
+        self.actions.push(action);
+
+        Some(())
+    }
+
+    /// Expands the wildcard import at the cursor into the list of the items
+    /// used by the document.
+    pub(super) fn expand_wildcard(&mut self, root: &LinkedNode, cursor: usize) -> Option<()> {
+        let mut node = root.leaf_at_compat(cursor)?;
+        while node.kind() != SyntaxKind::ModuleImport {
+            node = node.parent()?.clone();
+        }
+        let import = node.cast::<ast::ModuleImport>()?;
+        if !matches!(import.imports(), Some(ast::Imports::Wildcard)) {
+            return None;
+        }
+        let star = node.children().find(|c| c.kind() == SyntaxKind::Star)?;
+
+        let ei = self.ctx.expr_stage(&self.source);
+        let module = ei.resolves.get(&import.source().span())?.step.clone();
+        let fid = match module? {
+            Expr::Decl(decl) if matches!(decl.as_ref(), Decl::Module(..)) => decl.file_id()?,
+            _ => return None,
+        };
+        let module = self.ctx.source_by_id(fid).ok()?;
+        let exports = self.ctx.expr_stage(&module).exports.clone();
+
+        // The names are used if they are resolved to the exported items.
+        let mut names = ei
+            .resolves
+            .values()
+            .filter(|r| matches!(r.decl.as_ref(), Decl::IdentRef(..)))
+            .filter_map(|r| {
+                let name = r.decl.name();
+                let step = match exports.get(name)? {
+                    Expr::Ref(export) => Expr::Decl(export.decl.clone()),
+                    export => export.clone(),
+                };
+                (r.step.as_ref() == Some(&step)).then(|| name.to_string())
+            })
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            return None;
+        }
+        names.sort();
+        names.dedup();
+
+        let edit = self.text_edit(star.range(), names.join(", "));
+        let action = CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Expand wildcard import".to_string(),
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            edit: Some(self.local_edit(edit)?),
+            ..CodeAction::default()
+        });
+        self.actions.push(action);
+
+        Some(())
+    }
+
+    fn text_edit(&self, range: Range<usize>, new_text: String) -> TextEdit {
+        TextEdit {
+            range: self.ctx.to_lsp_range(range, &self.source),
+            new_text,
+        }
+    }
+}
+
+/// Get the names bound by a statement at the top level, or `None` if they are
+/// unknown, e.g. by a wildcard import.
+fn bound_names(node: &LinkedNode) -> Option<Vec<EcoString>> {
+    let names = match node.cast::<ast::Expr>() {
+        Some(ast::Expr::Let(binding)) => binding.kind().bindings(),
+        Some(ast::Expr::Import(import)) => {
+            let mut names = vec![];
+            match import.imports() {
+                Some(ast::Imports::Wildcard) => return None,
+                Some(ast::Imports::Items(items)) => {
+                    names.extend(items.iter().map(|item| item.bound_name()));
+                }
+                None => {}
+            }
+            match import.new_name() {
+                Some(alias) => names.push(alias),
+                // The name of the module is derived from its path.
+                None if import.imports().is_none() => return None,
+                None => {}
+            }
+            names
+        }
+        _ => vec![],
+    };
+
+    Some(names.into_iter().map(|name| name.get().clone()).collect())
+}
+
+impl<'a> ItemImport<'a> {
+    fn new(text: &str, node: LinkedNode<'a>) -> Option<Self> {
+        if node.kind() != SyntaxKind::ModuleImport {
+            return None;
+        }
+        let items = node
+            .children()
+            .find(|c| c.kind() == SyntaxKind::ImportItems)?;
+        // The comments in the list would be lost by rewriting it.
+        let has_comment = items
+            .children()
+            .any(|c| matches!(c.kind(), SyntaxKind::LineComment | SyntaxKind::BlockComment));
+        if has_comment {
+            return None;
+        }
+
+        let colon = node.children().find(|c| c.kind() == SyntaxKind::Colon)?;
+        let head = &text[node.offset()..colon.offset()];
+        let key = head.split_whitespace().collect::<Vec<_>>().join(" ");
+        Some(Self { node, items, key })
+    }
+}
@@ -28,7 +28,8 @@ use unscanny::Scanner;
 
 use crate::adt::interner::Interned;
 use crate::analysis::{
-    analyze_labels, func_signature, BuiltinTy, DynLabel, LocalContext, PathPreference, Ty,
+    analyze_labels, func_signature, importable_modules, new_import, BuiltinTy, DynLabel,
+    LocalContext, PathPreference, Ty,
 };
 use crate::completion::{
     Completion, CompletionCommand, CompletionContextKey, CompletionItem, CompletionKind,
@@ -38,8 +39,8 @@ use crate::completion::{
 use crate::prelude::*;
 use crate::syntax::{
     classify_context, interpret_mode_at, is_ident_like, node_ancestors, previous_decls,
-    surrounding_syntax, InterpretMode, PreviousDecl, SurroundingSyntax, SyntaxClass, SyntaxContext,
-    VarClass,
+    surrounding_syntax, Expr, InterpretMode, PreviousDecl, SurroundingSyntax, SyntaxClass,
+    SyntaxContext, VarClass,
 };
 use crate::ty::{
     DynTypeBounds, Iface, IfaceChecker, InsTy, SigTy, TyCtx, TypeInfo, TypeInterface, TypeVar,
@@ -59,6 +60,7 @@ mod snippet;
 mod type_;
 mod typst_specific;
 use kind::*;
+pub(crate) use path::path_label;
 use scope::*;
 use type_::*;
 
@@ -131,4 +131,48 @@ impl CompletionPair<'_, '_, '_> {
             }
         }
     }
+
+    /// Add completions for the names exported by other modules in the
+    /// workspace or the packages, which are imported on accepted.
+    pub fn auto_import_completions(&mut self, in_scope: &HashSet<EcoString>) {
+        if !matches!(self.cursor.surrounding_syntax, SurroundingSyntax::Regular) {
+            return;
+        }
+        // Searching the modules is expensive, so a typed prefix is required.
+        let Some(ident) = self.cursor.ident_cursor().clone() else {
+            return;
+        };
+        let prefix = &self.cursor.text[ident.offset()..self.cursor.cursor];
+        if prefix.len() < 2 {
+            return;
+        }
+        let prefix = prefix.to_owned();
+
+        let source = self.cursor.source.clone();
+        let root = LinkedNode::new(source.root());
+        let modules = importable_modules(self.worker.ctx, source.id());
+        for module in modules {
+            for (name, expr) in module.info.exports.iter() {
+                if !name.starts_with(prefix.as_str()) || in_scope.contains(name.as_ref()) {
+                    continue;
+                }
+
+                let kind = match expr {
+                    Expr::Decl(decl) if decl.kind() == DefKind::Function => CompletionKind::Func,
+                    _ => CompletionKind::Variable,
+                };
+                let (offset, new_text) = new_import(&root, &module.path, name);
+                let import =
+                    EcoTextEdit::new(self.cursor.lsp_range_of(offset..offset), new_text.into());
+                self.push_completion(Completion {
+                    kind,
+                    label: name.as_ref().into(),
+                    label_details: Some(eco_format!("import from \"{}\"", module.path)),
+                    detail: Some(eco_format!("Imports `{name}` from \"{}\".", module.path)),
+                    additional_text_edits: Some(vec![import]),
+                    ..Completion::default()
+                });
+            }
+        }
+    }
 }
@@ -66,19 +66,7 @@ impl CompletionPair<'_, '_, '_> {
                 continue;
             }
 
-            let label: EcoString = if has_root {
-                // diff with root
-                unix_slash(path.vpath().as_rooted_path()).into()
-            } else {
-                let base = base
-                    .vpath()
-                    .as_rooted_path()
-                    .parent()
-                    .unwrap_or(Path::new("/"));
-                let path = path.vpath().as_rooted_path();
-                let w = pathdiff::diff_paths(path, base)?;
-                unix_slash(&w).into()
-            };
+            let label = path_label(base, *path, has_root)?;
             crate::log_debug_ct!("compl_label: {label:?}");
 
             module_completions.push((label, CompletionKind::File));
@@ -149,3 +137,25 @@ impl CompletionPair<'_, '_, '_> {
         )
     }
 }
+
+/// Gets the path string referring to the file in the base file, which is
+/// relative to the root if `has_root` or to the base file otherwise.
+pub(crate) fn path_label(
+    base: TypstFileId,
+    path: TypstFileId,
+    has_root: bool,
+) -> Option<EcoString> {
+    if has_root {
+        // diff with root
+        return Some(unix_slash(path.vpath().as_rooted_path()).into());
+    }
+
+    let base = base
+        .vpath()
+        .as_rooted_path()
+        .parent()
+        .unwrap_or(Path::new("/"));
+    let path = path.vpath().as_rooted_path();
+    let w = pathdiff::diff_paths(path, base)?;
+    Some(unix_slash(&w).into())
+}
@@ -43,7 +43,9 @@ impl CompletionPair<'_, '_, '_> {
             return;
         };
 
+        let in_scope = defines.defines.keys().cloned().collect();
         self.def_completions(defines, parens);
+        self.auto_import_completions(&in_scope);
     }
 
     pub fn scope_defs(&mut self) -> Option<Defines> {
@@ -26,9 +26,9 @@ use crate::adt::revision::{RevisionLock, RevisionManager, RevisionManagerLike, R
 use crate::analysis::prelude::*;
 use crate::analysis::{
     analyze_bib, analyze_expr_, analyze_import_, analyze_signature, definition, post_type_check,
-    AllocStats, AnalysisStats, BibInfo, CompletionFeat, Definition, PathPreference, QueryStatGuard,
-    SemanticTokenCache, SemanticTokenContext, SemanticTokens, Signature, SignatureTarget, Ty,
-    TypeInfo,
+    AllocStats, AnalysisStats, BibInfo, CompletionFeat, Definition, ExportIndex, PathPreference,
+    QueryStatGuard, SemanticTokenCache, SemanticTokenContext, SemanticTokens, Signature,
+    SignatureTarget, Ty, TypeInfo,
 };
 use crate::docs::{DefDocs, TidyModuleDocs};
 use crate::syntax::{
@@ -37,8 +37,8 @@ use crate::syntax::{
 };
 use crate::upstream::{tooltip_, Tooltip};
 use crate::{
-    ColorTheme, CompilerQueryRequest, LspPosition, LspRange, LspWorldExt, PositionEncoding,
-    VersionedDocument,
+    ColorTheme, CompilerQueryRequest, DiagnosticsKey, DiagnosticsMap, LspPosition, LspRange,
+    LspWorldExt, PositionEncoding, VersionedDocument,
 };
 
 use super::TypeEnv;
@@ -357,6 +357,40 @@ impl LocalContext {
         }
     }
 
+    /// Get the index of the names exported by the modules in the workspace and
+    /// the packages.
+    pub(crate) fn export_index(&mut self) -> Arc<ExportIndex> {
+        if let Some(index) = self.slot.export_index.get() {
+            index.clone()
+        } else {
+            // may be built multiple times concurrently, but all of them are built in the
+            // same revision.
+            let index = Arc::new(ExportIndex::build(self));
+            self.slot.export_index.get_or_init(|| index).clone()
+        }
+    }
+
+    /// Get the diagnostics cached in the revision by the key, or compute them.
+    pub(crate) fn cached_diagnostics(
+        &mut self,
+        key: DiagnosticsKey,
+        compute: impl FnOnce(&mut Self) -> DiagnosticsMap,
+    ) -> Arc<DiagnosticsMap> {
+        if let Some(diagnostics) = self.slot.diagnostics.get(&key) {
+            return diagnostics.value().clone();
+        }
+
+        // may be computed multiple times concurrently, but all of them are computed in the
+        // same revision.
+        let diagnostics = Arc::new(compute(self));
+        self.slot
+            .diagnostics
+            .entry(key)
+            .or_insert(diagnostics)
+            .value()
+            .clone()
+    }
+
     /// Get the world surface for Typst compiler.
     pub fn world(&self) -> &LspWorld {
         &self.shared.world
@@ -1209,6 +1243,8 @@ impl AnalysisRevCache {
                     revision: slot.revision,
                     expr_stage: slot.data.expr_stage.crawl(revision.get()),
                     type_check: slot.data.type_check.crawl(revision.get()),
+                    export_index: OnceCell::new(),
+                    diagnostics: FxDashMap::default(),
                 })
                 .unwrap_or_else(|| self.default_slot.clone())
         })
@@ -1241,6 +1277,8 @@ struct AnalysisRevSlot {
     revision: usize,
     expr_stage: IncrCacheMap<u128, Arc<ExprInfo>>,
     type_check: IncrCacheMap<u128, Arc<TypeInfo>>,
+    export_index: OnceCell<Arc<ExportIndex>>,
+    diagnostics: FxDashMap<DiagnosticsKey, Arc<DiagnosticsMap>>,
 }
 
 impl Drop for AnalysisRevSlot {
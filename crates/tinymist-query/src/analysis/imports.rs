@@ -0,0 +1,143 @@
+//! Finds the modules that can be imported by a file.
+
+use std::cmp::Reverse;
+
+use typst::syntax::package::PackageSpec;
+use typst::syntax::VirtualPath;
+
+use super::path_label;
+use crate::adt::interner::Interned;
+use crate::package::list_package_by_namespace;
+use crate::prelude::*;
+use crate::syntax::ExprInfo;
+
+/// A module in the workspace or in a package, which can be imported by a
+/// file.
+pub(crate) struct ImportableModule {
+    /// The path to import the module, e.g. `utils.typ` or `@local/pkg:0.1.0`.
+    pub path: EcoString,
+    /// The expression information of the module.
+    pub info: Arc<ExprInfo>,
+}
+
+impl ImportableModule {
+    /// Whether the module exports the name.
+    pub fn exports(&self, name: &str) -> bool {
+        self.info.exports.get(&Interned::new_str(name)).is_some()
+    }
+}
+
+/// The modules in the workspace and the newest versions of the local and the
+/// downloaded preview packages, which is built once per revision.
+pub(crate) struct ExportIndex {
+    /// The source files in the workspace.
+    files: Vec<(TypstFileId, Arc<ExprInfo>)>,
+    /// The entries of the packages.
+    packages: Vec<(PackageSpec, Arc<ExprInfo>)>,
+}
+
+impl ExportIndex {
+    /// Builds the index by analyzing the modules.
+    pub(crate) fn build(ctx: &mut LocalContext) -> Self {
+        let mut files = vec![];
+        for fid in ctx.source_files().clone() {
+            let Ok(source) = ctx.source_by_id(fid) else {
+                continue;
+            };
+            files.push((fid, ctx.expr_stage(&source)));
+        }
+
+        let mut packages = vec![];
+        for spec in newest_packages(ctx) {
+            let toml_id = TypstFileId::new(Some(spec.clone()), VirtualPath::new("typst.toml"));
+            let Ok(manifest) = ctx.get_manifest(toml_id) else {
+                continue;
+            };
+            let entry = toml_id.join(&manifest.package.entrypoint);
+            let Ok(source) = ctx.source_by_id(entry) else {
+                continue;
+            };
+            packages.push((spec, ctx.expr_stage(&source)));
+        }
+
+        Self { files, packages }
+    }
+}
+
+/// Gets the newest version of each local or downloaded preview package.
+fn newest_packages(ctx: &LocalContext) -> Vec<PackageSpec> {
+    let mut specs = ["local", "preview"]
+        .into_iter()
+        .flat_map(|ns| list_package_by_namespace(&ctx.world.registry, ns.into()))
+        .map(|(_, spec)| spec)
+        .collect::<Vec<_>>();
+    specs.sort_by_key(|spec| {
+        (
+            spec.namespace.clone(),
+            spec.name.clone(),
+            Reverse(spec.version),
+        )
+    });
+    specs.dedup_by(|a, b| a.namespace == b.namespace && a.name == b.name);
+    specs
+}
+
+/// Gets the modules in the workspace and the packages that can be imported by
+/// the file.
+pub(crate) fn importable_modules(ctx: &mut LocalContext, id: TypstFileId) -> Vec<ImportableModule> {
+    let index = ctx.export_index();
+
+    let files = index.files.iter().filter(|(fid, _)| *fid != id);
+    let files = files.filter_map(|(fid, info)| {
+        let path = path_label(id, *fid, false)?;
+        Some(ImportableModule {
+            path,
+            info: info.clone(),
+        })
+    });
+    let packages = index.packages.iter().map(|(spec, info)| ImportableModule {
+        path: eco_format!("{spec}"),
+        info: info.clone(),
+    });
+
+    files.chain(packages).collect()
+}
+
+/// Gets the offset and the text to import the name from the module. The name
+/// is added to the items of an existing import of the module, or else a new
+/// import is inserted after the last import at the top level of the file.
+pub(crate) fn new_import(root: &LinkedNode, path: &str, name: &str) -> (usize, String) {
+    let imports = root
+        .children()
+        .filter(|child| child.kind() == SyntaxKind::ModuleImport)
+        .collect::<Vec<_>>();
+    if let Some(end) = imports.iter().find_map(|import| items_end(import, path)) {
+        return (end, format!(", {name}"));
+    }
+
+    match imports.last() {
+        Some(import) => (import.range().end, format!("\n#import \"{path}\": {name}")),
+        None => (0, format!("#import \"{path}\": {name}\n")),
+    }
+}
+
+/// Gets the end of the last item imported from the module at the path.
+fn items_end(import: &LinkedNode, path: &str) -> Option<usize> {
+    let ast::Expr::Str(source) = import.cast::<ast::ModuleImport>()?.source() else {
+        return None;
+    };
+    if source.get() != path {
+        return None;
+    }
+
+    let items = import
+        .children()
+        .find(|child| child.kind() == SyntaxKind::ImportItems)?;
+    let last = items.children().filter(|item| {
+        matches!(
+            item.kind(),
+            SyntaxKind::ImportItemPath | SyntaxKind::RenamedImportItem
+        )
+    });
+    Some(last.last()?.range().end)
+}
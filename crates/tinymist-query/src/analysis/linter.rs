@@ -0,0 +1,413 @@
+//! Linting the source code by static analysis.
+//!
+//! The linter reuses the results of the type checker to find problems that
+//! are not reported by the compiler, e.g. a wrong argument in a branch that is
+//! not evaluated in the current compilation.
+
+use rustc_hash::FxHashSet;
+use typst::diag::{eco_format, SourceDiagnostic};
+use typst::foundations::{Array, Content, Dict, Str, Type};
+use typst::layout::Rel;
+
+use super::prelude::*;
+use super::{analyze_signature, BuiltinTy, SharedContext, Signature, SignatureTarget};
+use crate::syntax::{Decl, DeclExpr, Expr, ExprInfo};
+
+/// A diagnostic reported by the linter.
+#[derive(Debug, Clone)]
+pub struct LintDiagnostic {
+    /// The diagnostic in the same form as the compiler's.
+    pub diag: SourceDiagnostic,
+    /// Whether the diagnostic marks unused or unreachable code, which is
+    /// usually faded out by the editor.
+    pub unnecessary: bool,
+}
+
+/// The result of linting a source file.
+pub type LintDiagnostics = EcoVec<LintDiagnostic>;
+
+/// Lints a source file.
+pub fn lint_source(ctx: &mut LocalContext, source: &Source) -> LintDiagnostics {
+    let mut worker = Linter {
+        ctx,
+        source: source.clone(),
+        diagnostics: EcoVec::new(),
+    };
+    worker.check(LinkedNode::new(source.root()));
+    worker.check_unused();
+    worker.diagnostics
+}
+
+/// The kind of an unused declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnusedKind {
+    /// A variable bound by `let`, `for` or a destructuring pattern.
+    Variable,
+    /// A parameter of a closure.
+    Parameter,
+    /// A function defined by `let`.
+    Function,
+    /// An item imported by `import`.
+    Import,
+}
+
+impl UnusedKind {
+    fn describe(self) -> &'static str {
+        match self {
+            UnusedKind::Variable => "variable",
+            UnusedKind::Parameter => "parameter",
+            UnusedKind::Function => "function",
+            UnusedKind::Import => "import",
+        }
+    }
+}
+
+/// A declaration that is never referenced.
+#[derive(Debug, Clone)]
+pub struct UnusedDecl {
+    /// The kind of the declaration.
+    pub kind: UnusedKind,
+    /// The declaration.
+    pub decl: DeclExpr,
+}
+
+/// Finds the declarations in a source file that are never referenced.
+///
+/// Declarations exported by a module may be used by other files, so they are
+/// only checked in the main file. Names starting with an underscore are
+/// considered intentionally unused.
+pub(crate) fn unused_decls(ctx: &mut LocalContext, source: &Source) -> Vec<UnusedDecl> {
+    let ei = ctx.expr_stage(source);
+    let is_main = ctx.world.main() == source.id();
+
+    let mut used = FxHashSet::default();
+    for r in ei.resolves.values() {
+        for expr in [&r.root, &r.step].into_iter().flatten() {
+            if let Expr::Decl(decl) = expr {
+                if *decl != r.decl {
+                    used.insert(decl.clone());
+                }
+            }
+        }
+    }
+
+    let root = LinkedNode::new(source.root());
+    let mut unused = ei
+        .resolves
+        .iter()
+        .filter(|(span, r)| **span == r.decl.span() && !used.contains(&r.decl))
+        .filter(|(_, r)| !r.decl.name().starts_with('_'))
+        .filter(|(_, r)| is_main || !is_exported(&ei, &r.decl))
+        .filter_map(|(span, r)| {
+            let kind = match r.decl.as_ref() {
+                Decl::Import(..) | Decl::ImportAlias(..) => UnusedKind::Import,
+                Decl::Func(..) => UnusedKind::Function,
+                Decl::Var(..) => {
+                    let node = root.find(*span)?;
+                    let parent = node.parent()?;
+                    let in_params = match parent.kind() {
+                        SyntaxKind::Params => true,
+                        SyntaxKind::Named | SyntaxKind::Spread => {
+                            parent.parent_kind() == Some(SyntaxKind::Params)
+                        }
+                        _ => false,
+                    };
+                    if in_params {
+                        UnusedKind::Parameter
+                    } else {
+                        UnusedKind::Variable
+                    }
+                }
+                _ => return None,
+            };
+
+            Some(UnusedDecl {
+                kind,
+                decl: r.decl.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    unused.sort_by_key(|unused| unused.decl.span().number());
+    unused
+}
+
+fn is_exported(ei: &ExprInfo, decl: &DeclExpr) -> bool {
+    ei.is_exported(decl)
+        || matches!(ei.exports.get(decl.name()), Some(Expr::Ref(r)) if r.decl == *decl)
+}
+
+struct Linter<'a> {
+    ctx: &'a mut LocalContext,
+    source: Source,
+    diagnostics: LintDiagnostics,
+}
+
+impl Linter<'_> {
+    fn warn(&mut self, diag: SourceDiagnostic) {
+        self.diagnostics.push(LintDiagnostic {
+            diag,
+            unnecessary: false,
+        });
+    }
+
+    fn unnecessary(&mut self, diag: SourceDiagnostic) {
+        self.diagnostics.push(LintDiagnostic {
+            diag,
+            unnecessary: true,
+        });
+    }
+
+    fn check(&mut self, node: LinkedNode) {
+        match node.kind() {
+            // Calls in math mode are displayed as-is if the callee is not a function.
+            SyntaxKind::Equation => return,
+            SyntaxKind::FuncCall => {
+                if let Some(call) = node.cast::<ast::FuncCall>() {
+                    self.check_call(call);
+                }
+            }
+            SyntaxKind::Code => {
+                if let Some(code) = node.cast::<ast::Code>() {
+                    self.check_unreachable(code);
+                }
+            }
+            _ => {}
+        }
+
+        for child in node.children() {
+            self.check(child);
+        }
+    }
+
+    fn check_unused(&mut self) {
+        let source = self.source.clone();
+        for unused in unused_decls(self.ctx, &source) {
+            let name = unused.decl.name();
+            let mut diag = SourceDiagnostic::warning(
+                unused.decl.span(),
+                eco_format!("unused {}: `{name}`", unused.kind.describe()),
+            );
+            if matches!(unused.kind, UnusedKind::Variable | UnusedKind::Parameter) {
+                diag.hint(eco_format!(
+                    "if this is intentional, prefix it with an underscore: `_{name}`"
+                ));
+            }
+            self.unnecessary(diag);
+        }
+    }
+
+    /// Checks the expressions after a `return`, `break` or `continue` in the
+    /// same code block.
+    fn check_unreachable(&mut self, code: ast::Code) {
+        let mut exprs = code.exprs().skip_while(|expr| {
+            !matches!(
+                expr,
+                ast::Expr::Return(..) | ast::Expr::Break(..) | ast::Expr::Continue(..)
+            )
+        });
+        if exprs.next().is_none() {
+            return;
+        }
+
+        for expr in exprs {
+            self.unnecessary(SourceDiagnostic::warning(expr.span(), "unreachable code"));
+        }
+    }
+
+    fn check_call(&mut self, call: ast::FuncCall) {
+        let callee = call.callee();
+        if !self.is_static_callee(callee) {
+            return;
+        }
+
+        if let Some(found) = self.non_callable(callee) {
+            self.warn(SourceDiagnostic::warning(
+                callee.span(),
+                eco_format!("expected function, found {found}"),
+            ));
+            return;
+        }
+
+        let target = SignatureTarget::SyntaxFast(self.source.clone(), callee.span());
+        let Some(sig) = analyze_signature(self.ctx.shared(), target) else {
+            return;
+        };
+        // todo: check positional arguments against the with stack
+        let check_pos = matches!(sig, Signature::Primary(..));
+        let sig = sig.primary();
+
+        let mut pos = 0;
+        let mut spread = false;
+        for arg in call.args().items() {
+            match arg {
+                ast::Arg::Pos(expr) => {
+                    if spread || !check_pos {
+                        continue;
+                    }
+
+                    let param = sig.get_pos(pos).or_else(|| sig.rest());
+                    pos += 1;
+                    match param {
+                        Some(param) => self.check_arg(&param.name, &param.ty, expr),
+                        None => self.warn(SourceDiagnostic::warning(
+                            expr.span(),
+                            "unexpected argument",
+                        )),
+                    }
+                }
+                ast::Arg::Named(named) => {
+                    let name: StrRef = named.name().get().as_str().into();
+                    match sig.get_named(&name) {
+                        Some(param) => self.check_arg(&param.name, &param.ty, named.expr()),
+                        None if !sig.has_spread_right() => {
+                            self.warn(SourceDiagnostic::warning(
+                                named.name().span(),
+                                eco_format!("unexpected argument: {name}"),
+                            ));
+                        }
+                        None => {}
+                    }
+                }
+                ast::Arg::Spread(..) => spread = true,
+            }
+        }
+    }
+
+    fn check_arg(&mut self, name: &StrRef, expected: &Ty, arg: ast::Expr) {
+        let Some(value) = literal_value(arg) else {
+            return;
+        };
+
+        if accepts(expected, &value) != Some(false) {
+            return;
+        }
+
+        let expected = expected.describe().unwrap_or_else(|| "any".into());
+        let found = value.ty().short_name();
+        self.warn(
+            SourceDiagnostic::warning(
+                arg.span(),
+                eco_format!("expected {expected}, found {found}"),
+            )
+            .with_hint(eco_format!("for parameter `{name}`")),
+        );
+    }
+
+    /// Whether the callee can be resolved without evaluating the code, i.e.
+    /// the function is bound to an identifier or is a member of a module or
+    /// an element function. Method calls are not checked.
+    fn is_static_callee(&mut self, callee: ast::Expr) -> bool {
+        match callee {
+            ast::Expr::Ident(..) => true,
+            ast::Expr::FieldAccess(access) => match self.ctx.type_of_span(access.target().span()) {
+                Some(Ty::Value(ins)) => matches!(ins.val, Value::Module(..) | Value::Func(..)),
+                Some(Ty::Builtin(BuiltinTy::Module(..))) => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn non_callable(&mut self, callee: ast::Expr) -> Option<EcoString> {
+        let ty = self.ctx.type_of_span(callee.span())?;
+        non_callable_type(&ty)
+    }
+}
+
+/// Gets the value of a literal argument, which is enough to know its type.
+fn literal_value(arg: ast::Expr) -> Option<Value> {
+    SharedContext::const_eval(arg).or_else(|| match arg {
+        ast::Expr::Content(..) => Some(Value::Content(Content::empty())),
+        ast::Expr::Array(..) => Some(Value::Array(Array::new())),
+        ast::Expr::Dict(..) => Some(Value::Dict(Dict::new())),
+        _ => None,
+    })
+}
+
+/// Describes the type of a value that is certainly not callable.
+fn non_callable_type(ty: &Ty) -> Option<EcoString> {
+    Some(match ty {
+        Ty::Value(ins) => match &ins.val {
+            Value::Func(..) | Value::Type(..) => return None,
+            val => val.ty().short_name().into(),
+        },
+        Ty::Builtin(BuiltinTy::Content | BuiltinTy::Element(..)) => "content".into(),
+        Ty::Builtin(BuiltinTy::None) => "none".into(),
+        Ty::Builtin(BuiltinTy::Type(ty)) => {
+            if *ty == Type::of::<Func>() || *ty == Type::of::<Type>() {
+                return None;
+            }
+            ty.short_name().into()
+        }
+        Ty::Boolean(..) => "boolean".into(),
+        Ty::Array(..) | Ty::Tuple(..) => "array".into(),
+        Ty::Dict(..) => "dictionary".into(),
+        Ty::Union(types) => {
+            let mut found = None;
+            for ty in types.iter() {
+                found = Some(non_callable_type(ty)?);
+            }
+            found?
+        }
+        _ => return None,
+    })
+}
+
+/// Checks whether a value is accepted by the type. Returns `None` if it cannot
+/// be decided statically.
+fn accepts(ty: &Ty, value: &Value) -> Option<bool> {
+    Some(match ty {
+        Ty::Any => true,
+        Ty::Union(types) => {
+            let mut res = Some(false);
+            for ty in types.iter() {
+                match accepts(ty, value) {
+                    Some(true) => return Some(true),
+                    Some(false) => {}
+                    None => res = None,
+                }
+            }
+            return res;
+        }
+        Ty::Value(ins) => match &ins.val {
+            expected @ (Value::Str(..) | Value::Int(..) | Value::Bool(..) | Value::Auto) => {
+                expected == value
+            }
+            _ => return None,
+        },
+        Ty::Builtin(builtin) => match builtin {
+            BuiltinTy::Type(ty) => type_accepts(*ty, value),
+            BuiltinTy::Content => type_accepts(Type::of::<Content>(), value),
+            BuiltinTy::None => matches!(value, Value::None),
+            BuiltinTy::Auto => matches!(value, Value::Auto),
+            BuiltinTy::Float => matches!(value, Value::Float(..) | Value::Int(..)),
+            BuiltinTy::Length => matches!(value, Value::Length(..)),
+            BuiltinTy::Color => matches!(value, Value::Color(..)),
+            _ => return None,
+        },
+        Ty::Boolean(..) => matches!(value, Value::Bool(..)),
+        Ty::Array(..) | Ty::Tuple(..) => matches!(value, Value::Array(..)),
+        Ty::Dict(..) => matches!(value, Value::Dict(..)),
+        _ => return None,
+    })
+}
+
+/// Checks whether a value can be casted to the type, following the implicit
+/// conversions of the compiler.
+fn type_accepts(ty: Type, value: &Value) -> bool {
+    if value.ty() == ty {
+        return true;
+    }
+
+    if ty == Type::of::<f64>() {
+        matches!(value, Value::Int(..))
+    } else if ty == Type::of::<Content>() {
+        matches!(value, Value::Str(..) | Value::Symbol(..) | Value::None)
+    } else if ty == Type::of::<Rel>() {
+        matches!(value, Value::Length(..) | Value::Ratio(..))
+    } else if ty == Type::of::<Str>() {
+        matches!(value, Value::Symbol(..))
+    } else {
+        false
+    }
+}
@@ -73,11 +73,13 @@ impl TypeChecker<'_> {
 
     fn check_dict(&mut self, elems: &Interned<Vec<ArgExpr>>) -> Ty {
         let mut fields = Vec::new();
+        let mut site = None;
 
         for elem in elems.iter() {
             match elem {
                 ArgExpr::Named(n) => {
                     let (name, value) = n.as_ref();
+                    site = site.or_else(|| Some(name.span()).filter(|s| !s.is_detached()));
                     let name = name.name().clone();
                     let val = self.check(value);
                     fields.push((name, val));
@@ -92,7 +94,12 @@ impl TypeChecker<'_> {
             }
         }
 
-        Ty::Dict(RecordTy::new(fields))
+        let record = RecordTy::new(fields);
+        // The dictionary is located by the site of its first key.
+        if let Some(site) = site {
+            self.info.shape_sites.entry(record.clone()).or_insert(site);
+        }
+        Ty::Dict(record)
     }
 
     fn check_args(&mut self, args: &Interned<Vec<ArgExpr>>) -> Ty {
@@ -412,6 +419,10 @@ impl TypeChecker<'_> {
         };
 
         self.constrain(&sig, &var);
+        let site = def_id.span();
+        if !site.is_detached() {
+            self.info.func_sites.entry(sig.clone()).or_insert(site);
+        }
         sig
     }
 
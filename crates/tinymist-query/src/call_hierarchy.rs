@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, SymbolKind,
+};
+
+use crate::{
+    adt::interner::Interned,
+    prelude::*,
+    syntax::{get_index_info, DeclExpr, Expr, ExprInfo},
+};
+
+/// The [`textDocument/prepareCallHierarchy`] request is sent from the client
+/// to the server to resolve the call hierarchy item of the function denoted
+/// by the given text document position.
+///
+/// [`textDocument/prepareCallHierarchy`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_prepareCallHierarchy
+#[derive(Debug, Clone)]
+pub struct PrepareCallHierarchyRequest {
+    /// The path of the document to request for.
+    pub path: PathBuf,
+    /// The source code position to request for.
+    pub position: LspPosition,
+}
+
+impl SemanticRequest for PrepareCallHierarchyRequest {
+    type Response = Vec<CallHierarchyItem>;
+
+    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+        let decl = func_decl_at(ctx, &self.path, self.position)?;
+        Some(vec![func_item(ctx, &decl)?])
+    }
+}
+
+/// The [`callHierarchy/incomingCalls`] request is sent from the client to the
+/// server to resolve the callers of a call hierarchy item.
+///
+/// The item is identified by the path of its document and the start of its
+/// selection range.
+///
+/// [`callHierarchy/incomingCalls`]: https://microsoft.github.io/language-server-protocol/specification#callHierarchy_incomingCalls
+#[derive(Debug, Clone)]
+pub struct CallHierarchyIncomingCallsRequest {
+    /// The path of the document where the function is defined.
+    pub path: PathBuf,
+    /// The position of the name of the function.
+    pub position: LspPosition,
+}
+
+impl SemanticRequest for CallHierarchyIncomingCallsRequest {
+    type Response = Vec<CallHierarchyIncomingCall>;
+
+    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+        let decl = func_decl_at(ctx, &self.path, self.position)?;
+        let fid = decl.file_id()?;
+
+        // Collects call sites grouped by their callers. A call at the module
+        // level is attributed to the module itself.
+        let mut calls: IndexMap<(TypstFileId, Option<DeclExpr>), Vec<LspRange>> = IndexMap::new();
+        let mut searched = HashSet::new();
+        let mut worklist = vec![fid];
+        searched.insert(fid);
+        // The function may be imported and exported under aliases, e.g. by
+        // `import "a.typ": f as g`, which are searched as well.
+        let mut names = HashSet::new();
+        names.insert(decl.name().clone());
+
+        while let Some(ref_fid) = worklist.pop() {
+            let Ok(src) = ctx.source_by_id(ref_fid) else {
+                continue;
+            };
+            let index = get_index_info(&src);
+            if !names.iter().any(|name| index.identifiers.contains(name)) {
+                continue;
+            }
+
+            let ei = ctx.expr_stage(&src);
+            let root = LinkedNode::new(src.root());
+            let mut spans = ei
+                .get_refs(decl.clone())
+                .map(|(s, _)| *s)
+                .collect::<Vec<_>>();
+            spans.sort_by_key(|span| span.number());
+
+            for span in spans {
+                let Some(callee) = root.find(span).and_then(|node| callee_of(&node)) else {
+                    continue;
+                };
+                let caller = enclosing_func(&callee);
+                let range = ctx.to_lsp_range(callee.range(), &src);
+                calls.entry((ref_fid, caller)).or_default().push(range);
+            }
+
+            let exported = exported_names(&ei, &decl);
+            if !exported.is_empty() {
+                names.extend(exported);
+                let deps = ctx.module_dependencies().get(&ref_fid);
+                let dependents = deps.map(|dep| dep.dependents.clone()).into_iter().flatten();
+                for dep in dependents {
+                    if searched.insert(dep) {
+                        worklist.push(dep);
+                    }
+                }
+            }
+        }
+
+        let res = calls
+            .into_iter()
+            .filter_map(|((fid, caller), from_ranges)| {
+                let from = match caller {
+                    Some(caller) => func_item(ctx, &caller)?,
+                    None => module_item(ctx, fid)?,
+                };
+                Some(CallHierarchyIncomingCall { from, from_ranges })
+            })
+            .collect();
+
+        crate::log_debug_ct!("incoming calls: {res:?}");
+        Some(res)
+    }
+}
+
+/// The [`callHierarchy/outgoingCalls`] request is sent from the client to the
+/// server to resolve the functions called by a call hierarchy item.
+///
+/// The item is identified by the path of its document and the start of its
+/// selection range.
+///
+/// [`callHierarchy/outgoingCalls`]: https://microsoft.github.io/language-server-protocol/specification#callHierarchy_outgoingCalls
+#[derive(Debug, Clone)]
+pub struct CallHierarchyOutgoingCallsRequest {
+    /// The path of the document where the function is defined.
+    pub path: PathBuf,
+    /// The position of the name of the function.
+    pub position: LspPosition,
+}
+
+impl SemanticRequest for CallHierarchyOutgoingCallsRequest {
+    type Response = Vec<CallHierarchyOutgoingCall>;
+
+    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+        let decl = func_decl_at(ctx, &self.path, self.position)?;
+        let src = ctx.source_by_id(decl.file_id()?).ok()?;
+        let root = LinkedNode::new(src.root());
+        let closure = root.find(decl.span())?.parent()?.clone();
+        let body = closure.find(closure.cast::<ast::Closure>()?.body().span())?;
+
+        let mut callees = vec![];
+        collect_calls(&body, &mut callees);
+
+        let mut calls: IndexMap<DeclExpr, Vec<LspRange>> = IndexMap::new();
+        for callee in callees {
+            let Some(def) = ctx.def_of_span(&src, None, callee.span()) else {
+                continue;
+            };
+            if !matches!(def.decl.as_ref(), Decl::Func(..)) {
+                continue;
+            }
+            let range = ctx.to_lsp_range(callee.range(), &src);
+            calls.entry(def.decl).or_default().push(range);
+        }
+
+        let res = calls
+            .into_iter()
+            .filter_map(|(callee, from_ranges)| {
+                let to = func_item(ctx, &callee)?;
+                Some(CallHierarchyOutgoingCall { to, from_ranges })
+            })
+            .collect();
+
+        crate::log_debug_ct!("outgoing calls: {res:?}");
+        Some(res)
+    }
+}
+
+/// Finds the declaration of the user function denoted by the position.
+fn func_decl_at(ctx: &mut LocalContext, path: &Path, position: LspPosition) -> Option<DeclExpr> {
+    let source = ctx.source_by_path(path).ok()?;
+    let syntax = ctx.classify_pos(&source, position, 1)?;
+    let def = ctx.def_of_syntax(&source, None, syntax)?;
+    matches!(def.decl.as_ref(), Decl::Func(..)).then_some(def.decl)
+}
+
+/// Gets the names under which the module exports the declaration.
+fn exported_names(ei: &ExprInfo, decl: &DeclExpr) -> Vec<Interned<str>> {
+    let of = Expr::Decl(decl.clone());
+    let exports = ei.exports.iter().filter(|(_, export)| match export {
+        Expr::Ref(ref_expr) => ref_expr.root.as_ref() == Some(&of),
+        _ => **export == of,
+    });
+    exports.map(|(name, _)| name.clone()).collect()
+}
+
+/// Creates a call hierarchy item for a user function.
+fn func_item(ctx: &mut LocalContext, decl: &DeclExpr) -> Option<CallHierarchyItem> {
+    let fid = decl.file_id()?;
+    let src = ctx.source_by_id(fid).ok()?;
+    let name = LinkedNode::new(src.root()).find(decl.span())?;
+
+    let binding = iter::successors(Some(&name), |node| node.parent())
+        .find(|node| node.kind() == SyntaxKind::LetBinding);
+    let params = name
+        .parent()
+        .and_then(|closure| closure.cast::<ast::Closure>())
+        .map(|closure| closure.params().to_untyped().clone().into_text());
+
+    Some(CallHierarchyItem {
+        name: decl.name().to_string(),
+        kind: SymbolKind::FUNCTION,
+        tags: None,
+        detail: params.map(|params| params.to_string()),
+        uri: ctx.uri_for_id(fid).ok()?,
+        range: ctx.to_lsp_range(binding.unwrap_or(&name).range(), &src),
+        selection_range: ctx.to_lsp_range(name.range(), &src),
+        data: None,
+    })
+}
+
+/// Creates a call hierarchy item for the top level of a module.
+fn module_item(ctx: &mut LocalContext, fid: TypstFileId) -> Option<CallHierarchyItem> {
+    let src = ctx.source_by_id(fid).ok()?;
+    let name = fid.vpath().as_rooted_path().file_name()?.to_str()?;
+    let range = ctx.to_lsp_range(0..src.text().len(), &src);
+
+    Some(CallHierarchyItem {
+        name: name.to_string(),
+        kind: SymbolKind::FILE,
+        tags: None,
+        detail: None,
+        uri: ctx.uri_for_id(fid).ok()?,
+        range,
+        selection_range: ctx.to_lsp_range(0..0, &src),
+        data: None,
+    })
+}
+
+/// Gets the callee expression if the identifier is called, e.g. `f` in `f()`
+/// or `m.f` in `m.f()`.
+fn callee_of<'a>(ident: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    let mut callee = ident.clone();
+    if let Some(access) = ident
+        .parent()
+        .filter(|p| p.kind() == SyntaxKind::FieldAccess)
+    {
+        let field = access.cast::<ast::FieldAccess>()?.field();
+        if field.span() == ident.span() {
+            callee = access.clone();
+        }
+    }
+
+    let call = callee.parent()?.cast::<ast::FuncCall>()?;
+    (call.callee().span() == callee.span()).then_some(callee)
+}
+
+/// Gets the named function whose body contains the node.
+fn enclosing_func(node: &LinkedNode) -> Option<DeclExpr> {
+    iter::successors(node.parent(), |node| node.parent()).find_map(|node| {
+        let closure = node.cast::<ast::Closure>()?;
+        node.parent()?.cast::<ast::LetBinding>()?;
+        Some(Decl::func(closure.name()?).into())
+    })
+}
+
+/// Collects the identifiers being called in the subtree, which are resolved to
+/// their definitions later.
+fn collect_calls<'a>(node: &LinkedNode<'a>, calls: &mut Vec<LinkedNode<'a>>) {
+    if let Some(call) = node.cast::<ast::FuncCall>() {
+        let callee = match call.callee() {
+            ast::Expr::Ident(ident) => Some(ident.span()),
+            ast::Expr::FieldAccess(access) => Some(access.field().span()),
+            _ => None,
+        };
+        if let Some(callee) = callee.and_then(|span| node.find(span)) {
+            calls.push(callee);
+        }
+    }
+
+    for child in node.children() {
+        collect_calls(&child, calls);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn incoming() {
+        snapshot_testing("call_hierarchy", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let request = CallHierarchyIncomingCallsRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request.request(ctx);
+            assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
+        });
+    }
+
+    #[test]
+    fn outgoing() {
+        snapshot_testing("call_hierarchy", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let request = CallHierarchyOutgoingCallsRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request.request(ctx);
+            assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
+        });
+    }
+}
@@ -1,3 +1,5 @@
+use lsp_types::CodeActionContext;
+
 use crate::{analysis::CodeActionWorker, prelude::*, SemanticRequest};
 
 /// The [`textDocument/codeAction`] request is sent from the client to the
@@ -64,6 +66,9 @@ pub struct CodeActionRequest {
     pub path: PathBuf,
     /// The range of the document to get code actions for.
     pub range: LspRange,
+    /// The context carrying the diagnostics overlapping the range, which are
+    /// fixed by the quick fixes.
+    pub context: CodeActionContext,
 }
 
 impl SemanticRequest for CodeActionRequest {
@@ -75,8 +80,92 @@ impl SemanticRequest for CodeActionRequest {
 
         let root = LinkedNode::new(source.root());
         let mut worker = CodeActionWorker::new(ctx, source.clone());
-        worker.work(root, range);
+        worker.work(root.clone(), range.clone());
+        worker.diagnostic_actions(&root, &self.context.diagnostics);
+        // Finding the unused declarations checks the whole file, so it is only
+        // done when the quick fixes are asked for.
+        if asks_for_unused_fixes(&self.context) {
+            worker.unused_actions(&root, range);
+        }
 
         (!worker.actions.is_empty()).then_some(worker.actions)
     }
 }
+
+/// Whether the client asks for the quick fixes, or for the actions on the
+/// diagnostics of unused declarations reported by the linter.
+fn asks_for_unused_fixes(context: &CodeActionContext) -> bool {
+    let quick_fixes = context.only.as_ref().is_some_and(|only| {
+        only.iter()
+            .any(|kind| kind.as_str().starts_with(CodeActionKind::QUICKFIX.as_str()))
+    });
+    let unused = context.diagnostics.iter().any(|diag| {
+        diag.source.as_deref() == Some("tinymist") && diag.message.starts_with("unused ")
+    });
+    quick_fixes || unused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::find_module_level_docs;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("code_action", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let docs = find_module_level_docs(&source).unwrap_or_default();
+            let properties = get_test_properties(&docs);
+            let title = properties.get("title").copied().unwrap_or_default();
+
+            // The quick fixes are offered for the diagnostics of the compiled document
+            // and of the linter.
+            let mut diagnostics = crate::DiagnosticsMap::default();
+            if properties.get("compile") == Some(&"true") {
+                diagnostics = crate::compile_diagnostics(&ctx.world, ctx.position_encoding());
+            }
+            if properties.get("lint") == Some(&"true") {
+                let lints = crate::lint_sources_diagnostics(ctx, [source.clone()]);
+                crate::merge_lint_diagnostics(&mut diagnostics, lints);
+            }
+            let diagnostics = diagnostics
+                .remove(&path_to_url(&path).unwrap())
+                .unwrap_or_default();
+
+            let request = CodeActionRequest {
+                path: path.clone(),
+                range: ctx.to_lsp_range(find_test_range(&source), &source),
+                context: CodeActionContext {
+                    diagnostics,
+                    ..Default::default()
+                },
+            };
+
+            let actions = request.request(ctx).unwrap_or_default();
+            let action = actions.into_iter().find_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) if action.title.starts_with(title) => {
+                    Some(action)
+                }
+                _ => None,
+            });
+
+            let result = match action.and_then(|action| action.edit?.changes) {
+                Some(changes) => {
+                    let mut edits = changes.into_values().next().unwrap();
+                    edits.sort_by_key(|edit| (edit.range.start.line, edit.range.start.character));
+
+                    let mut text = source.text().to_owned();
+                    for edit in edits.iter().rev() {
+                        let range = ctx.to_typst_range(edit.range, &source).unwrap();
+                        text.replace_range(range, &edit.new_text);
+                    }
+                    text
+                }
+                None => "no code action".to_owned(),
+            };
+            assert_snapshot!(result);
+        });
+    }
+}
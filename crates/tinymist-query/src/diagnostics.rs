@@ -1,12 +1,24 @@
+use lsp_types::{DiagnosticTag, NumberOrString};
 use reflexo_typst::EntryReader;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use tinymist_world::LspWorld;
 use typst::syntax::Span;
 
-use crate::{prelude::*, LspWorldExt};
+use crate::{analysis::lint_source, prelude::*, LspWorldExt};
 
 /// Stores diagnostics for files.
 pub type DiagnosticsMap = HashMap<Url, Vec<Diagnostic>>;
 
+/// Identifies the diagnostics cached in an analysis revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum DiagnosticsKey {
+    /// The diagnostics of compiling the document with the entry file.
+    Compile(TypstFileId),
+    /// The lints of the source file.
+    Lint(TypstFileId),
+}
+
 type TypstDiagnostic = typst::diag::SourceDiagnostic;
 type TypstSeverity = typst::diag::Severity;
 
@@ -16,6 +28,8 @@ struct LocalDiagContext<'a> {
     pub world: &'a LspWorld,
     /// The position encoding for the source.
     pub position_encoding: PositionEncoding,
+    /// The tool that produces the diagnostics.
+    pub source: &'static str,
 }
 
 impl std::ops::Deref for LocalDiagContext<'_> {
@@ -26,15 +40,168 @@ impl std::ops::Deref for LocalDiagContext<'_> {
     }
 }
 
+/// A known error of the diagnostics, which is attached to the LSP diagnostics
+/// as their `data` to offer quick fixes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum KnownError {
+    /// An identifier is not defined in the scope.
+    UnknownVariable {
+        /// The name of the variable.
+        name: EcoString,
+    },
+    /// A file is not found in the file system.
+    FileNotFound {
+        /// The absolute path searched for the file.
+        path: PathBuf,
+    },
+    /// A named argument is not accepted by the function.
+    UnexpectedArgument {
+        /// The name of the argument.
+        name: EcoString,
+    },
+    /// A label is referred but not defined in the document.
+    LabelNotFound {
+        /// The name of the label, without the angle brackets.
+        label: EcoString,
+    },
+}
+
+impl KnownError {
+    /// Parses the known error from the message of a diagnostic.
+    pub fn parse(message: &str) -> Option<Self> {
+        static FILE_NOT_FOUND: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^file not found \(searched at (.+)\)$").unwrap());
+        static LABEL_NOT_FOUND: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^label `<(.+)>` does not exist").unwrap());
+
+        if let Some(name) = message.strip_prefix("unknown variable: ") {
+            return Some(Self::UnknownVariable { name: name.into() });
+        }
+        if let Some(name) = message.strip_prefix("unexpected argument: ") {
+            return Some(Self::UnexpectedArgument { name: name.into() });
+        }
+        if let Some(caps) = FILE_NOT_FOUND.captures(message) {
+            return Some(Self::FileNotFound {
+                path: caps[1].into(),
+            });
+        }
+        if let Some(caps) = LABEL_NOT_FOUND.captures(message) {
+            return Some(Self::LabelNotFound {
+                label: caps[1].into(),
+            });
+        }
+
+        None
+    }
+
+    /// The code of the error in the LSP diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnknownVariable { .. } => "unknown-variable",
+            Self::FileNotFound { .. } => "file-not-found",
+            Self::UnexpectedArgument { .. } => "unexpected-argument",
+            Self::LabelNotFound { .. } => "label-not-found",
+        }
+    }
+}
+
 /// Converts a list of Typst diagnostics to LSP diagnostics.
 pub fn convert_diagnostics<'a>(
     world: &LspWorld,
     errors: impl IntoIterator<Item = &'a TypstDiagnostic>,
     position_encoding: PositionEncoding,
+) -> DiagnosticsMap {
+    convert_diagnostics_(world, errors, position_encoding, "typst")
+}
+
+/// Compiles the main file of the world and converts the errors and warnings
+/// to LSP diagnostics.
+pub fn compile_diagnostics(
+    world: &LspWorld,
+    position_encoding: PositionEncoding,
+) -> DiagnosticsMap {
+    let res = typst::compile(world);
+    let errors = res.output.err().unwrap_or_default();
+    convert_diagnostics(
+        world,
+        errors.iter().chain(res.warnings.iter()),
+        position_encoding,
+    )
+}
+
+/// Lints the source files that the compilation depends on and converts the
+/// results to LSP diagnostics.
+pub fn lint_diagnostics(ctx: &mut LocalContext) -> DiagnosticsMap {
+    let mut sources = vec![];
+    for path in ctx.dependencies() {
+        if path.extension().is_none_or(|ext| ext != "typ") {
+            continue;
+        }
+        // Files out of the workspace, e.g. packages, are not linted.
+        let Ok(source) = ctx.source_by_path(&path) else {
+            continue;
+        };
+        sources.push(source);
+    }
+
+    lint_sources_diagnostics(ctx, sources)
+}
+
+/// Lints the given source files and converts the results to LSP diagnostics.
+pub fn lint_sources_diagnostics(
+    ctx: &mut LocalContext,
+    sources: impl IntoIterator<Item = Source>,
+) -> DiagnosticsMap {
+    let mut lints = EcoVec::new();
+    for source in sources {
+        lints.extend(lint_source(ctx, &source));
+    }
+
+    let ctx = LocalDiagContext {
+        world: &ctx.world,
+        position_encoding: ctx.position_encoding(),
+        source: "tinymist",
+    };
+
+    lints
+        .iter()
+        .flat_map(|lint| {
+            let (uri, mut diag) = convert_diagnostic(&ctx, &lint.diag).map_err(|err| {
+                log::error!(
+                    "could not convert lint to diagnostic: {err:?} lint to convert: {lint:?}"
+                );
+            })?;
+            if lint.unnecessary {
+                diag.tags = Some(vec![DiagnosticTag::UNNECESSARY]);
+            }
+            Ok::<_, ()>((uri, diag))
+        })
+        .into_group_map()
+}
+
+/// Merges the lints into the compiler diagnostics. A lint is skipped if the
+/// compiler has already reported a diagnostic at the same range, e.g. a call
+/// that is actually evaluated and fails.
+pub fn merge_lint_diagnostics(diagnostics: &mut DiagnosticsMap, lints: DiagnosticsMap) {
+    for (url, lints) in lints {
+        let items = diagnostics.entry(url).or_default();
+        let reported = items.iter().map(|diag| diag.range).collect::<Vec<_>>();
+        let lints = lints.into_iter();
+        items.extend(lints.filter(|lint| !reported.contains(&lint.range)));
+    }
+}
+
+fn convert_diagnostics_<'a>(
+    world: &LspWorld,
+    errors: impl IntoIterator<Item = &'a TypstDiagnostic>,
+    position_encoding: PositionEncoding,
+    source: &'static str,
 ) -> DiagnosticsMap {
     let ctx = LocalDiagContext {
         world,
         position_encoding,
+        source,
     };
 
     errors
@@ -76,12 +243,20 @@ fn convert_diagnostic(
 
     let tracepoints = diagnostic_related_information(ctx, typst_diagnostic, ctx.position_encoding)?;
 
+    let known = KnownError::parse(typst_message);
+    let code = known
+        .as_ref()
+        .map(|known| NumberOrString::String(known.code().to_owned()));
+    let data = known.and_then(|known| serde_json::to_value(known).ok());
+
     let diagnostic = Diagnostic {
         range: lsp_range,
         severity: Some(lsp_severity),
         message: lsp_message,
-        source: Some("typst".to_owned()),
+        source: Some(ctx.source.to_owned()),
         related_information: Some(tracepoints),
+        code,
+        data,
         ..Default::default()
     };
 
@@ -170,3 +345,27 @@ fn diagnostic_hints(typst_hints: &[EcoString]) -> Format<impl Iterator<Item = Ec
         .interleave(typst_hints.iter().cloned())
         .format("")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_errors() {
+        let parse = |message: &str| KnownError::parse(message).map(|known| known.code());
+        assert_eq!(parse("unknown variable: foo"), Some("unknown-variable"));
+        assert_eq!(
+            parse("unexpected argument: foo"),
+            Some("unexpected-argument")
+        );
+        assert_eq!(
+            parse("file not found (searched at /root/a.typ)"),
+            Some("file-not-found")
+        );
+        assert_eq!(
+            parse("label `<intro>` does not exist in the document"),
+            Some("label-not-found")
+        );
+        assert_eq!(parse("unexpected argument"), None);
+    }
+}
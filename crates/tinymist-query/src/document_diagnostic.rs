@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+
+use lsp_types::{
+    DocumentDiagnosticReport, DocumentDiagnosticReportKind, DocumentDiagnosticReportResult,
+    FullDocumentDiagnosticReport, RelatedFullDocumentDiagnosticReport,
+    RelatedUnchangedDocumentDiagnosticReport, UnchangedDocumentDiagnosticReport,
+    WorkspaceDiagnosticReport, WorkspaceDiagnosticReportResult, WorkspaceDocumentDiagnosticReport,
+    WorkspaceFullDocumentDiagnosticReport, WorkspaceUnchangedDocumentDiagnosticReport,
+};
+use reflexo::hash::hash128;
+use reflexo_typst::{EntryReader, TaskInputs};
+
+use crate::{
+    compile_diagnostics, lint_sources_diagnostics, merge_lint_diagnostics, prelude::*,
+    syntax::Expr, DiagnosticsKey, DiagnosticsMap,
+};
+
+/// The [`textDocument/diagnostic`] request is sent from the client to the
+/// server to pull the diagnostics of a given text document.
+///
+/// The document is compiled on demand, and the diagnostics are reused until
+/// the workspace is changed. If it is not reachable from the main file, it is
+/// compiled as the entry instead.
+///
+/// [`textDocument/diagnostic`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_diagnostic
+#[derive(Debug, Clone)]
+pub struct DocumentDiagnosticRequest {
+    /// The path of the document to request for.
+    pub path: PathBuf,
+    /// The result id of the diagnostics reported previously.
+    pub previous_result_id: Option<String>,
+}
+
+impl SemanticRequest for DocumentDiagnosticRequest {
+    type Response = DocumentDiagnosticReportResult;
+
+    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+        let uri = ctx.uri_for_id(source.id()).ok()?;
+
+        let mut diagnostics = compile_file_diagnostics(ctx, source.id()).as_ref().clone();
+        let lints = lint_file_diagnostics(ctx, source);
+        merge_lint_diagnostics(&mut diagnostics, lints.as_ref().clone());
+
+        let items = diagnostics.remove(&uri).unwrap_or_default();
+        let related_documents = (!diagnostics.is_empty()).then(|| {
+            diagnostics
+                .into_iter()
+                .map(|(uri, items)| {
+                    let report = FullDocumentDiagnosticReport {
+                        result_id: None,
+                        items,
+                    };
+                    (uri, DocumentDiagnosticReportKind::Full(report))
+                })
+                .collect()
+        });
+
+        let result_id = result_id(&items);
+        let report = if self.previous_result_id.as_ref() == Some(&result_id) {
+            DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                related_documents,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                    result_id,
+                },
+            })
+        } else {
+            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items,
+                },
+            })
+        };
+
+        Some(DocumentDiagnosticReportResult::Report(report))
+    }
+}
+
+/// The [`workspace/diagnostic`] request is sent from the client to the server
+/// to pull the diagnostics of the workspace.
+///
+/// The main file is compiled if there is one, and every source file in the
+/// workspace is linted. Files without diagnostics are also reported, so that
+/// the client can clear stale ones.
+///
+/// [`workspace/diagnostic`]: https://microsoft.github.io/language-server-protocol/specification#workspace_diagnostic
+#[derive(Debug, Clone)]
+pub struct WorkspaceDiagnosticRequest {
+    /// The result ids of the diagnostics reported previously, by document.
+    pub previous_result_ids: HashMap<Url, String>,
+}
+
+impl SemanticRequest for WorkspaceDiagnosticRequest {
+    type Response = WorkspaceDiagnosticReportResult;
+
+    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+        let mut diagnostics = if ctx.world.entry_state().is_inactive() {
+            DiagnosticsMap::default()
+        } else {
+            let main = ctx.world.main();
+            let diagnostics = ctx.cached_diagnostics(DiagnosticsKey::Compile(main), |ctx| {
+                compile_diagnostics(&ctx.world, ctx.position_encoding())
+            });
+            diagnostics.as_ref().clone()
+        };
+
+        let sources = ctx.source_files().clone();
+        let sources = sources
+            .into_iter()
+            .filter_map(|fid| ctx.source_by_id(fid).ok())
+            .collect::<Vec<_>>();
+        for source in sources {
+            if let Ok(uri) = ctx.uri_for_id(source.id()) {
+                diagnostics.entry(uri).or_default();
+            }
+            let lints = lint_file_diagnostics(ctx, source);
+            merge_lint_diagnostics(&mut diagnostics, lints.as_ref().clone());
+        }
+
+        let items = diagnostics
+            .into_iter()
+            .map(|(uri, items)| {
+                let result_id = result_id(&items);
+                if self.previous_result_ids.get(&uri) == Some(&result_id) {
+                    let report = UnchangedDocumentDiagnosticReport { result_id };
+                    let report = WorkspaceUnchangedDocumentDiagnosticReport {
+                        uri,
+                        version: None,
+                        unchanged_document_diagnostic_report: report,
+                    };
+                    WorkspaceDocumentDiagnosticReport::Unchanged(report)
+                } else {
+                    let report = FullDocumentDiagnosticReport {
+                        result_id: Some(result_id),
+                        items,
+                    };
+                    let report = WorkspaceFullDocumentDiagnosticReport {
+                        uri,
+                        version: None,
+                        full_document_diagnostic_report: report,
+                    };
+                    WorkspaceDocumentDiagnosticReport::Full(report)
+                }
+            })
+            .collect();
+
+        Some(WorkspaceDiagnosticReportResult::Report(
+            WorkspaceDiagnosticReport { items },
+        ))
+    }
+}
+
+/// Compiles the document that the file belongs to. The diagnostics are cached
+/// by the entry file in the revision, so that the document is compiled once
+/// for all of its files until it is changed.
+///
+/// The main file of the world is preferred, but the file is compiled as the
+/// entry if it is not reachable from the main file, e.g. a chapter file that
+/// is not included by the pinned main file.
+fn compile_file_diagnostics(ctx: &mut LocalContext, fid: TypstFileId) -> Arc<DiagnosticsMap> {
+    let main = (!ctx.world.entry_state().is_inactive()).then(|| ctx.world.main());
+    if let Some(main) = main.filter(|main| is_reachable(ctx, *main, fid)) {
+        return ctx.cached_diagnostics(DiagnosticsKey::Compile(main), |ctx| {
+            compile_diagnostics(&ctx.world, ctx.position_encoding())
+        });
+    }
+
+    ctx.cached_diagnostics(DiagnosticsKey::Compile(fid), |ctx| {
+        let world = ctx.world.task(TaskInputs {
+            entry: Some(ctx.world.entry_state().select_in_workspace(fid)),
+            ..Default::default()
+        });
+        compile_diagnostics(&world, ctx.position_encoding())
+    })
+}
+
+/// Lints the source file, which is cached in the revision.
+fn lint_file_diagnostics(ctx: &mut LocalContext, source: Source) -> Arc<DiagnosticsMap> {
+    ctx.cached_diagnostics(DiagnosticsKey::Lint(source.id()), |ctx| {
+        lint_sources_diagnostics(ctx, [source])
+    })
+}
+
+/// Checks whether the file is imported or included from the main file. The
+/// imports are resolved syntactically, so that the main file is not compiled
+/// for nothing.
+fn is_reachable(ctx: &mut LocalContext, main: TypstFileId, fid: TypstFileId) -> bool {
+    let mut searched = HashSet::new();
+    let mut worklist = vec![main];
+    while let Some(id) = worklist.pop() {
+        if id == fid {
+            return true;
+        }
+        if !searched.insert(id) {
+            continue;
+        }
+        let Ok(source) = ctx.source_by_id(id) else {
+            continue;
+        };
+        let ei = ctx.expr_stage(&source);
+        for r in ei.resolves.values() {
+            let is_path = matches!(
+                r.decl.as_ref(),
+                Decl::ImportPath(..) | Decl::IncludePath(..)
+            );
+            if let (true, Some(Expr::Decl(module))) = (is_path, &r.root) {
+                worklist.extend(module.file_id());
+            }
+        }
+    }
+
+    false
+}
+
+/// Identifies a list of diagnostics, so that unchanged reports can be skipped.
+fn result_id(items: &[Diagnostic]) -> String {
+    let items = serde_json::to_string(items).unwrap_or_default();
+    format!("{:x}", hash128(&items))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::tests::*;
+
+    fn full_items(result: DocumentDiagnosticReportResult) -> Vec<Diagnostic> {
+        match result {
+            DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Full(report)) => {
+                report.full_document_diagnostic_report.items
+            }
+            _ => panic!("expected a full report"),
+        }
+    }
+
+    #[test]
+    fn test() {
+        snapshot_testing("document_diagnostic", &|ctx, path| {
+            let main = ctx.source_by_path(&path).unwrap().id();
+
+            let mut reports = BTreeMap::new();
+            for fid in ctx.source_files().clone() {
+                let path = ctx.path_for_id(fid).unwrap();
+                let request = DocumentDiagnosticRequest {
+                    path: path.clone(),
+                    previous_result_id: None,
+                };
+                let items = full_items(request.request(ctx).unwrap());
+
+                let request = DocumentDiagnosticRequest {
+                    path,
+                    previous_result_id: Some(result_id(&items)),
+                };
+                let unchanged = request.request(ctx).unwrap();
+                assert!(matches!(
+                    unchanged,
+                    DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Unchanged(..))
+                ));
+
+                let name = fid.vpath().as_rootless_path().display().to_string();
+                let reachable = is_reachable(ctx, main, fid);
+                reports.insert(name, json!({ "reachable": reachable, "items": items }));
+            }
+
+            assert_snapshot!(JsonRepr::new_redacted(reports, &REDACT_LOC));
+        });
+    }
+}
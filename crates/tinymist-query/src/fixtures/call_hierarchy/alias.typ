@@ -0,0 +1,9 @@
+/// path: lib.typ
+#let helper(x) = x
+-----
+/// path: reexport.typ
+#import "lib.typ": helper as h
+-----
+#import "reexport.typ": h
+#h[a]
+#(/* position after */ h);
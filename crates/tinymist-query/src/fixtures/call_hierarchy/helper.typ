@@ -0,0 +1,9 @@
+/// path: lib.typ
+#let helper(x) = x
+#let wrap(x) = helper(x)
+-----
+#import "lib.typ": helper, wrap
+#let page-tpl(body) = helper(body) + wrap(body)
+#helper[a]
+#page-tpl[b]
+#(/* position after */ helper);
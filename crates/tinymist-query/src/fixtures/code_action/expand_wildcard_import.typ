@@ -0,0 +1,9 @@
+/// path: a.typ
+#let a = 1
+#let b = 2
+#let f(x) = x
+-----
+/// title: Expand wildcard import
+#import "a.typ": *
+#f(a)
+/* range -8..-7 */
@@ -0,0 +1,4 @@
+/// title: Extract to `
+#let x = 1
+#f(x + 2)
+/* range -7..-2 */
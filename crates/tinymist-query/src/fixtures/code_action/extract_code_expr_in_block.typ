@@ -0,0 +1,6 @@
+/// title: Extract to `
+#{
+  let x = 1
+  x * 2
+}
+/* range -8..-3 */
@@ -0,0 +1,4 @@
+/// title: Extract to function
+#let x = ()
+#x.push(1)
+/* range -10..-1 */
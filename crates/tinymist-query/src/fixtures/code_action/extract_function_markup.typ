@@ -0,0 +1,4 @@
+/// title: Extract to function
+#let name = [Alice]
+Hello #name!
+/* range -13..-1 */
@@ -0,0 +1,7 @@
+/// title: Extract to function
+#{
+  let x = ()
+  x.push(1)
+  x
+}
+/* range -16..-7 */
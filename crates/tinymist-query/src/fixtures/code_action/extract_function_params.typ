@@ -0,0 +1,7 @@
+/// title: Extract to function
+#let y = 1
+#for x in (1, 2) {
+  let z = x + y
+  z * x
+}
+/* range -24..-3 */
@@ -0,0 +1,6 @@
+/// title: Extract to function
+#let f(x) = {
+  if x { return 1 }
+  2
+}
+/* range -24..-7 */
@@ -0,0 +1,4 @@
+/// title: Extract to function
+#set text(red)
+Hello
+/* range -21..-7 */
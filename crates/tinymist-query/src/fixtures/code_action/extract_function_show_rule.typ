@@ -0,0 +1,4 @@
+/// title: Extract to function
+#show heading: strong
+= Title
+/* range -30..-9 */
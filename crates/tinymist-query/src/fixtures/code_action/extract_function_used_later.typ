@@ -0,0 +1,6 @@
+/// title: Extract to function
+#{
+  let x = 1
+  x
+}
+/* range -16..-7 */
@@ -0,0 +1,3 @@
+/// title: Extract to `
+Hello *world*!
+/* range -15..-2 */
@@ -0,0 +1,3 @@
+/// title: Extract to `
+#let x = 1
+/* range -6..-5 */
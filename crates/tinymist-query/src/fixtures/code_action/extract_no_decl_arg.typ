@@ -0,0 +1,3 @@
+/// title: Extract to `
+#f(a: 1)
+/* range -6..-5 */
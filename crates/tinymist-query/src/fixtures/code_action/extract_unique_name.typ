@@ -0,0 +1,4 @@
+/// title: Extract to `
+#let extracted = 1
+#f(extracted + 2)
+/* range -15..-2 */
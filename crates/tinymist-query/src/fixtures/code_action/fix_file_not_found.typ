@@ -0,0 +1,7 @@
+/// path: chapters/intro.typ
+Intro
+-----
+/// title: Change path to "chapters/intro.typ"
+/// compile: true
+#include "chapter/intro.typ"
+/* range -20..-1 */
@@ -0,0 +1,5 @@
+/// title: Change label to `intro`
+/// compile: true
+= Introduction <intro>
+See @intor.
+/* range -8..-2 */
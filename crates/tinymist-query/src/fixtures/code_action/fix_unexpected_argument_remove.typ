@@ -0,0 +1,5 @@
+/// title: Remove argument `sise`
+/// compile: true
+#let f(x, size: 1) = x
+#f(1, sise: 2)
+/* range -9..-5 */
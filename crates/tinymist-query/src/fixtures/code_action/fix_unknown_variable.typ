@@ -0,0 +1,11 @@
+/// path: a.typ
+#let foo = 1
+-----
+/// path: b.typ
+#let bar = 2
+-----
+/// title: Import `foo` from "a.typ"
+/// compile: true
+#import "b.typ": bar
+#foo
+/* range -4..-1 */
@@ -0,0 +1,9 @@
+/// path: a.typ
+#let foo = 1
+#let bar = 2
+-----
+/// title: Import `foo` from "a.typ"
+/// compile: true
+#import "a.typ": bar
+#foo
+/* range -4..-1 */
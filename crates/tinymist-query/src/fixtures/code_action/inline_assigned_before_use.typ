@@ -0,0 +1,8 @@
+/// title: Inline
+#{
+  let a = 1
+  let b = a
+  a = 2
+  b
+}
+/* range -20..-15 */
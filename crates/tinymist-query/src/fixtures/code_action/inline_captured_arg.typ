@@ -0,0 +1,5 @@
+/// title: Inline
+#let f(a) = { let b = 1; a + b }
+#let b = 2
+#f(b)
+/* range -45..-41 */
@@ -0,0 +1,6 @@
+/// title: Inline
+#let add(a, b) = a + b
+#add(1, 2)
+#g(add(x, 3) * 2)
+#g(add)
+/* range -55..-50 */
@@ -0,0 +1,4 @@
+/// title: Inline
+#let id(x) = x
+#f(id(1 + 2) * 3)
+/* range -28..-23 */
@@ -0,0 +1,6 @@
+/// title: Inline
+#{
+  let x = 1
+  x += 1
+}
+/* range -17..-12 */
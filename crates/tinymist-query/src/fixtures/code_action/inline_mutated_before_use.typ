@@ -0,0 +1,8 @@
+/// title: Inline
+#{
+  let a = (1,)
+  let b = a.len()
+  a.push(2)
+  b
+}
+/* range -30..-25 */
@@ -0,0 +1,4 @@
+/// title: Inline
+#let f(x) = { if x { return 1 }; 2 }
+#let g(y) = f(y) + 1
+/* range -53..-49 */
@@ -0,0 +1,6 @@
+/// title: Inline
+#let y = 1
+#let x = y
+#let y = 2
+#x
+/* range -20..-15 */
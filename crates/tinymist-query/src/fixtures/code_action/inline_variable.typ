@@ -0,0 +1,4 @@
+/// title: Inline
+#let x = 1 + 2
+#f(x, x * 2)
+/* range -23..-20 */
@@ -0,0 +1,4 @@
+/// title: Inline
+#let name = [Alice]
+Hello #name!
+/* range -6..-1 */
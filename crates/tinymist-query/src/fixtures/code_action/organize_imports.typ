@@ -0,0 +1,10 @@
+/// path: a.typ
+#let a = 1
+#let b = 2
+#let f(x) = x
+-----
+/// title: Organize imports
+#import "a.typ": f, a
+#import "a.typ": b, f
+#f(a)
+/* range -50..-43 */
@@ -0,0 +1,11 @@
+/// path: a.typ
+#let a = 1
+#let b = 2
+#let f(x) = x
+-----
+/// title: Organize imports
+#import "a.typ": a
+#let b = 1
+#import "a.typ": f, b
+#f(a, b)
+/* range -61..-54 */
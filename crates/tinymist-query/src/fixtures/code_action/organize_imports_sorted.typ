@@ -0,0 +1,9 @@
+/// path: a.typ
+#let a = 1
+#let b = 2
+#let f(x) = x
+-----
+/// title: Organize imports
+#import "a.typ": a, f
+#f(a)
+/* range -28..-21 */
@@ -0,0 +1,5 @@
+/// title: Prefix `size` with an underscore
+/// lint: true
+#let f(x, size: 1) = x
+#f(1)
+/* range -19..-15 */
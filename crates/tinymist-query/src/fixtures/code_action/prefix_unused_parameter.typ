@@ -0,0 +1,5 @@
+/// title: Prefix `a` with an underscore
+/// lint: true
+#let f(a, b) = b
+#f(1, 2)
+/* range -19..-18 */
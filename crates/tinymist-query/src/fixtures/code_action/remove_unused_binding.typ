@@ -0,0 +1,6 @@
+/// title: Remove unused binding `x`
+/// lint: true
+#let y = 1
+#let x = 2
+#y
+/* range -9..-8 */
@@ -0,0 +1,9 @@
+/// path: a.typ
+#let a = 1
+#let b = 2
+-----
+/// title: Remove unused import `b`
+/// lint: true
+#import "a.typ": a, b
+#a
+/* range -5..-4 */
@@ -0,0 +1,7 @@
+/// path: chapter.typ
+= Chapter
+-----
+/// path: other.typ
+= Other
+-----
+#include "chapter.typ"
@@ -0,0 +1,4 @@
+/// path: chapter.typ
+#let x = y
+-----
+= Main
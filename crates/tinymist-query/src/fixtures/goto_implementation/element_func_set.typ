@@ -0,0 +1,7 @@
+#set heading(numbering: "1.")
+#show heading.where(level: 1): set text(red)
+#show math.frac: it => it
+#set /* position after */text(size: 12pt)
+
+= Introduction
+#heading[Summary]
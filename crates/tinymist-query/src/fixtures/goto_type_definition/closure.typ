@@ -0,0 +1,2 @@
+#let g = x => x
+#(/* position after */ g);
@@ -0,0 +1,3 @@
+#let p = (x: 1, y: 2)
+#let q = p
+#(/* position after */ q);
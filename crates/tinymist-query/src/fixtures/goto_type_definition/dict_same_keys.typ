@@ -0,0 +1,3 @@
+#let p = (x: 1, y: 2)
+#let q = (x: 3, y: 4)
+#(/* position after */ q);
@@ -0,0 +1,9 @@
+/// compile: true
+#set page(width: 200pt, height: 200pt)
+Hello world
+
+/* position after */$ x^2 + y $
+
+#rect(width: 20pt, height: 10pt)
+
+#rotate(90deg)[Rotated]
@@ -0,0 +1,9 @@
+/// compile: true
+#set /* position after */page(width: 200pt, height: 200pt)
+Hello world
+
+$ x^2 + y $
+
+#rect(width: 20pt, height: 10pt)
+
+#rotate(90deg)[Rotated]
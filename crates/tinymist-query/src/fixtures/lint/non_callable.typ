@@ -0,0 +1,2 @@
+#let x = 1
+#x()
\ No newline at end of file
@@ -0,0 +1 @@
+#text(foo: 1)[]
\ No newline at end of file
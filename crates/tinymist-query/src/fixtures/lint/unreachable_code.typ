@@ -0,0 +1,2 @@
+#let f() = { return 1; 2 }
+#f()
\ No newline at end of file
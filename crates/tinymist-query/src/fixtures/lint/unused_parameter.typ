@@ -0,0 +1,2 @@
+#let f(a, b) = b
+#f(1, 2)
\ No newline at end of file
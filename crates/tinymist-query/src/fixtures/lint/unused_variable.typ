@@ -0,0 +1,2 @@
+#let x = 1
+#let _y = 2
\ No newline at end of file
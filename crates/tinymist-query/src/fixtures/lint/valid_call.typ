@@ -0,0 +1,3 @@
+#let f(a, b: 1) = a + b
+#f(1, b: 2)
+#text(size: 12pt)[]
\ No newline at end of file
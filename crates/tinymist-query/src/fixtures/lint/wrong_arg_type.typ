@@ -0,0 +1 @@
+#heading(level: "a")[]
\ No newline at end of file
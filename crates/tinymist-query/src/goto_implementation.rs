@@ -0,0 +1,172 @@
+use typst::foundations::{Element, NativeElement};
+use typst::syntax::Span;
+
+use crate::{
+    prelude::*,
+    ty::{BuiltinTy, Ty, TypeInfo},
+};
+
+/// The [`textDocument/implementation`] request asks the server for the
+/// implementation locations of a symbol at a given text document position.
+///
+/// [`textDocument/implementation`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_implementation
+///
+/// In Typst, an element function is implemented by the `show` and `set` rules
+/// affecting how the element looks, which are found in the workspace.
+///
+/// # Compatibility
+///
+/// This request was introduced in specification version 3.6.0.
+#[derive(Debug, Clone)]
+pub struct GotoImplementationRequest {
+    /// The path of the document to request for.
+    pub path: PathBuf,
+    /// The source code position to request for.
+    pub position: LspPosition,
+}
+
+impl SemanticRequest for GotoImplementationRequest {
+    type Response = GotoDefinitionResponse;
+
+    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+        let cursor = ctx.to_typst_pos(self.position, &source)?;
+        let leaf = LinkedNode::new(source.root()).leaf_at_compat(cursor + 1)?;
+
+        let (node, elem) = match ctx.classify_pos(&source, self.position, 1) {
+            Some(syntax) => {
+                let node = syntax.node().clone();
+                let elem = element_of_span(&ctx.type_check(&source), node.span());
+                (node, elem)
+            }
+            None => (leaf.clone(), None),
+        };
+        let (node, elem) = match elem {
+            Some(elem) => (node, elem),
+            None => markup_element(&leaf)?,
+        };
+        let origin_selection_range = ctx.to_lsp_range(node.range(), &source);
+
+        let mut links = vec![];
+        for fid in ctx.source_files().clone() {
+            let Ok(source) = ctx.source_by_id(fid) else {
+                continue;
+            };
+            let Ok(uri) = ctx.uri_for_id(fid) else {
+                continue;
+            };
+            let ti = ctx.type_check(&source);
+
+            let mut rules = vec![];
+            find_rules(&ti, LinkedNode::new(source.root()), elem, &mut rules);
+            for rule in rules {
+                let range = ctx.to_lsp_range(rule, &source);
+                links.push(LocationLink {
+                    origin_selection_range: Some(origin_selection_range),
+                    target_uri: uri.clone(),
+                    target_range: range,
+                    target_selection_range: range,
+                });
+            }
+        }
+
+        crate::log_debug_ct!("goto_implementation: {elem:?} {links:?}");
+        (!links.is_empty()).then_some(GotoDefinitionResponse::Link(links))
+    }
+}
+
+/// Finds the ranges of the `show` and `set` rules on the element.
+fn find_rules(ti: &TypeInfo, node: LinkedNode, elem: Element, rules: &mut Vec<Range<usize>>) {
+    let target = match node.cast::<ast::Expr>() {
+        Some(ast::Expr::Set(set)) => Some(set.target()),
+        Some(ast::Expr::Show(show)) => show.selector(),
+        _ => None,
+    };
+    if let Some(target) = target.and_then(rule_target) {
+        if element_of_span(ti, target) == Some(elem) {
+            rules.push(node.range());
+        }
+    }
+
+    for child in node.children() {
+        find_rules(ti, child, elem, rules);
+    }
+}
+
+/// Gets the span of the element function selected by the target of a rule,
+/// e.g. `heading` in `heading.where(level: 1)`.
+fn rule_target(target: ast::Expr) -> Option<Span> {
+    match target {
+        ast::Expr::Ident(..) | ast::Expr::FieldAccess(..) => Some(target.span()),
+        ast::Expr::FuncCall(call) => match call.callee() {
+            ast::Expr::FieldAccess(access) if access.field().as_str() == "where" => {
+                rule_target(access.target())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Gets the element function typed on the syntax structure.
+fn element_of_span(ti: &TypeInfo, span: Span) -> Option<Element> {
+    let ty = ti.type_of_span(span)?;
+    element_of_ty(&ti.simplify(ty, false))
+}
+
+fn element_of_ty(ty: &Ty) -> Option<Element> {
+    match ty {
+        Ty::Value(ins) => match &ins.val {
+            Value::Func(func) => func.element(),
+            _ => None,
+        },
+        Ty::Builtin(BuiltinTy::Element(elem)) => Some(*elem),
+        Ty::Union(types) => types.iter().find_map(element_of_ty),
+        _ => None,
+    }
+}
+
+/// Gets the element created by the markup around the leaf, e.g. a heading.
+fn markup_element<'a>(leaf: &LinkedNode<'a>) -> Option<(LinkedNode<'a>, Element)> {
+    let mut node = leaf.clone();
+    loop {
+        let elem = match node.kind() {
+            SyntaxKind::Heading => typst::model::HeadingElem::elem(),
+            SyntaxKind::Strong => typst::model::StrongElem::elem(),
+            SyntaxKind::Emph => typst::model::EmphElem::elem(),
+            SyntaxKind::Raw => typst::text::RawElem::elem(),
+            SyntaxKind::Link => typst::model::LinkElem::elem(),
+            SyntaxKind::Ref => typst::model::RefElem::elem(),
+            SyntaxKind::ListItem => typst::model::ListItem::elem(),
+            SyntaxKind::EnumItem => typst::model::EnumItem::elem(),
+            SyntaxKind::TermItem => typst::model::TermItem::elem(),
+            SyntaxKind::Equation => typst::math::EquationElem::elem(),
+            _ => {
+                node = node.parent()?.clone();
+                continue;
+            }
+        };
+        return Some((node, elem));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("goto_implementation", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let request = GotoImplementationRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request.request(ctx);
+            assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
+        });
+    }
+}
@@ -0,0 +1,165 @@
+use rustc_hash::FxHashSet;
+use typst::syntax::Span;
+
+use crate::{
+    adt::interner::Interned,
+    prelude::*,
+    ty::{Ty, TyCtx, TypeInfo},
+};
+
+/// The [`textDocument/typeDefinition`] request asks the server for the type
+/// definition location of a symbol at a given text document position.
+///
+/// [`textDocument/typeDefinition`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_typeDefinition
+///
+/// In Typst, the type of a variable is inferred by the type checker, and the
+/// type definitions are the functions and the dictionaries producing the type.
+///
+/// # Compatibility
+///
+/// This request was introduced in specification version 3.6.0.
+#[derive(Debug, Clone)]
+pub struct GotoTypeDefinitionRequest {
+    /// The path of the document to request for.
+    pub path: PathBuf,
+    /// The source code position to request for.
+    pub position: LspPosition,
+}
+
+impl SemanticRequest for GotoTypeDefinitionRequest {
+    type Response = GotoDefinitionResponse;
+
+    fn request(self, ctx: &mut LocalContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+        let syntax = ctx.classify_pos(&source, self.position, 1)?;
+        let node = syntax.node();
+        let origin_selection_range = ctx.to_lsp_range(node.range(), &source);
+
+        let ti = ctx.type_check(&source);
+        let ty = ti.type_of_span(node.span())?;
+
+        let mut worker = TypeDefWorker {
+            ctx,
+            visited: FxHashSet::default(),
+            sites: vec![],
+        };
+        worker.check(&ti, &ty);
+
+        let links = worker.sites.into_iter().filter_map(|site| {
+            let fid = site.id()?;
+            let source = ctx.source_by_id(fid).ok()?;
+            let range = site_range(&source, site)?;
+            let range = ctx.to_lsp_range(range, &source);
+            Some(LocationLink {
+                origin_selection_range: Some(origin_selection_range),
+                target_uri: ctx.uri_for_id(fid).ok()?,
+                target_range: range,
+                target_selection_range: range,
+            })
+        });
+        let links = links.collect::<Vec<_>>();
+
+        crate::log_debug_ct!("goto_type_definition: {links:?}");
+        (!links.is_empty()).then_some(GotoDefinitionResponse::Link(links))
+    }
+}
+
+struct TypeDefWorker<'a> {
+    ctx: &'a mut LocalContext,
+    visited: FxHashSet<Interned<Decl>>,
+    sites: Vec<Span>,
+}
+
+impl TypeDefWorker<'_> {
+    /// Finds the definitions producing the type, which are typed by `ti`.
+    fn check(&mut self, ti: &Arc<TypeInfo>, ty: &Ty) {
+        match ty {
+            Ty::Var(var) => {
+                // A variable defined by a function is typed by the function.
+                if matches!(var.def.as_ref(), Decl::Func(..)) {
+                    self.push(var.def.span());
+                    return;
+                }
+                if !self.visited.insert(var.def.clone()) {
+                    return;
+                }
+
+                // The variable imported from another file is typed there.
+                let ti = match var.def.file_id() {
+                    Some(fid) if ti.fid != Some(fid) => self.ctx.type_check_by_id(fid),
+                    _ => ti.clone(),
+                };
+                let Some(bounds) = ti.global_bounds(var, true) else {
+                    return;
+                };
+                for lb in bounds.lbs.iter() {
+                    self.check(&ti, lb);
+                }
+            }
+            Ty::Let(bounds) => {
+                for lb in bounds.lbs.iter() {
+                    self.check(ti, lb);
+                }
+            }
+            Ty::Union(types) => {
+                for ty in types.iter() {
+                    self.check(ti, ty);
+                }
+            }
+            Ty::Func(..) | Ty::With(..) if ti.func_sites.contains_key(ty) => {
+                self.push(ti.func_sites[ty]);
+            }
+            Ty::With(with) => self.check(ti, &with.sig),
+            Ty::Dict(record) => {
+                if let Some(site) = ti.shape_sites.get(record) {
+                    self.push(*site);
+                }
+            }
+            Ty::Value(ins) => {
+                if let Value::Func(func) = &ins.val {
+                    self.push(func.span());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push(&mut self, site: Span) {
+        if !site.is_detached() && !self.sites.contains(&site) {
+            self.sites.push(site);
+        }
+    }
+}
+
+/// Gets the range of the definition at the site. A dictionary is located by
+/// its first key, and is extended to the whole dictionary.
+fn site_range(source: &Source, site: Span) -> Option<Range<usize>> {
+    let node = LinkedNode::new(source.root()).find(site)?;
+    let dict = node
+        .parent()
+        .filter(|parent| matches!(parent.kind(), SyntaxKind::Named | SyntaxKind::Keyed))
+        .and_then(|parent| parent.parent())
+        .filter(|dict| dict.kind() == SyntaxKind::Dict);
+    Some(dict.unwrap_or(&node).range())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("goto_type_definition", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let request = GotoTypeDefinitionRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request.request(ctx);
+            assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
+        });
+    }
+}
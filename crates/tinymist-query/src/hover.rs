@@ -74,26 +74,7 @@ impl StatefulRequest for HoverRequest {
         if let Some(provider) = ctx.analysis.periscope.clone() {
             if let Some(doc) = doc.clone() {
                 let position = jump_from_cursor(&doc.document, &source, cursor);
-                let position = position.or_else(|| {
-                    for idx in 1..100 {
-                        let next_cursor = cursor + idx;
-                        if next_cursor < source.text().len() {
-                            let position = jump_from_cursor(&doc.document, &source, next_cursor);
-                            if position.is_some() {
-                                return position;
-                            }
-                        }
-                        let prev_cursor = cursor.checked_sub(idx);
-                        if let Some(prev_cursor) = prev_cursor {
-                            let position = jump_from_cursor(&doc.document, &source, prev_cursor);
-                            if position.is_some() {
-                                return position;
-                            }
-                        }
-                    }
-
-                    None
-                });
+                let position = position.into_iter().next();
 
                 log::info!("telescope position: {:?}", position);
                 let content = position.and_then(|pos| provider.periscope_at(ctx, doc, pos));
@@ -2,74 +2,214 @@
 
 use std::num::NonZeroUsize;
 
+use rustc_hash::FxHashMap;
+use typst::introspection::Tag;
 use typst::model::Document;
 use typst::{
-    layout::{Frame, FrameItem, Point, Position},
-    syntax::{LinkedNode, Source, Span, SyntaxKind},
+    layout::{Frame, FrameItem, Point, Position, Transform},
+    syntax::{LinkedNode, Source, Span},
 };
 use typst_shim::syntax::LinkedNodeExt;
 
-/// Find the output location in the document for a cursor position.
-pub fn jump_from_cursor(document: &Document, source: &Source, cursor: usize) -> Option<Position> {
-    let node = LinkedNode::new(source.root()).leaf_at_compat(cursor)?;
-    if node.kind() != SyntaxKind::Text {
-        return None;
+/// Find the output locations in the document for a cursor position, at most
+/// one per page.
+///
+/// The innermost node around the cursor that is laid out in the document is
+/// located, e.g. a text, an equation, a figure or an image. If none of them is
+/// found, the nearest text in the same file is located instead.
+pub fn jump_from_cursor(document: &Document, source: &Source, cursor: usize) -> Vec<Position> {
+    let Some(node) = LinkedNode::new(source.root()).leaf_at_compat(cursor) else {
+        return vec![];
+    };
+
+    let mut nearest = Nearest::new(node.span());
+    match jump_from_spans(document, &ancestor_spans(&node), &mut nearest) {
+        Some((_, positions)) => positions,
+        None => nearest.position.into_iter().collect(),
     }
+}
 
-    let mut min_dis = u64::MAX;
-    let mut point = Point::default();
-    let mut ppage = 0usize;
+/// Find the span of the innermost node around the cursor that is laid out in
+/// the document.
+pub fn jump_span_from_cursor(document: &Document, source: &Source, cursor: usize) -> Option<Span> {
+    let node = LinkedNode::new(source.root()).leaf_at_compat(cursor)?;
 
-    let span = node.span();
-    for (idx, page) in document.pages.iter().enumerate() {
-        let t_dis = min_dis;
-        if let Some(point) = find_in_frame(&page.frame, span, &mut min_dis, &mut point) {
-            return Some(Position {
-                page: NonZeroUsize::new(idx + 1)?,
-                point,
-            });
-        }
-        if t_dis != min_dis {
-            ppage = idx;
+    let spans = ancestor_spans(&node);
+    let mut nearest = Nearest::new(Span::detached());
+    let (depth, _) = jump_from_spans(document, &spans, &mut nearest)?;
+    Some(spans[depth])
+}
+
+/// Collect the spans of the node and its ancestors, from the innermost one.
+fn ancestor_spans(node: &LinkedNode) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut ancestor = Some(node);
+    while let Some(node) = ancestor {
+        if !node.span().is_detached() {
+            spans.push(node.span());
         }
+        ancestor = node.parent();
     }
+    spans
+}
 
-    if min_dis == u64::MAX {
-        return None;
+/// Find the output locations of the innermost span laid out in the document,
+/// at most one per page, by walking the pages once. Returns the index of the
+/// span as well.
+fn jump_from_spans(
+    document: &Document,
+    spans: &[Span],
+    nearest: &mut Nearest,
+) -> Option<(usize, Vec<Position>)> {
+    let mut depths = FxHashMap::default();
+    for (depth, span) in spans.iter().enumerate() {
+        depths.entry(*span).or_insert(depth);
     }
 
-    Some(Position {
-        page: NonZeroUsize::new(ppage + 1)?,
-        point,
-    })
+    let mut pages = vec![];
+    for (idx, page) in document.pages.iter().enumerate() {
+        let Some(page_no) = NonZeroUsize::new(idx + 1) else {
+            continue;
+        };
+        nearest.page = page_no;
+        let mut finder = Finder {
+            depths: &depths,
+            found: vec![None; spans.len()],
+            nearest: &mut *nearest,
+        };
+        finder.find(&page.frame, Transform::identity());
+        pages.push((page_no, finder.found));
+    }
+
+    let depth =
+        (0..spans.len()).find(|depth| pages.iter().any(|(_, found)| found[*depth].is_some()))?;
+    let positions = pages.into_iter().filter_map(|(page, found)| {
+        let point = found[depth]?;
+        Some(Position { page, point })
+    });
+    Some((depth, positions.collect()))
 }
 
-/// Find the position of a span in a frame.
-fn find_in_frame(frame: &Frame, span: Span, min_dis: &mut u64, res: &mut Point) -> Option<Point> {
-    for (mut pos, item) in frame.items() {
-        if let FrameItem::Group(group) = item {
-            // TODO: Handle transformation.
-            if let Some(point) = find_in_frame(&group.frame, span, min_dis, res) {
-                return Some(point + pos);
-            }
+/// The text nearest to the span of the cursor, by the distance of the span
+/// numbers in the same file.
+struct Nearest {
+    span: Span,
+    dis: u64,
+    page: NonZeroUsize,
+    position: Option<Position>,
+}
+
+impl Nearest {
+    fn new(span: Span) -> Self {
+        Self {
+            span,
+            dis: u64::MAX,
+            page: NonZeroUsize::MIN,
+            position: None,
         }
+    }
 
-        if let FrameItem::Text(text) = item {
-            for glyph in &text.glyphs {
-                if glyph.span.0 == span {
-                    return Some(pos);
+    fn update(&mut self, span: Span, point: Point) {
+        if self.span.is_detached() || span.id() != self.span.id() {
+            return;
+        }
+
+        let dis = span.number().abs_diff(self.span.number());
+        if dis < self.dis {
+            self.dis = dis;
+            self.position = Some(Position {
+                page: self.page,
+                point,
+            });
+        }
+    }
+}
+
+struct Finder<'a> {
+    /// The candidate spans, by their depths from the cursor.
+    depths: &'a FxHashMap<Span, usize>,
+    /// The first position of each candidate span in the page.
+    found: Vec<Option<Point>>,
+    nearest: &'a mut Nearest,
+}
+
+impl Finder<'_> {
+    /// Find the positions of the candidate spans in a frame, which is placed
+    /// in the page by the transform.
+    fn find(&mut self, frame: &Frame, ts: Transform) {
+        for (pos, item) in frame.items() {
+            let pos = *pos;
+            match item {
+                FrameItem::Group(group) => {
+                    let ts = ts
+                        .pre_concat(Transform::translate(pos.x, pos.y))
+                        .pre_concat(group.transform);
+                    self.find(&group.frame, ts);
                 }
-                if glyph.span.0.id() == span.id() {
-                    let dis = glyph.span.0.number().abs_diff(span.number());
-                    if dis < *min_dis {
-                        *min_dis = dis;
-                        *res = pos;
+                FrameItem::Text(text) => {
+                    let mut x = pos.x;
+                    for glyph in &text.glyphs {
+                        let point = Point::new(x, pos.y).transform(ts);
+                        self.hit(glyph.span.0, point);
+                        self.nearest.update(glyph.span.0, point);
+                        x += glyph.x_advance.at(text.size);
                     }
                 }
-                pos.x += glyph.x_advance.at(text.size);
+                FrameItem::Shape(_, span) | FrameItem::Image(_, _, span) => {
+                    self.hit(*span, pos.transform(ts));
+                }
+                // The start of an element, e.g. an equation or a figure.
+                FrameItem::Tag(Tag::Start(elem)) => self.hit(elem.span(), pos.transform(ts)),
+                FrameItem::Tag(_) | FrameItem::Link(..) => {}
             }
         }
     }
 
-    None
+    fn hit(&mut self, span: Span, point: Point) {
+        if let Some(depth) = self.depths.get(&span) {
+            self.found[*depth].get_or_insert(point);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::layout::Abs;
+
+    use super::*;
+    use crate::syntax::find_module_level_docs;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("jump", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let docs = find_module_level_docs(&source).unwrap_or_default();
+            let properties = get_test_properties(&docs);
+            let doc = compile_doc_for_test(ctx, &properties).unwrap();
+
+            let position = find_test_position(&source);
+            let cursor = ctx.to_typst_pos(position, &source).unwrap() + 1;
+            let positions = jump_from_cursor(&doc.document, &source, cursor);
+
+            for Position { page, point } in &positions {
+                let size = doc.document.pages[page.get() - 1].frame.size();
+                let inside = point.x >= Abs::zero() && point.x <= size.x;
+                let inside = inside && point.y >= Abs::zero() && point.y <= size.y;
+                assert!(inside, "jumped out of the page: {point:?}");
+            }
+
+            let pages = positions
+                .iter()
+                .map(|pos| pos.page.get())
+                .collect::<Vec<_>>();
+            // The text of the node located in the document, which is absent if
+            // the nearest text is located instead.
+            let span = jump_span_from_cursor(&doc.document, &source, cursor);
+            let span = span.and_then(|span| source.range(span));
+            let span = span.map(|range| &source.text()[range]);
+            assert_snapshot!(JsonRepr::new_pure(json!({ "pages": pages, "span": span })));
+        });
+    }
 }
@@ -23,6 +23,8 @@ mod entry;
 pub use entry::*;
 mod diagnostics;
 pub use diagnostics::*;
+mod document_diagnostic;
+pub use document_diagnostic::*;
 mod code_action;
 pub use code_action::*;
 mod code_context;
@@ -51,6 +53,10 @@ mod goto_declaration;
 pub use goto_declaration::*;
 mod goto_definition;
 pub use goto_definition::*;
+mod goto_implementation;
+pub use goto_implementation::*;
+mod goto_type_definition;
+pub use goto_type_definition::*;
 mod hover;
 pub use hover::*;
 mod inlay_hint;
@@ -77,6 +83,8 @@ mod prepare_rename;
 pub use prepare_rename::*;
 mod references;
 pub use references::*;
+mod call_hierarchy;
+pub use call_hierarchy::*;
 
 mod lsp_typst_boundary;
 pub use lsp_typst_boundary::*;
@@ -155,7 +163,10 @@ macro_rules! log_debug_ct {
 #[allow(missing_docs)]
 mod polymorphic {
     use completion::CompletionList;
-    use lsp_types::TextEdit;
+    use lsp_types::{
+        CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall,
+        DocumentDiagnosticReportResult, TextEdit, WorkspaceDiagnosticReportResult,
+    };
     use serde::{Deserialize, Serialize};
     use typst::foundations::Dict;
 
@@ -267,8 +278,15 @@ mod polymorphic {
         Hover(HoverRequest),
         GotoDefinition(GotoDefinitionRequest),
         GotoDeclaration(GotoDeclarationRequest),
+        GotoTypeDefinition(GotoTypeDefinitionRequest),
+        GotoImplementation(GotoImplementationRequest),
         References(ReferencesRequest),
+        PrepareCallHierarchy(PrepareCallHierarchyRequest),
+        CallHierarchyIncomingCalls(CallHierarchyIncomingCallsRequest),
+        CallHierarchyOutgoingCalls(CallHierarchyOutgoingCallsRequest),
         InlayHint(InlayHintRequest),
+        DocumentDiagnostic(DocumentDiagnosticRequest),
+        WorkspaceDiagnostic(WorkspaceDiagnosticRequest),
         DocumentColor(DocumentColorRequest),
         DocumentLink(DocumentLinkRequest),
         DocumentHighlight(DocumentHighlightRequest),
@@ -304,8 +322,15 @@ mod polymorphic {
                 Self::Hover(..) => PinnedFirst,
                 Self::GotoDefinition(..) => PinnedFirst,
                 Self::GotoDeclaration(..) => PinnedFirst,
+                Self::GotoTypeDefinition(..) => PinnedFirst,
+                Self::GotoImplementation(..) => PinnedFirst,
                 Self::References(..) => PinnedFirst,
+                Self::PrepareCallHierarchy(..) => PinnedFirst,
+                Self::CallHierarchyIncomingCalls(..) => PinnedFirst,
+                Self::CallHierarchyOutgoingCalls(..) => PinnedFirst,
                 Self::InlayHint(..) => Unique,
+                Self::DocumentDiagnostic(..) => Unique,
+                Self::WorkspaceDiagnostic(..) => Mergeable,
                 Self::DocumentColor(..) => PinnedFirst,
                 Self::DocumentLink(..) => PinnedFirst,
                 Self::DocumentHighlight(..) => PinnedFirst,
@@ -340,8 +365,15 @@ mod polymorphic {
                 Self::Hover(req) => &req.path,
                 Self::GotoDefinition(req) => &req.path,
                 Self::GotoDeclaration(req) => &req.path,
+                Self::GotoTypeDefinition(req) => &req.path,
+                Self::GotoImplementation(req) => &req.path,
                 Self::References(req) => &req.path,
+                Self::PrepareCallHierarchy(req) => &req.path,
+                Self::CallHierarchyIncomingCalls(req) => &req.path,
+                Self::CallHierarchyOutgoingCalls(req) => &req.path,
                 Self::InlayHint(req) => &req.path,
+                Self::DocumentDiagnostic(req) => &req.path,
+                Self::WorkspaceDiagnostic(..) => return None,
                 Self::DocumentColor(req) => &req.path,
                 Self::DocumentLink(req) => &req.path,
                 Self::DocumentHighlight(req) => &req.path,
@@ -378,8 +410,15 @@ mod polymorphic {
         Hover(Option<Hover>),
         GotoDefinition(Option<GotoDefinitionResponse>),
         GotoDeclaration(Option<GotoDeclarationResponse>),
+        GotoTypeDefinition(Option<GotoDefinitionResponse>),
+        GotoImplementation(Option<GotoDefinitionResponse>),
         References(Option<Vec<LspLocation>>),
+        PrepareCallHierarchy(Option<Vec<CallHierarchyItem>>),
+        CallHierarchyIncomingCalls(Option<Vec<CallHierarchyIncomingCall>>),
+        CallHierarchyOutgoingCalls(Option<Vec<CallHierarchyOutgoingCall>>),
         InlayHint(Option<Vec<InlayHint>>),
+        DocumentDiagnostic(Option<DocumentDiagnosticReportResult>),
+        WorkspaceDiagnostic(Option<WorkspaceDiagnosticReportResult>),
         DocumentColor(Option<Vec<ColorInformation>>),
         DocumentLink(Option<Vec<DocumentLink>>),
         DocumentHighlight(Option<Vec<DocumentHighlight>>),
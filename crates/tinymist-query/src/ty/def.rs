@@ -1182,6 +1182,10 @@ pub struct TypeInfo {
     pub local_binds: snapshot_map::SnapshotMap<DeclExpr, Ty>,
     /// The typing on syntax structures
     pub mapping: FxHashMap<Span, FxHashSet<Ty>>,
+    /// The syntax structures defining the function types
+    pub func_sites: FxHashMap<Ty, Span>,
+    /// The syntax structures defining the dictionary types
+    pub shape_sites: FxHashMap<Interned<RecordTy>, Span>,
 
     pub(super) cano_cache: Mutex<TypeCanoStore>,
 }
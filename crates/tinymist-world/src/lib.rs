@@ -132,24 +132,24 @@ impl CompileOnceArgs {
     /// Get a universe instance from the given arguments.
     pub fn resolve(&self) -> anyhow::Result<LspUniverse> {
         let entry = self.entry()?.try_into()?;
-        let inputs = self
-            .inputs
-            .iter()
-            .map(|(k, v)| (Str::from(k.as_str()), Value::Str(Str::from(v.as_str()))))
-            .collect();
         let fonts = LspUniverseBuilder::resolve_fonts(self.font.clone())?;
         let package = LspUniverseBuilder::resolve_package(
             self.cert.as_deref().map(From::from),
             Some(&self.package),
         );
 
-        LspUniverseBuilder::build(
-            entry,
-            Arc::new(LazyHash::new(inputs)),
-            Arc::new(fonts),
-            package,
-        )
-        .context("failed to create universe")
+        LspUniverseBuilder::build(entry, self.resolve_inputs(), Arc::new(fonts), package)
+            .context("failed to create universe")
+    }
+
+    /// Get the `sys.inputs` from the arguments.
+    pub fn resolve_inputs(&self) -> ImmutDict {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|(k, v)| (Str::from(k.as_str()), Value::Str(Str::from(v.as_str()))))
+            .collect();
+        Arc::new(LazyHash::new(inputs))
     }
 
     /// Get the entry options from the arguments.
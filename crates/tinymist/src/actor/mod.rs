@@ -6,6 +6,7 @@ pub mod preview;
 pub mod typ_client;
 pub mod typ_server;
 
+use std::path::Path;
 use std::sync::Arc;
 
 use reflexo::ImmutPath;
@@ -18,9 +19,10 @@ use tokio::sync::mpsc;
 use typst::layout::Position;
 
 use crate::{
+    project::Project,
     task::{ExportConfig, ExportTask, ExportUserConfig},
-    world::{ImmutDict, LspUniverseBuilder},
-    LanguageState,
+    world::LspUniverseBuilder,
+    CompileConfig, LanguageState,
 };
 use typ_client::{CompileClientActor, CompileHandler};
 use typ_server::{CompileServerActor, CompileServerOpts};
@@ -37,12 +39,64 @@ impl LanguageState {
         self.restart_server(dedicate, entry);
     }
 
+    /// Reload the project manifest and restart the servers of its entries.
+    ///
+    /// The server of an entry is started lazily when a file belonging to it is
+    /// opened.
+    pub fn restart_project(&mut self) {
+        let resolver = self.entry_resolver();
+        let roots = resolver.root_path.iter().chain(resolver.roots.iter());
+        let project = Project::discover(roots);
+        self.project = project;
+
+        let (stale, dedicates) = std::mem::take(&mut self.dedicates)
+            .into_iter()
+            .partition::<Vec<_>, _>(|dedicate| Project::is_group(&dedicate.handle.diag_group));
+        self.dedicates = dedicates;
+        for mut prev in stale {
+            self.client.handle.spawn(async move { prev.settle().await });
+        }
+
+        let opened = self.memory_changes.keys().cloned().collect::<Vec<_>>();
+        for path in opened {
+            self.ensure_project_server(&path);
+        }
+    }
+
+    /// Start the server of the project entry that the file belongs to if it
+    /// is not started yet. Returns whether the file belongs to an entry.
+    pub fn ensure_project_server(&mut self, path: &Path) -> bool {
+        let Some(entry) = self.project.as_ref().and_then(|p| p.entry_of(path)) else {
+            return false;
+        };
+
+        let group = entry.group();
+        let main = entry.main.as_path().into();
+        if self.dedicate(&group).is_none() {
+            log::info!("starting server for project entry {group}, opened file: {path:?}");
+            self.restart_server(&group, Some(main));
+        }
+        true
+    }
+
+    /// Get the compile configuration of the given group.
+    pub fn group_config(&self, group: &str) -> CompileConfig {
+        // Resolves fonts before cloning so that groups share the font resolver.
+        let _ = self.compile_config().determine_fonts();
+        let entry = self.project.as_ref().and_then(|p| p.entry_by_group(group));
+        match entry {
+            Some(entry) => entry.compile_config(self.compile_config()),
+            None => self.compile_config().clone(),
+        }
+    }
+
     /// Restart the server with the given group.
     fn restart_server(&mut self, group: &str, entry: Option<ImmutPath>) {
+        let config = self.group_config(group);
         let server = self.server(
             group.to_owned(),
-            self.entry_resolver().resolve(entry),
-            self.compile_config().determine_inputs(),
+            config.entry_resolver.resolve(entry),
+            config,
             self.vfs_snapshot(),
         );
 
@@ -71,23 +125,25 @@ impl LanguageState {
         &self,
         editor_group: String,
         entry: EntryState,
-        inputs: ImmutDict,
+        config: CompileConfig,
         snapshot: FileChangeSet,
     ) -> CompileClientActor {
         let (intr_tx, intr_rx) = mpsc::unbounded_channel();
+        let inputs = config.determine_inputs();
 
         // Run Export actors before preparing cluster to avoid loss of events
         let export = ExportTask::new(ExportConfig {
             group: editor_group.clone(),
             editor_tx: Some(self.editor_tx.clone()),
             config: ExportUserConfig {
-                output: self.compile_config().output_path.clone(),
-                mode: self.compile_config().export_pdf,
+                output: config.output_path.clone(),
+                mode: config.export_pdf,
             },
             kind: ExportKind::Pdf {
-                creation_timestamp: self.config.compile.determine_creation_timestamp(),
+                creation_timestamp: config.determine_creation_timestamp(),
             },
-            count_words: self.config.compile.notify_status,
+            count_words: config.notify_status,
+            last_exported: Default::default(),
         });
 
         log::info!(
@@ -96,7 +152,7 @@ impl LanguageState {
 
         // Create the compile handler for client consuming results.
         let const_config = self.const_config();
-        let periscope_args = self.compile_config().periscope_args.clone();
+        let periscope_args = config.periscope_args.clone();
         let handle = Arc::new(CompileHandler {
             #[cfg(feature = "preview")]
             inner: std::sync::Arc::new(parking_lot::RwLock::new(None)),
@@ -111,7 +167,7 @@ impl LanguageState {
                 allow_multiline_token: const_config.tokens_multiline_token_support,
                 remove_html: !self.config.support_html_in_markdown,
                 completion_feat: self.config.completion.clone(),
-                color_theme: match self.compile_config().color_theme.as_deref() {
+                color_theme: match config.color_theme.as_deref() {
                     Some("dark") => tinymist_query::ColorTheme::Dark,
                     _ => tinymist_query::ColorTheme::Light,
                 },
@@ -126,15 +182,15 @@ impl LanguageState {
                 stats: Arc::default(),
             }),
 
-            notified_revision: parking_lot::Mutex::new(0),
+            notified_revision: Arc::new(parking_lot::Mutex::new(0)),
         });
 
-        let font_resolver = self.compile_config().determine_fonts();
+        let font_resolver = config.determine_fonts();
         let entry_ = entry.clone();
         let compile_handle = handle.clone();
         let cache = self.cache.clone();
-        let cert_path = self.compile_config().determine_certification_path();
-        let package = self.compile_config().determine_package_opts();
+        let cert_path = config.determine_certification_path();
+        let package = config.determine_package_opts();
 
         self.client.handle.spawn_blocking(move || {
             // Create the world
@@ -161,7 +217,6 @@ impl LanguageState {
         });
 
         // Create the client
-        let config = self.compile_config().clone();
         let client = CompileClientActor::new(handle, config, entry);
         // We do send memory changes instead of initializing compiler with them.
         // This is because there are state recorded inside of the compiler actor, and we
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use lsp_types::notification::Notification;
 use serde::{Deserialize, Serialize};
@@ -8,7 +8,7 @@ use tokio::sync::{mpsc, oneshot};
 use typst_preview::{ControlPlaneMessage, Previewer};
 
 use super::typ_client::CompileHandler;
-use crate::tool::preview::HttpServer;
+use crate::tool::preview::{set_diff_baseline, DiffBaseline, HttpServer};
 
 pub struct PreviewTab {
     /// Task ID
@@ -29,6 +29,8 @@ pub enum PreviewRequest {
     Started(PreviewTab),
     Kill(String, oneshot::Sender<LspResult<JsonValue>>),
     Scroll(String, ControlPlaneMessage),
+    ExportSite(String, PathBuf, oneshot::Sender<LspResult<JsonValue>>),
+    SetDiffBaseline(String, DiffBaseline, oneshot::Sender<LspResult<JsonValue>>),
 }
 
 pub struct PreviewActor {
@@ -89,6 +91,12 @@ impl PreviewActor {
                 PreviewRequest::Scroll(task_id, req) => {
                     self.scroll(task_id, req).await;
                 }
+                PreviewRequest::ExportSite(task_id, dir, tx) => {
+                    self.export_site(task_id, dir, tx);
+                }
+                PreviewRequest::SetDiffBaseline(task_id, baseline, tx) => {
+                    self.set_diff_baseline(task_id, baseline, tx);
+                }
             }
         }
     }
@@ -96,6 +104,65 @@ impl PreviewActor {
     async fn scroll(&mut self, task_id: String, req: ControlPlaneMessage) -> Option<()> {
         self.tabs.get(&task_id)?.ctl_tx.send(req).ok()
     }
+
+    fn export_site(
+        &self,
+        task_id: String,
+        dir: PathBuf,
+        tx: oneshot::Sender<LspResult<JsonValue>>,
+    ) {
+        let Some(tab) = self.tabs.get(&task_id) else {
+            let _ = tx.send(Err(internal_error("task not found")));
+            return;
+        };
+        let Some(document) = tab.previewer.document() else {
+            let _ = tx.send(Err(internal_error("document is not compiled yet")));
+            return;
+        };
+
+        self.client.handle.spawn_blocking(move || {
+            log::info!("PreviewTask({task_id}): exporting site to {dir:?}");
+            let res = typst_preview::export_site(&document, &dir);
+            let res = res.map_err(|err| {
+                log::error!("PreviewTask({task_id}): failed to export site: {err}");
+                internal_error("failed to export site")
+            });
+            let _ = tx.send(res.map(|_| JsonValue::Null));
+        });
+    }
+
+    fn set_diff_baseline(
+        &self,
+        task_id: String,
+        baseline: DiffBaseline,
+        tx: oneshot::Sender<LspResult<JsonValue>>,
+    ) {
+        let Some(tab) = self.tabs.get(&task_id) else {
+            let _ = tx.send(Err(internal_error("task not found")));
+            return;
+        };
+        let diff = tab.previewer.diff_handle();
+        let compile_handler = tab.compile_handler.clone();
+        let addr = tab.srv.addr;
+
+        self.client.handle.spawn(async move {
+            log::info!("PreviewTask({task_id}): comparing against {baseline:?}");
+            let res = set_diff_baseline(&diff, &compile_handler, baseline).await;
+            let res = res.map_err(|err| {
+                log::error!("PreviewTask({task_id}): failed to set diff baseline: {err:#}");
+                internal_error(format!("failed to set diff baseline: {err:#}"))
+            });
+            let res = res.map(|_| {
+                let changed_pages = diff.diff().map(|diff| diff.changed_pages());
+                serde_json::json!({
+                    "staticServerAddr": addr.to_string(),
+                    "diffPath": "/diff",
+                    "changedPages": changed_pages.unwrap_or_default(),
+                })
+            });
+            let _ = tx.send(res);
+        });
+    }
 }
 
 #[derive(Serialize, Deserialize)]
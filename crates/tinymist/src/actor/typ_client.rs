@@ -28,7 +28,7 @@ use anyhow::bail;
 use log::{error, info, trace};
 use reflexo_typst::{
     error::prelude::*, typst::prelude::*, vfs::notify::MemoryEvent, world::EntryState,
-    CompileReport, EntryReader, Error, ImmutPath, TaskInputs,
+    CompileReport, EntryReader, Error, ImmutPath, TaskInputs, TypstDocument,
 };
 use sync_lsp::{just_future, QueryFuture};
 use tinymist_query::{
@@ -66,7 +66,7 @@ pub struct CompileHandler {
     pub(crate) export: ExportTask,
     pub(crate) editor_tx: EditorSender,
 
-    pub(crate) notified_revision: parking_lot::Mutex<usize>,
+    pub(crate) notified_revision: Arc<parking_lot::Mutex<usize>>,
 }
 
 impl CompileHandler {
@@ -103,6 +103,11 @@ impl CompileHandler {
         Ok(ArtifactSnap { rx })
     }
 
+    /// Get the document last exported by the compiler thread
+    pub fn last_exported(&self) -> Option<Arc<TypstDocument>> {
+        self.export.last_exported()
+    }
+
     pub fn flush_compile(&self) {
         // todo: better way to flush compile
         let _ = self.intr_tx.send(Interrupt::Compile);
@@ -124,14 +129,7 @@ impl CompileHandler {
     }
 
     fn push_diagnostics(&self, revision: usize, diagnostics: Option<DiagnosticsMap>) {
-        let dv = DocVersion {
-            group: self.diag_group.clone(),
-            revision,
-        };
-        let res = self.editor_tx.send(EditorRequest::Diag(dv, diagnostics));
-        if let Err(err) = res {
-            error!("failed to send diagnostics: {err:#}");
-        }
+        push_diagnostics(&self.editor_tx, &self.diag_group, revision, diagnostics);
     }
 
     fn notify_diagnostics(
@@ -154,7 +152,31 @@ impl CompileHandler {
         // todo: check all errors in this file
         let detached = entry.is_inactive();
         let valid = !detached;
-        self.push_diagnostics(revision, valid.then_some(diagnostics));
+        if !valid {
+            self.push_diagnostics(revision, None);
+            return;
+        }
+        self.push_diagnostics(revision, Some(diagnostics.clone()));
+
+        // Lints all the dependencies, which is slow, so it doesn't block the
+        // compile thread. The lints are dropped if a newer revision is
+        // notified meanwhile.
+        let analysis = self.analysis.clone();
+        let world = world.clone();
+        let editor_tx = self.editor_tx.clone();
+        let group = self.diag_group.clone();
+        let notified_revision = self.notified_revision.clone();
+        rayon::spawn(move || {
+            let mut diagnostics = diagnostics;
+            let mut ctx = analysis.snapshot(world);
+            let lints = tinymist_query::lint_diagnostics(&mut ctx);
+            tinymist_query::merge_lint_diagnostics(&mut diagnostics, lints);
+
+            let n_rev = notified_revision.lock();
+            if *n_rev == revision {
+                push_diagnostics(&editor_tx, &group, revision, Some(diagnostics));
+            }
+        });
     }
 
     // todo: multiple preview support
@@ -181,6 +203,22 @@ impl CompileHandler {
     }
 }
 
+fn push_diagnostics(
+    editor_tx: &EditorSender,
+    group: &str,
+    revision: usize,
+    diagnostics: Option<DiagnosticsMap>,
+) {
+    let dv = DocVersion {
+        group: group.to_owned(),
+        revision,
+    };
+    let res = editor_tx.send(EditorRequest::Diag(dv, diagnostics));
+    if let Err(err) = res {
+        error!("failed to send diagnostics: {err:#}");
+    }
+}
+
 impl CompilationHandle<LspCompilerFeat> for CompileHandler {
     fn status(&self, revision: usize, _rep: CompileReport) {
         // todo: seems to duplicate with CompileStatus
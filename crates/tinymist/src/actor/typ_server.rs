@@ -15,8 +15,8 @@ use reflexo_typst::{
     typst::prelude::EcoVec,
     vfs::notify::{FilesystemEvent, MemoryEvent, NotifyMessage, UpstreamUpdateEvent},
     watch_deps, CompileEnv, CompileReport, Compiler, CompilerFeat, CompilerUniverse, CompilerWorld,
-    ConsoleDiagReporter, EntryReader, GenericExporter, Revising, TaskInputs, TypstDocument,
-    WorldDeps,
+    ConsoleDiagReporter, EntryReader, GenericExporter, ImmutPath, Revising, TaskInputs,
+    TypstDocument, WorldDeps,
 };
 use typst::diag::{SourceDiagnostic, SourceResult};
 
@@ -135,6 +135,9 @@ impl<F: CompilerFeat> CompiledArtifact<F> {
 pub trait CompilationHandle<F: CompilerFeat>: Send + Sync + 'static {
     fn status(&self, revision: usize, rep: CompileReport);
     fn notify_compile(&self, res: &CompiledArtifact<F>, rep: CompileReport);
+    /// Notifies the snapshot revised while the entry is suspended, from which
+    /// the tasks on other entries can be compiled.
+    fn notify_suspended(&self, _snap: &CompileSnapshot<F>) {}
 }
 
 impl<F: CompilerFeat + Send + Sync + 'static> CompilationHandle<F>
@@ -178,6 +181,8 @@ pub enum Interrupt<F: CompilerFeat> {
     /// Request compiler to respond a snapshot with at least a compilation
     /// happens on or after current revision.
     CurrentRead(oneshot::Sender<SucceededArtifact<F>>),
+    /// Dependencies of the tasks compiled from the snapshots.
+    Dependencies(Vec<ImmutPath>),
     /// Memory file changes.
     Memory(MemoryEvent),
     /// File system event.
@@ -451,6 +456,7 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileServerActor<F> {
         curr_reads: &mut Vec<oneshot::Sender<SucceededArtifact<F>>>,
         is_once: bool,
     ) -> Option<CompiledArtifact<F>> {
+        let revised = reason.any();
         self.suspended_reason.see(reason);
         let reason = std::mem::take(&mut self.suspended_reason);
         let start = reflexo::time::now();
@@ -461,6 +467,9 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileServerActor<F> {
 
         if self.suspended {
             self.suspended_reason.see(reason);
+            if revised {
+                self.compile_handle.notify_suspended(&compiling);
+            }
 
             for reader in curr_reads.drain(..) {
                 let _ = reader.send(SucceededArtifact::Suspend(compiling.clone()));
@@ -615,6 +624,10 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileServerActor<F> {
                 self.process_compile(artifact, send);
                 self.process_lagged_compile()
             }
+            Interrupt::Dependencies(deps) => {
+                send(Notify(NotifyMessage::SyncDependency(deps)));
+                no_reason()
+            }
             Interrupt::Memory(event) => {
                 log::debug!("CompileServerActor: memory event incoming");
 
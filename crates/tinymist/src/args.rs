@@ -1,7 +1,7 @@
 use std::path::Path;
 
 use once_cell::sync::Lazy;
-use sync_lsp::transport::MirrorArgs;
+use sync_lsp::transport::{ListenAddr, MirrorArgs};
 
 use tinymist::{CompileFontArgs, CompileOnceArgs};
 
@@ -26,6 +26,11 @@ pub enum Commands {
     /// Runs language server for tracing some typst program.
     #[clap(hide(true))]
     TraceLsp(TraceLspArgs),
+    /// Compiles a document and exports it like the language server does
+    #[clap(alias = "export")]
+    Compile(tinymist::tool::compile::CompileCliArgs),
+    /// Checks documents and reports diagnostics in machine-readable formats
+    Check(tinymist::tool::check::CheckCliArgs),
     /// Runs preview server
     #[cfg(feature = "preview")]
     Preview(tinymist::tool::preview::PreviewCliArgs),
@@ -134,6 +139,10 @@ pub struct TraceLspArgs {
 pub struct LspArgs {
     #[clap(flatten)]
     pub mirror: MirrorArgs,
+    /// Listens for clients on the address instead of using stdio, e.g.
+    /// `tcp://127.0.0.1:9257` or `ws://127.0.0.1:9257`
+    #[clap(long, value_name = "ADDR")]
+    pub listen: Option<ListenAddr>,
     #[clap(flatten)]
     pub font: CompileFontArgs,
 }
@@ -332,6 +332,33 @@ impl LanguageState {
         self.preview.scroll(task_id, req)
     }
 
+    /// Export the document of a preview instance to a static site.
+    #[cfg(feature = "preview")]
+    pub fn export_preview_site(&mut self, mut args: Vec<JsonValue>) -> AnySchedulableResponse {
+        let task_id = get_arg!(args[0] as String);
+        let dir = get_arg!(args[1] as PathBuf);
+        if !dir.is_absolute() {
+            return Err(invalid_params("site directory must be absolute path"));
+        }
+
+        self.preview.export_site(task_id, dir)
+    }
+
+    /// Set the baseline that the document of a preview instance is compared
+    /// against.
+    #[cfg(feature = "preview")]
+    pub fn set_preview_diff_baseline(
+        &mut self,
+        mut args: Vec<JsonValue>,
+    ) -> AnySchedulableResponse {
+        use crate::tool::preview::DiffBaseline;
+
+        let task_id = get_arg!(args[0] as String);
+        let baseline = get_arg!(args[1] as DiffBaseline);
+
+        self.preview.set_diff_baseline(task_id, baseline)
+    }
+
     /// Initialize a new template.
     pub fn init_template(&mut self, mut args: Vec<JsonValue>) -> AnySchedulableResponse {
         use crate::tool::package::{self, TemplateSource};
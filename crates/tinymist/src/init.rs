@@ -144,6 +144,7 @@ impl Initializer for SuperInit {
             err,
         } = self;
         let const_config = config.const_config.clone();
+        let pull_diagnostics = config.pull_diagnostics;
         // Bootstrap server
         let service = LanguageState::main(client, config, err.is_none());
 
@@ -156,6 +157,29 @@ impl Initializer for SuperInit {
         });
         let document_formatting_provider =
             (!const_config.doc_fmt_dynamic_registration).then_some(OneOf::Left(true));
+        let document_range_formatting_provider =
+            (!const_config.doc_fmt_dynamic_registration).then_some(OneOf::Left(true));
+        let document_on_type_formatting_provider = (!const_config.doc_fmt_dynamic_registration)
+            .then(|| DocumentOnTypeFormattingOptions {
+                first_trigger_character: ON_TYPE_FORMATTING_TRIGGERS[0].to_owned(),
+                more_trigger_character: Some(
+                    ON_TYPE_FORMATTING_TRIGGERS[1..]
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect(),
+                ),
+            });
+
+        // Diagnostics are still pushed after compilation, so pulling them is opt-in to avoid
+        // reporting them twice.
+        let diagnostic_provider = pull_diagnostics.then(|| {
+            DiagnosticServerCapabilities::Options(DiagnosticOptions {
+                identifier: Some("tinymist".to_owned()),
+                inter_file_dependencies: true,
+                workspace_diagnostics: true,
+                work_done_progress_options: Default::default(),
+            })
+        });
 
         let file_operations = const_config.notify_will_rename_files.then(|| {
             WorkspaceFileOperationsServerCapabilities {
@@ -190,7 +214,11 @@ impl Initializer for SuperInit {
                     },
                 }),
                 definition_provider: Some(OneOf::Left(true)),
+                type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+                implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
                 references_provider: Some(OneOf::Left(true)),
+                call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+                diagnostic_provider,
                 completion_provider: Some(CompletionOptions {
                     // Please update the language-configuration.json if you are changing this
                     // setting.
@@ -248,6 +276,8 @@ impl Initializer for SuperInit {
                     file_operations,
                 }),
                 document_formatting_provider,
+                document_range_formatting_provider,
+                document_on_type_formatting_provider,
                 inlay_hint_provider: Some(OneOf::Left(true)),
                 code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
                 code_lens_provider: Some(CodeLensOptions {
@@ -267,6 +297,9 @@ impl Initializer for SuperInit {
     }
 }
 
+/// The characters closing a block, which trigger formatting of the block.
+pub(crate) const ON_TYPE_FORMATTING_TRIGGERS: &[&str] = &["}", "]"];
+
 // region Configuration Items
 const CONFIG_ITEMS: &[&str] = &[
     "tinymist",
@@ -276,6 +309,7 @@ const CONFIG_ITEMS: &[&str] = &[
     "semanticTokens",
     "formatterMode",
     "formatterPrintWidth",
+    "pullDiagnostics",
     "completion",
     "fontPaths",
     "systemFonts",
@@ -303,6 +337,9 @@ pub struct Config {
     pub formatter_print_width: Option<u32>,
     /// Whether to remove html from markup content in responses.
     pub support_html_in_markdown: bool,
+    /// Whether to serve pull diagnostics requests. It is only read at
+    /// initialization.
+    pub pull_diagnostics: bool,
     /// Tinymist's completion features.
     pub completion: CompletionFeat,
 }
@@ -386,6 +423,7 @@ impl Config {
         assign_config!(formatter_mode := "formatterMode"?: FormatterMode);
         assign_config!(formatter_print_width := "formatterPrintWidth"?: Option<u32>);
         assign_config!(support_html_in_markdown := "supportHtmlInMarkdown"?: bool);
+        assign_config!(pull_diagnostics := "pullDiagnostics"?: bool);
         assign_config!(completion := "completion"?: CompletionFeat);
         assign_config!(completion.trigger_suggest := "triggerSuggest"?: bool);
         assign_config!(completion.trigger_parameter_hints := "triggerParameterHints"?: bool);
@@ -425,6 +463,8 @@ pub struct ConstConfig {
     pub cfg_change_registration: bool,
     /// Allow notifying workspace/didRenameFiles
     pub notify_will_rename_files: bool,
+    /// Allow dynamic registration of watched files.
+    pub watch_files_dynamic_registration: bool,
     /// Allow dynamic registration of semantic tokens.
     pub tokens_dynamic_registration: bool,
     /// Allow overlapping tokens.
@@ -461,6 +501,7 @@ impl From<&InitializeParams> for ConstConfig {
 
         let workspace = params.capabilities.workspace.as_ref();
         let file_operations = try_(|| workspace?.file_operations.as_ref());
+        let watch_files = try_(|| workspace?.did_change_watched_files.as_ref());
         let doc = params.capabilities.text_document.as_ref();
         let sema = try_(|| doc?.semantic_tokens.as_ref());
         let fold = try_(|| doc?.folding_range.as_ref());
@@ -470,6 +511,7 @@ impl From<&InitializeParams> for ConstConfig {
             position_encoding,
             cfg_change_registration: try_or(|| workspace?.configuration, false),
             notify_will_rename_files: try_or(|| file_operations?.will_rename, false),
+            watch_files_dynamic_registration: try_or(|| watch_files?.dynamic_registration, false),
             tokens_dynamic_registration: try_or(|| sema?.dynamic_registration, false),
             tokens_overlapping_token_support: try_or(|| sema?.overlapping_token_support, false),
             tokens_multiline_token_support: try_or(|| sema?.multiline_token_support, false),
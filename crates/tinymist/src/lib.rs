@@ -21,6 +21,7 @@
 mod actor;
 mod cmd;
 mod init;
+mod project;
 mod resource;
 mod server;
 mod stats;
@@ -30,6 +31,7 @@ pub mod tool;
 mod utils;
 
 pub use init::*;
+pub use project::*;
 pub use server::*;
 pub use sync_lsp::LspClient;
 pub use tinymist_query as query;
@@ -14,14 +14,14 @@ use clap::Parser;
 use clap_builder::CommandFactory;
 use clap_complete::generate;
 use futures::future::MaybeDone;
-use lsp_server::RequestId;
+use lsp_server::{Connection, RequestId};
 use once_cell::sync::Lazy;
 use reflexo::ImmutPath;
 use reflexo_typst::{package::PackageSpec, TaskInputs, TypstDict};
 use serde_json::Value as JsonValue;
 use sync_lsp::{
     internal_error,
-    transport::{with_stdio_transport, MirrorArgs},
+    transport::{with_listen_transport, with_stdio_transport, MirrorArgs},
     LspBuilder, LspClientRoot, LspResult,
 };
 use tinymist::{CompileConfig, Config, LanguageState, RegularInit, SuperInit, UserActionTask};
@@ -82,6 +82,12 @@ fn main() -> anyhow::Result<()> {
         Commands::Query(query_cmds) => query_main(query_cmds),
         Commands::Lsp(args) => lsp_main(args),
         Commands::TraceLsp(args) => trace_lsp_main(args),
+        Commands::Compile(args) => {
+            use tinymist::tool::compile::compile_main;
+
+            RUNTIMES.tokio_runtime.block_on(compile_main(args))
+        }
+        Commands::Check(args) => tinymist::tool::check::check_main(args),
         #[cfg(feature = "preview")]
         Commands::Preview(args) => {
             #[cfg(feature = "preview")]
@@ -114,20 +120,27 @@ pub fn lsp_main(args: LspArgs) -> anyhow::Result<()> {
     log::info!("tinymist version information: {pairs:?}");
     log::info!("starting Language server: {args:#?}");
 
-    let is_replay = !args.mirror.replay.is_empty();
-    with_stdio_transport(args.mirror.clone(), |conn| {
+    let serve = |conn: Connection, is_replay: bool| {
         let client = LspClientRoot::new(RUNTIMES.tokio_runtime.handle().clone(), conn.sender);
         LanguageState::install(LspBuilder::new(
             RegularInit {
                 client: client.weak().to_typed(),
-                font_opts: args.font,
+                font_opts: args.font.clone(),
                 exec_cmds: Vec::new(),
             },
             client.weak(),
         ))
         .build()
         .start(conn.receiver, is_replay)
-    })?;
+    };
+
+    match &args.listen {
+        Some(listen) => with_listen_transport(listen, |conn| serve(conn, false))?,
+        None => {
+            let is_replay = !args.mirror.replay.is_empty();
+            with_stdio_transport(args.mirror.clone(), |conn| serve(conn, is_replay))?
+        }
+    }
 
     log::info!("language server did shut down");
     Ok(())
@@ -0,0 +1,255 @@
+//! The project manifest, which declares the independent documents of a
+//! workspace.
+//!
+//! A manifest is a `tinymist.toml` file in a workspace root:
+//!
+//! ```toml
+//! [[entry]]
+//! name = "thesis"
+//! main = "thesis/main.typ"
+//! output-path = "$root/target/$dir/$name"
+//!
+//! [[entry]]
+//! name = "slides"
+//! main = "slides/main.typ"
+//! font-paths = ["slides/fonts"]
+//! inputs = { handout = "false" }
+//! ```
+//!
+//! Each entry is compiled by a dedicated compile group, and an opened file is
+//! served by the entry it belongs to.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use reflexo::path::PathClean;
+use reflexo_typst::ImmutPath;
+use serde::Deserialize;
+use typst::foundations::IntoValue;
+use typst_shim::utils::LazyHash;
+
+use crate::{CompileConfig, PathPattern};
+
+/// The file name of the project manifest.
+pub const PROJECT_MANIFEST: &str = "tinymist.toml";
+
+/// The prefix of the compile groups serving project entries.
+const PROJECT_GROUP_PREFIX: &str = "entry:";
+
+/// The content of a project manifest.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProjectManifest {
+    /// The entries of the project.
+    #[serde(default, rename = "entry")]
+    pub entries: Vec<ProjectEntry>,
+}
+
+/// An independent document declared in the project manifest.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProjectEntry {
+    /// The unique name of the entry.
+    pub name: String,
+    /// The path to the main file, relative to the manifest.
+    pub main: PathBuf,
+    /// The `sys.inputs` for the entry.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, String>,
+    /// The font paths for the entry, relative to the manifest. They are
+    /// searched before the font paths from the configuration.
+    #[serde(default)]
+    pub font_paths: Vec<PathBuf>,
+    /// The path pattern to store the artifacts of the entry.
+    #[serde(default)]
+    pub output_path: Option<PathPattern>,
+}
+
+impl ProjectEntry {
+    /// The compile group serving the entry.
+    pub fn group(&self) -> String {
+        format!("{PROJECT_GROUP_PREFIX}{}", self.name)
+    }
+
+    /// Derives the compile configuration of the entry from the configuration
+    /// of the editor.
+    pub fn compile_config(&self, base: &CompileConfig) -> CompileConfig {
+        let mut config = base.clone();
+        config.entry_resolver.entry = Some(self.main.as_path().into());
+        config.has_default_entry_path = true;
+
+        if let Some(output_path) = &self.output_path {
+            config.output_path = output_path.clone();
+        }
+
+        if !self.font_paths.is_empty() {
+            let base_paths = config.determine_font_opts().font_paths;
+            config.font_paths = self.font_paths.iter().cloned().chain(base_paths).collect();
+            config.fonts = Default::default();
+        }
+
+        if !self.inputs.is_empty() {
+            let mut inputs = (**config.lsp_inputs).clone();
+            for (key, value) in &self.inputs {
+                inputs.insert(key.as_str().into(), value.as_str().into_value());
+            }
+            config.lsp_inputs = Arc::new(LazyHash::new(inputs));
+        }
+
+        config
+    }
+}
+
+/// A loaded project manifest.
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// The directory containing the manifest.
+    pub root: ImmutPath,
+    /// The entries of the project, whose paths are absolute.
+    pub entries: Vec<ProjectEntry>,
+}
+
+impl Project {
+    /// Finds and loads the project manifest in the given root directories.
+    pub fn discover<'a>(roots: impl IntoIterator<Item = &'a ImmutPath>) -> Option<Self> {
+        for root in roots {
+            let manifest = root.join(PROJECT_MANIFEST);
+            if !manifest.is_file() {
+                continue;
+            }
+
+            match Self::load(&manifest) {
+                Ok(project) => return Some(project),
+                Err(err) => log::error!("failed to load project manifest {manifest:?}: {err:#}"),
+            }
+        }
+
+        None
+    }
+
+    /// Loads the project manifest at the given path.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).context("failed to read manifest")?;
+        let root = path.parent().context("manifest has no parent directory")?;
+        Self::parse(root, &content)
+    }
+
+    /// Parses the content of a project manifest located in the root.
+    pub fn parse(root: &Path, content: &str) -> anyhow::Result<Self> {
+        let manifest: ProjectManifest = toml::from_str(content).context("invalid manifest")?;
+
+        let mut entries = manifest.entries;
+        for (idx, entry) in entries.iter().enumerate() {
+            if entries[..idx].iter().any(|prev| prev.name == entry.name) {
+                anyhow::bail!("duplicate entry name: {}", entry.name);
+            }
+        }
+        for entry in entries.iter_mut() {
+            entry.main = root.join(&entry.main).clean();
+            for path in entry.font_paths.iter_mut() {
+                *path = root.join(&*path).clean();
+            }
+        }
+
+        Ok(Self {
+            root: root.into(),
+            entries,
+        })
+    }
+
+    /// Checks whether the compile group serves a project entry.
+    pub fn is_group(group: &str) -> bool {
+        group.starts_with(PROJECT_GROUP_PREFIX)
+    }
+
+    /// Gets the entry by its compile group.
+    pub fn entry_by_group(&self, group: &str) -> Option<&ProjectEntry> {
+        let name = group.strip_prefix(PROJECT_GROUP_PREFIX)?;
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Picks the entry that a file belongs to.
+    ///
+    /// A main file belongs to its entry. Otherwise, the file belongs to the
+    /// entry whose main file is in the closest ancestor directory of it.
+    pub fn entry_of(&self, path: &Path) -> Option<&ProjectEntry> {
+        if let Some(entry) = self.entries.iter().find(|entry| entry.main == path) {
+            return Some(entry);
+        }
+
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let dir = entry.main.parent()?;
+                path.starts_with(dir)
+                    .then(|| (dir.components().count(), entry))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, entry)| entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[[entry]]
+name = "thesis"
+main = "thesis/main.typ"
+output-path = "$root/target/$dir/$name"
+
+[[entry]]
+name = "slides"
+main = "slides/main.typ"
+font-paths = ["slides/fonts"]
+inputs = { handout = "false" }
+"#;
+
+    fn root() -> &'static Path {
+        Path::new(if cfg!(windows) { "C:\\root" } else { "/root" })
+    }
+
+    #[test]
+    fn test_parse() {
+        let project = Project::parse(root(), MANIFEST).unwrap();
+
+        assert_eq!(project.entries.len(), 2);
+        let slides = &project.entries[1];
+        assert_eq!(slides.group(), "entry:slides");
+        assert_eq!(slides.main, root().join("slides/main.typ").clean());
+        assert_eq!(slides.font_paths, vec![root().join("slides/fonts").clean()]);
+        assert_eq!(
+            slides.inputs.get("handout").map(String::as_str),
+            Some("false")
+        );
+    }
+
+    #[test]
+    fn test_duplicate_name() {
+        let manifest = "[[entry]]\nname = \"a\"\nmain = \"a.typ\"\n".repeat(2);
+        assert!(Project::parse(root(), &manifest).is_err());
+    }
+
+    #[test]
+    fn test_entry_of() {
+        let project = Project::parse(root(), MANIFEST).unwrap();
+        let entry_of = |path: &str| {
+            let path = root().join(path).clean();
+            project.entry_of(&path).map(|entry| entry.name.as_str())
+        };
+
+        assert_eq!(entry_of("thesis/main.typ"), Some("thesis"));
+        assert_eq!(entry_of("thesis/chapters/intro.typ"), Some("thesis"));
+        assert_eq!(entry_of("slides/main.typ"), Some("slides"));
+        assert_eq!(entry_of("lib/utils.typ"), None);
+        assert_eq!(
+            project
+                .entry_by_group("entry:thesis")
+                .map(|e| e.main.clone()),
+            Some(root().join("thesis/main.typ").clean())
+        );
+    }
+}
@@ -9,13 +9,16 @@ use anyhow::anyhow;
 use anyhow::Context;
 use log::{error, info, trace};
 use lsp_server::RequestId;
-use lsp_types::request::{GotoDeclarationParams, WorkspaceConfiguration};
+use lsp_types::request::{
+    GotoDeclarationParams, GotoImplementationParams, GotoTypeDefinitionParams,
+    WorkspaceConfiguration,
+};
 use lsp_types::*;
 use once_cell::sync::OnceCell;
 use reflexo_typst::{
     error::prelude::*,
     vfs::notify::{FileChangeSet, MemoryEvent},
-    Bytes, Error, ImmutPath, TaskInputs, Time,
+    Bytes, EntryReader, Error, ImmutPath, TaskInputs, Time,
 };
 use request::{RegisterCapability, UnregisterCapability};
 use serde::{Deserialize, Serialize};
@@ -23,16 +26,19 @@ use serde_json::{Map, Value as JsonValue};
 use sync_lsp::*;
 use task::{CacheTask, ExportUserConfig, FormatTask, FormatterConfig, UserActionTask};
 use tinymist_query::{
-    to_typst_range, CompilerQueryRequest, CompilerQueryResponse, FoldRequestFeature,
-    PositionEncoding, SyntaxRequest,
+    to_typst_position, to_typst_range, CompilerQueryRequest, CompilerQueryResponse,
+    FoldRequestFeature, PositionEncoding, SyntaxRequest,
 };
 use tinymist_query::{EntryResolver, PageSelection};
 use tokio::sync::mpsc;
-use typst::{diag::FileResult, syntax::Source};
+use typst::diag::FileResult;
+use typst::syntax::{LinkedNode, Source, SyntaxKind};
+use typst_shim::syntax::LinkedNodeExt;
 
 use super::{init::*, *};
 use crate::actor::editor::EditorRequest;
 use crate::actor::typ_client::CompileClientActor;
+use crate::project::{Project, PROJECT_MANIFEST};
 
 pub(crate) use futures::Future;
 
@@ -83,6 +89,8 @@ pub struct LanguageState {
     pub primary: Option<CompileClientActor>,
     /// The compiler actors for tasks
     pub dedicates: Vec<CompileClientActor>,
+    /// The project manifest of the workspace.
+    pub project: Option<Project>,
     /// The formatter tasks running in backend, which will be scheduled by async
     /// runtime.
     pub formatter: FormatTask,
@@ -108,6 +116,7 @@ impl LanguageState {
             editor_tx,
             primary: None,
             dedicates: Vec::new(),
+            project: None,
             memory_changes: HashMap::new(),
             #[cfg(feature = "preview")]
             preview: tool::preview::PreviewState::new(client.cast(|s| &mut s.preview)),
@@ -142,6 +151,7 @@ impl LanguageState {
             );
 
             service.restart_primary();
+            service.restart_project();
 
             // Run the cluster in the background after we referencing it
             client.handle.spawn(editor_actor.run());
@@ -170,6 +180,11 @@ impl LanguageState {
         self.primary.as_ref().expect("primary")
     }
 
+    /// Get the compile server of the project entry that the file belongs to.
+    pub fn project_server(&self, path: &Path) -> Option<&CompileClientActor> {
+        self.dedicate(&self.project.as_ref()?.entry_of(path)?.group())
+    }
+
     /// Get the task-dedicated compile server.
     pub fn dedicate(&self, group: &str) -> Option<&CompileClientActor> {
         self.dedicates
@@ -194,7 +209,12 @@ impl LanguageState {
         let provider = provider
             .with_command("tinymist.doStartPreview", State::start_preview)
             .with_command("tinymist.doKillPreview", State::kill_preview)
-            .with_command("tinymist.scrollPreview", State::scroll_preview);
+            .with_command("tinymist.scrollPreview", State::scroll_preview)
+            .with_command("tinymist.doExportPreviewSite", State::export_preview_site)
+            .with_command(
+                "tinymist.doSetPreviewDiffBaseline",
+                State::set_preview_diff_baseline,
+            );
 
         // todo: .on_sync_mut::<notifs::Cancel>(handlers::handle_cancel)?
         let mut provider = provider
@@ -207,9 +227,13 @@ impl LanguageState {
             .with_request_::<DocumentSymbolRequest>(State::document_symbol)
             // Sync for low latency
             .with_request_::<Formatting>(State::formatting)
+            .with_request_::<RangeFormatting>(State::range_formatting)
+            .with_request_::<OnTypeFormatting>(State::on_type_formatting)
             .with_request_::<SelectionRangeRequest>(State::selection_range)
             // latency insensitive
             .with_request_::<InlayHintRequest>(State::inlay_hint)
+            .with_request_::<DocumentDiagnosticRequest>(State::document_diagnostic)
+            .with_request_::<WorkspaceDiagnosticRequest>(State::workspace_diagnostic)
             .with_request_::<DocumentColor>(State::document_color)
             .with_request_::<DocumentLinkRequest>(State::document_link)
             .with_request_::<ColorPresentationRequest>(State::color_presentation)
@@ -222,7 +246,12 @@ impl LanguageState {
             .with_request_::<Rename>(State::rename)
             .with_request_::<GotoDefinition>(State::goto_definition)
             .with_request_::<GotoDeclaration>(State::goto_declaration)
+            .with_request_::<GotoTypeDefinition>(State::goto_type_definition)
+            .with_request_::<GotoImplementation>(State::goto_implementation)
             .with_request_::<References>(State::references)
+            .with_request_::<CallHierarchyPrepare>(State::prepare_call_hierarchy)
+            .with_request_::<CallHierarchyIncomingCalls>(State::call_hierarchy_incoming_calls)
+            .with_request_::<CallHierarchyOutgoingCalls>(State::call_hierarchy_outgoing_calls)
             .with_request_::<WorkspaceSymbolRequest>(State::symbol)
             .with_request_::<OnEnter>(State::on_enter)
             .with_request_::<WillRenameFiles>(State::will_rename_files)
@@ -232,6 +261,7 @@ impl LanguageState {
             .with_notification::<DidCloseTextDocument>(State::did_close)
             .with_notification::<DidChangeTextDocument>(State::did_change)
             .with_notification::<DidSaveTextDocument>(State::did_save)
+            .with_notification::<DidChangeWatchedFiles>(State::did_change_watched_files)
             .with_notification::<DidChangeConfiguration>(State::did_change_configuration)
             // commands
             .with_command_("tinymist.exportPdf", State::export_pdf)
@@ -370,32 +400,69 @@ impl LanguageState {
 
         const FORMATTING_REGISTRATION_ID: &str = "formatting";
         const DOCUMENT_FORMATTING_METHOD_ID: &str = "textDocument/formatting";
+        const RANGE_FORMATTING_REGISTRATION_ID: &str = "rangeFormatting";
+        const DOCUMENT_RANGE_FORMATTING_METHOD_ID: &str = "textDocument/rangeFormatting";
+        const ON_TYPE_FORMATTING_REGISTRATION_ID: &str = "onTypeFormatting";
+        const DOCUMENT_ON_TYPE_FORMATTING_METHOD_ID: &str = "textDocument/onTypeFormatting";
+
+        pub fn get_formatting_registrations() -> Vec<Registration> {
+            let on_type_options = DocumentOnTypeFormattingRegistrationOptions {
+                document_selector: None,
+                first_trigger_character: ON_TYPE_FORMATTING_TRIGGERS[0].to_owned(),
+                more_trigger_character: Some(
+                    ON_TYPE_FORMATTING_TRIGGERS[1..]
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect(),
+                ),
+            };
 
-        pub fn get_formatting_registration() -> Registration {
-            Registration {
-                id: FORMATTING_REGISTRATION_ID.to_owned(),
-                method: DOCUMENT_FORMATTING_METHOD_ID.to_owned(),
-                register_options: None,
-            }
+            vec![
+                Registration {
+                    id: FORMATTING_REGISTRATION_ID.to_owned(),
+                    method: DOCUMENT_FORMATTING_METHOD_ID.to_owned(),
+                    register_options: None,
+                },
+                Registration {
+                    id: RANGE_FORMATTING_REGISTRATION_ID.to_owned(),
+                    method: DOCUMENT_RANGE_FORMATTING_METHOD_ID.to_owned(),
+                    register_options: None,
+                },
+                Registration {
+                    id: ON_TYPE_FORMATTING_REGISTRATION_ID.to_owned(),
+                    method: DOCUMENT_ON_TYPE_FORMATTING_METHOD_ID.to_owned(),
+                    register_options: Some(serde_json::to_value(on_type_options).unwrap()),
+                },
+            ]
         }
 
-        pub fn get_formatting_unregistration() -> Unregistration {
-            Unregistration {
-                id: FORMATTING_REGISTRATION_ID.to_owned(),
-                method: DOCUMENT_FORMATTING_METHOD_ID.to_owned(),
-            }
+        pub fn get_formatting_unregistrations() -> Vec<Unregistration> {
+            vec![
+                Unregistration {
+                    id: FORMATTING_REGISTRATION_ID.to_owned(),
+                    method: DOCUMENT_FORMATTING_METHOD_ID.to_owned(),
+                },
+                Unregistration {
+                    id: RANGE_FORMATTING_REGISTRATION_ID.to_owned(),
+                    method: DOCUMENT_RANGE_FORMATTING_METHOD_ID.to_owned(),
+                },
+                Unregistration {
+                    id: ON_TYPE_FORMATTING_REGISTRATION_ID.to_owned(),
+                    method: DOCUMENT_ON_TYPE_FORMATTING_METHOD_ID.to_owned(),
+                },
+            ]
         }
 
         match (enable, self.formatter_registered) {
             (true, false) => {
                 trace!("registering formatter");
-                self.register_capability(vec![get_formatting_registration()])
+                self.register_capability(get_formatting_registrations())
                     .inspect(|_| self.formatter_registered = enable)
                     .context("could not register formatter")
             }
             (false, true) => {
                 trace!("unregistering formatter");
-                self.unregister_capability(vec![get_formatting_unregistration()])
+                self.unregister_capability(get_formatting_unregistrations())
                     .inspect(|_| self.formatter_registered = enable)
                     .context("could not unregister formatter")
             }
@@ -439,6 +506,30 @@ impl LanguageState {
             }
         }
 
+        if self.const_config().watch_files_dynamic_registration {
+            trace!("setting up to watch the project manifests");
+
+            const WATCH_REGISTRATION_ID: &str = "watch_project_manifest";
+            const WATCH_METHOD_ID: &str = "workspace/didChangeWatchedFiles";
+
+            let options = DidChangeWatchedFilesRegistrationOptions {
+                watchers: vec![FileSystemWatcher {
+                    glob_pattern: GlobPattern::String(format!("**/{PROJECT_MANIFEST}")),
+                    kind: None,
+                }],
+            };
+            let err = self
+                .register_capability(vec![Registration {
+                    id: WATCH_REGISTRATION_ID.to_owned(),
+                    method: WATCH_METHOD_ID.to_owned(),
+                    register_options: serde_json::to_value(options).ok(),
+                }])
+                .err();
+            if let Some(err) = err {
+                error!("could not register to watch the project manifests: {err}");
+            }
+        }
+
         if self.const_config().cfg_change_registration {
             trace!("setting up to request config change notifications");
 
@@ -489,6 +580,11 @@ impl LanguageState {
         self.create_source(path.clone(), text)
             .map_err(|e| invalid_params(e.to_string()))?;
 
+        // Files of a project entry are compiled by the server of the entry.
+        if self.ensure_project_server(&path) {
+            return Ok(());
+        }
+
         // Focus after opening
         self.implicit_focus_entry(|| Some(path.as_path().into()), 'o');
         Ok(())
@@ -511,7 +607,34 @@ impl LanguageState {
         Ok(())
     }
 
-    fn did_save(&mut self, _params: DidSaveTextDocumentParams) -> LspResult<()> {
+    fn did_save(&mut self, params: DidSaveTextDocumentParams) -> LspResult<()> {
+        // The manifest is watched if the client supports it, as it is not a typst
+        // document and the save notification may not be sent for it.
+        if self.const_config().watch_files_dynamic_registration {
+            return Ok(());
+        }
+
+        let path = as_path(params.text_document);
+        if path
+            .file_name()
+            .is_some_and(|name| name == PROJECT_MANIFEST)
+        {
+            log::info!("project manifest saved, reloading: {path:?}");
+            self.restart_project();
+        }
+        Ok(())
+    }
+
+    fn did_change_watched_files(&mut self, params: DidChangeWatchedFilesParams) -> LspResult<()> {
+        let manifest = params.changes.into_iter().find(|change| {
+            as_path_(change.uri.clone())
+                .file_name()
+                .is_some_and(|name| name == PROJECT_MANIFEST)
+        });
+        if let Some(change) = manifest {
+            log::info!("project manifest changed, reloading: {:?}", change.uri);
+            self.restart_project();
+        }
         Ok(())
     }
 
@@ -528,8 +651,12 @@ impl LanguageState {
             }
         }
 
-        for e in self.primary.iter_mut().chain(self.dedicates.iter_mut()) {
-            e.sync_config(self.config.compile.clone());
+        let groups = self.servers_mut().map(|e| e.handle.diag_group.clone());
+        let groups = groups.collect::<Vec<_>>();
+        let configs = groups.iter().map(|group| self.group_config(group));
+        let configs = configs.collect::<Vec<_>>();
+        for (e, config) in self.servers_mut().zip(configs) {
+            e.sync_config(config);
         }
 
         if config.compile.output_path != self.config.compile.output_path
@@ -544,11 +671,21 @@ impl LanguageState {
                 .as_mut()
                 .unwrap()
                 .change_export_config(config.clone());
+
+            let project_groups = self.dedicates.iter_mut();
+            let project_groups = project_groups.filter(|e| Project::is_group(&e.handle.diag_group));
+            for e in project_groups {
+                e.change_export_config(ExportUserConfig {
+                    output: e.config.output_path.clone(),
+                    mode: e.config.export_pdf,
+                });
+            }
         }
 
         if config.compile.primary_opts() != self.config.compile.primary_opts() {
             self.config.compile.fonts = OnceCell::new(); // todo: don't reload fonts if not changed
             self.restart_primary();
+            self.restart_project();
             // todo: restart dedicates
         }
 
@@ -638,11 +775,81 @@ impl LanguageState {
         run_query!(req_id, self.GotoDeclaration(path, position))
     }
 
+    fn goto_type_definition(
+        &mut self,
+        req_id: RequestId,
+        params: GotoTypeDefinitionParams,
+    ) -> ScheduledResult {
+        let (path, position) = as_path_pos(params.text_document_position_params);
+        run_query!(req_id, self.GotoTypeDefinition(path, position))
+    }
+
+    fn goto_implementation(
+        &mut self,
+        req_id: RequestId,
+        params: GotoImplementationParams,
+    ) -> ScheduledResult {
+        let (path, position) = as_path_pos(params.text_document_position_params);
+        run_query!(req_id, self.GotoImplementation(path, position))
+    }
+
     fn references(&mut self, req_id: RequestId, params: ReferenceParams) -> ScheduledResult {
         let (path, position) = as_path_pos(params.text_document_position);
         run_query!(req_id, self.References(path, position))
     }
 
+    fn prepare_call_hierarchy(
+        &mut self,
+        req_id: RequestId,
+        params: CallHierarchyPrepareParams,
+    ) -> ScheduledResult {
+        let (path, position) = as_path_pos(params.text_document_position_params);
+        run_query!(req_id, self.PrepareCallHierarchy(path, position))
+    }
+
+    fn call_hierarchy_incoming_calls(
+        &mut self,
+        req_id: RequestId,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> ScheduledResult {
+        let path = as_path_(params.item.uri);
+        let position = params.item.selection_range.start;
+        run_query!(req_id, self.CallHierarchyIncomingCalls(path, position))
+    }
+
+    fn call_hierarchy_outgoing_calls(
+        &mut self,
+        req_id: RequestId,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> ScheduledResult {
+        let path = as_path_(params.item.uri);
+        let position = params.item.selection_range.start;
+        run_query!(req_id, self.CallHierarchyOutgoingCalls(path, position))
+    }
+
+    fn document_diagnostic(
+        &mut self,
+        req_id: RequestId,
+        params: DocumentDiagnosticParams,
+    ) -> ScheduledResult {
+        let path = as_path(params.text_document);
+        let previous_result_id = params.previous_result_id;
+        run_query!(req_id, self.DocumentDiagnostic(path, previous_result_id))
+    }
+
+    fn workspace_diagnostic(
+        &mut self,
+        req_id: RequestId,
+        params: WorkspaceDiagnosticParams,
+    ) -> ScheduledResult {
+        let previous_result_ids = params
+            .previous_result_ids
+            .into_iter()
+            .map(|id| (id.uri, id.value))
+            .collect();
+        run_query!(req_id, self.WorkspaceDiagnostic(previous_result_ids))
+    }
+
     fn hover(&mut self, req_id: RequestId, params: HoverParams) -> ScheduledResult {
         let (path, position) = as_path_pos(params.text_document_position_params);
         self.implicit_focus_entry(|| Some(path.as_path().into()), 'h');
@@ -721,6 +928,63 @@ impl LanguageState {
         self.client.schedule(req_id, self.formatter.run(source))
     }
 
+    fn range_formatting(
+        &mut self,
+        req_id: RequestId,
+        params: DocumentRangeFormattingParams,
+    ) -> ScheduledResult {
+        if matches!(self.config.formatter_mode, FormatterMode::Disable) {
+            return Ok(None);
+        }
+
+        let path: ImmutPath = as_path(params.text_document).as_path().into();
+        let source = self
+            .query_source(path, |source: typst::syntax::Source| Ok(source))
+            .map_err(|e| internal_error(format!("could not format document: {e}")))?;
+        let range = to_typst_range(params.range, self.const_config().position_encoding, &source)
+            .ok_or_else(|| invalid_params("invalid range"))?;
+        self.client
+            .schedule(req_id, self.formatter.run_range(source, Some(range)))
+    }
+
+    fn on_type_formatting(
+        &mut self,
+        req_id: RequestId,
+        params: DocumentOnTypeFormattingParams,
+    ) -> ScheduledResult {
+        if matches!(self.config.formatter_mode, FormatterMode::Disable) {
+            return Ok(None);
+        }
+
+        let (path, position) = as_path_pos(params.text_document_position);
+        let source = self
+            .query_source(path.as_path().into(), |source: Source| Ok(source))
+            .map_err(|e| internal_error(format!("could not format document: {e}")))?;
+        let cursor = to_typst_position(position, self.const_config().position_encoding, &source)
+            .ok_or_else(|| invalid_params("invalid position"))?;
+
+        // Formats the block closed by the typed bracket.
+        let root = LinkedNode::new(source.root());
+        let block = root.leaf_at_compat(cursor).and_then(|leaf| {
+            let leaf = if leaf.range().end == cursor {
+                leaf
+            } else {
+                leaf.prev_leaf()?
+            };
+            let closes_block = matches!(
+                leaf.kind(),
+                SyntaxKind::RightBrace | SyntaxKind::RightBracket
+            );
+            let block = leaf.parent()?;
+            (closes_block && leaf.range().end == cursor).then(|| block.range())
+        });
+        let Some(range) = block else {
+            return Ok(None);
+        };
+        self.client
+            .schedule(req_id, self.formatter.run_range(source, Some(range)))
+    }
+
     fn inlay_hint(&mut self, req_id: RequestId, params: InlayHintParams) -> ScheduledResult {
         let path = as_path(params.text_document);
         let range = params.range;
@@ -755,7 +1019,8 @@ impl LanguageState {
     fn code_action(&mut self, req_id: RequestId, params: CodeActionParams) -> ScheduledResult {
         let path = as_path(params.text_document);
         let range = params.range;
-        run_query!(req_id, self.CodeAction(path, range))
+        let context = params.context;
+        run_query!(req_id, self.CodeAction(path, range, context))
     }
 
     fn code_lens(&mut self, req_id: RequestId, params: CodeLensParams) -> ScheduledResult {
@@ -895,6 +1160,14 @@ impl LanguageState {
         }
 
         let new_entry = new_entry();
+        let project = self.project.as_ref();
+        if new_entry
+            .as_deref()
+            .zip(project)
+            .is_some_and(|(path, project)| project.entry_of(path).is_some())
+        {
+            return;
+        }
 
         let update_result = self.focus_entry(new_entry.clone());
         match update_result {
@@ -1023,15 +1296,24 @@ impl LanguageState {
 
         let primary = || self.primary();
         let is_pinning = self.pinning;
+        // Files of a project entry are served by the server of the entry, whose
+        // main file is always pinned.
+        let project_server = match &query {
+            OnExport(req) => self.project_server(&req.path),
+            query => query.associated_path().and_then(|p| self.project_server(p)),
+        };
         just_ok(match query {
             FoldingRange(req) => query_source!(self, FoldingRange, req)?,
             SelectionRange(req) => query_source!(self, SelectionRange, req)?,
             DocumentSymbol(req) => query_source!(self, DocumentSymbol, req)?,
             OnEnter(req) => query_source!(self, OnEnter, req)?,
             ColorPresentation(req) => CompilerQueryResponse::ColorPresentation(req.request()),
-            OnExport(req) => return primary().on_export(req),
+            OnExport(req) => return project_server.unwrap_or_else(primary).on_export(req),
             ServerInfo(_) => return primary().collect_server_info(),
-            _ => return Self::query_on(primary(), is_pinning, query),
+            _ => match project_server {
+                Some(server) => return Self::query_on(server, true, query),
+                None => return Self::query_on(primary(), is_pinning, query),
+            },
         })
     }
 
@@ -1045,18 +1327,30 @@ impl LanguageState {
         assert!(query.fold_feature() != FoldRequestFeature::ContextFreeUnique);
 
         let fut_stat = client.query_snapshot_with_stat(&query)?;
-        let entry = query
-            .associated_path()
-            .map(|path| client.entry_resolver().resolve(Some(path.into())))
-            .or_else(|| {
-                let root = client.entry_resolver().root(None)?;
-                Some(EntryState::new_rooted(root, Some(*DETACHED_ENTRY)))
-            });
+        let resolver = client.entry_resolver();
+        let is_workspace = matches!(query, WorkspaceDiagnostic(..));
+        let entry = if is_workspace {
+            // The workspace is checked with the primary entry, or the default
+            // entry if the primary one is inactive.
+            resolver
+                .resolve_default()
+                .map(|path| resolver.resolve(Some(path)))
+        } else {
+            query
+                .associated_path()
+                .map(|path| resolver.resolve(Some(path.into())))
+                .or_else(|| {
+                    let root = resolver.root(None)?;
+                    Some(EntryState::new_rooted(root, Some(*DETACHED_ENTRY)))
+                })
+        };
 
         just_future(async move {
             let mut snap = fut_stat.fut.receive().await?;
             // todo: whether it is safe to inherit success_doc with changed entry
-            if !is_pinning {
+            let keeps_entry =
+                is_pinning || (is_workspace && !snap.world.entry_state().is_inactive());
+            if !keeps_entry {
                 snap = snap.task(TaskInputs {
                     entry,
                     ..Default::default()
@@ -1081,8 +1375,19 @@ impl LanguageState {
                 Hover(req) => snap.run_stateful(req, R::Hover),
                 GotoDefinition(req) => snap.run_stateful(req, R::GotoDefinition),
                 GotoDeclaration(req) => snap.run_semantic(req, R::GotoDeclaration),
+                GotoTypeDefinition(req) => snap.run_semantic(req, R::GotoTypeDefinition),
+                GotoImplementation(req) => snap.run_semantic(req, R::GotoImplementation),
                 References(req) => snap.run_stateful(req, R::References),
+                PrepareCallHierarchy(req) => snap.run_semantic(req, R::PrepareCallHierarchy),
+                CallHierarchyIncomingCalls(req) => {
+                    snap.run_semantic(req, R::CallHierarchyIncomingCalls)
+                }
+                CallHierarchyOutgoingCalls(req) => {
+                    snap.run_semantic(req, R::CallHierarchyOutgoingCalls)
+                }
                 InlayHint(req) => snap.run_semantic(req, R::InlayHint),
+                DocumentDiagnostic(req) => snap.run_semantic(req, R::DocumentDiagnostic),
+                WorkspaceDiagnostic(req) => snap.run_semantic(req, R::WorkspaceDiagnostic),
                 DocumentHighlight(req) => snap.run_semantic(req, R::DocumentHighlight),
                 DocumentColor(req) => snap.run_semantic(req, R::DocumentColor),
                 DocumentLink(req) => snap.run_semantic(req, R::DocumentLink),
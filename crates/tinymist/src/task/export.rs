@@ -4,7 +4,7 @@ use std::str::FromStr;
 use std::{path::PathBuf, sync::Arc};
 
 use anyhow::{bail, Context};
-use reflexo_typst::{EntryReader, EntryState, TaskInputs, TypstDatetime};
+use reflexo_typst::{EntryReader, EntryState, TaskInputs, TypstDatetime, TypstDocument};
 use tinymist_query::{ExportKind, PageSelection};
 use tokio::sync::mpsc;
 use typlite::Typlite;
@@ -62,6 +62,11 @@ impl ExportTask {
         let task = self.factory.task();
         task.signal(snap, s, self);
     }
+
+    /// Get the document last exported.
+    pub fn last_exported(&self) -> Option<Arc<TypstDocument>> {
+        self.factory.task().last_exported.lock().clone()
+    }
 }
 
 impl SyncTaskFactory<ExportConfig> {
@@ -83,6 +88,16 @@ impl SyncTaskFactory<ExportConfig> {
             export.do_export(&kind, artifact).await
         }
     }
+
+    /// Exports a compiled artifact with the configured kind, regardless of the
+    /// export mode.
+    pub fn export_artifact(
+        &self,
+        artifact: CompiledArtifact<LspCompilerFeat>,
+    ) -> impl Future<Output = anyhow::Result<Option<PathBuf>>> {
+        let export = self.task();
+        async move { export.do_export(&export.kind, artifact).await }
+    }
 }
 
 #[derive(Clone, Default)]
@@ -92,6 +107,8 @@ pub struct ExportConfig {
     pub config: ExportUserConfig,
     pub kind: ExportKind,
     pub count_words: bool,
+    /// The document last exported, which is shared by the configurations.
+    pub last_exported: Arc<Mutex<Option<Arc<TypstDocument>>>>,
 }
 
 impl ExportConfig {
@@ -200,6 +217,7 @@ impl ExportConfig {
 
         // Prepare the document.
         let doc = doc.map_err(|_| anyhow::anyhow!("no document"))?;
+        let exported = doc.clone();
 
         // Prepare data.
         let kind2 = kind.clone();
@@ -316,6 +334,7 @@ impl ExportConfig {
         tokio::fs::write(&to, data.await??)
             .await
             .with_context(|| format!("RenderActor({kind:?}): failed to export"))?;
+        *self.last_exported.lock() = Some(exported);
 
         log::info!("RenderActor({kind:?}): export complete");
         Ok(Some(to))
@@ -1,8 +1,10 @@
 //! The actor that handles formatting.
 
-use std::iter::zip;
+use std::ops::Range;
+use std::time::Duration;
 
 use lsp_types::TextEdit;
+use similar::{Algorithm, DiffTag, TextDiff};
 use sync_lsp::{just_future, SchedulableResponse};
 use tinymist_query::{to_lsp_range, PositionEncoding};
 use typst::syntax::Source;
@@ -63,6 +65,16 @@ impl FormatTask {
     }
 
     pub fn run(&self, src: Source) -> SchedulableResponse<Option<Vec<TextEdit>>> {
+        self.run_range(src, None)
+    }
+
+    /// Formats the document and keeps the edits intersecting the range. The
+    /// whole document is formatted if no range is given.
+    pub fn run_range(
+        &self,
+        src: Source,
+        range: Option<Range<usize>>,
+    ) -> SchedulableResponse<Option<Vec<TextEdit>>> {
         let c = self.factory.task();
         just_future(async move {
             let formatted = match &c.config {
@@ -75,44 +87,103 @@ impl FormatTask {
                 FormatterConfig::Disable => None,
             };
 
-            Ok(formatted.and_then(|formatted| calc_diff(src, formatted, c.position_encoding)))
+            Ok(formatted.map(|formatted| calc_diff(src, formatted, range, c.position_encoding)))
         })
     }
 }
 
-/// A simple implementation of the diffing algorithm, borrowed from
-/// [`Source::replace`].
-fn calc_diff(prev: Source, next: String, encoding: PositionEncoding) -> Option<Vec<TextEdit>> {
+/// Calculates a line-based minimal diff between the documents, so that the
+/// unchanged lines keep their cursor positions and undo history. If a range
+/// is given, only the edits intersecting it are kept.
+fn calc_diff(
+    prev: Source,
+    next: String,
+    range: Option<Range<usize>>,
+    encoding: PositionEncoding,
+) -> Vec<TextEdit> {
     let old = prev.text();
-    let new = &next;
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Myers)
+        .timeout(Duration::from_secs(1))
+        .diff_lines(old, &next);
+
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let mut old_offsets = Vec::with_capacity(old_lines.len() + 1);
+    old_offsets.push(0);
+    for line in old_lines {
+        old_offsets.push(old_offsets.last().unwrap() + line.len());
+    }
 
-    let mut prefix = zip(old.bytes(), new.bytes())
-        .take_while(|(x, y)| x == y)
-        .count();
+    diff.ops()
+        .iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .map(|op| {
+            let old_range = op.old_range();
+            let replace = old_offsets[old_range.start]..old_offsets[old_range.end];
+            (replace, new_lines[op.new_range()].concat())
+        })
+        .filter(|(replace, _)| {
+            range
+                .as_ref()
+                .map_or(true, |range| overlaps(replace, range))
+        })
+        .map(|(replace, with)| TextEdit {
+            range: to_lsp_range(replace, &prev, encoding),
+            new_text: with,
+        })
+        .collect()
+}
 
-    if prefix == old.len() && prefix == new.len() {
-        return Some(vec![]);
+/// Checks whether the edit intersects the range. An edit that only touches the
+/// boundary of the range, e.g. the line right after it, is excluded unless one
+/// of them is empty.
+fn overlaps(replace: &Range<usize>, range: &Range<usize>) -> bool {
+    if replace.is_empty() || range.is_empty() {
+        replace.start <= range.end && range.start <= replace.end
+    } else {
+        replace.start < range.end && range.start < replace.end
     }
+}
 
-    while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
-        prefix -= 1;
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range as LspRange};
+
+    use super::*;
+
+    fn diff(prev: &str, next: &str, range: Option<Range<usize>>) -> Vec<TextEdit> {
+        let prev = Source::detached(prev);
+        calc_diff(prev, next.to_owned(), range, PositionEncoding::Utf16)
     }
 
-    let mut suffix = zip(old[prefix..].bytes().rev(), new[prefix..].bytes().rev())
-        .take_while(|(x, y)| x == y)
-        .count();
+    fn edit(start: u32, end: u32, new_text: &str) -> TextEdit {
+        TextEdit {
+            range: LspRange::new(Position::new(start, 0), Position::new(end, 0)),
+            new_text: new_text.to_owned(),
+        }
+    }
 
-    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
-        suffix += 1;
+    #[test]
+    fn test_diff_unchanged() {
+        assert_eq!(diff("a\nb\n", "a\nb\n", None), vec![]);
     }
 
-    let replace = prefix..old.len() - suffix;
-    let with = &new[prefix..new.len() - suffix];
+    #[test]
+    fn test_diff_lines() {
+        let edits = diff("a\nb\nc\nd\n", "a\nB\nc\nD\n", None);
+        assert_eq!(edits, vec![edit(1, 2, "B\n"), edit(3, 4, "D\n")]);
+    }
 
-    let range = to_lsp_range(replace, &prev, encoding);
+    #[test]
+    fn test_diff_in_range() {
+        let edits = diff("a\nb\nc\nd\n", "a\nB\nc\nD\n", Some(6..7));
+        assert_eq!(edits, vec![edit(3, 4, "D\n")]);
+    }
 
-    Some(vec![TextEdit {
-        new_text: with.to_owned(),
-        range,
-    }])
+    #[test]
+    fn test_diff_adjacent_to_range() {
+        let edits = diff("a\nb\nc\nd\n", "a\nB\nc\nD\n", Some(4..6));
+        assert_eq!(edits, vec![]);
+    }
 }
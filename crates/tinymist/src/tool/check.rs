@@ -0,0 +1,318 @@
+//! Headless checker for Typst, which reports the diagnostics shown in the
+//! editor in machine-readable formats.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Url};
+use reflexo_typst::ImmutPath;
+use serde_json::{json, Value as JsonValue};
+use tinymist_query::analysis::Analysis;
+use tinymist_query::{
+    compile_diagnostics, lint_diagnostics, merge_lint_diagnostics, PositionEncoding,
+};
+
+use crate::*;
+
+/// The format of the reported diagnostics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum CheckFormat {
+    /// The LSP diagnostics keyed by document URI.
+    #[default]
+    Json,
+    /// The Static Analysis Results Interchange Format (SARIF) 2.1.0.
+    Sarif,
+    /// The workflow commands creating annotations in GitHub Actions.
+    Github,
+}
+
+/// CLI Arguments for the check tool.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct CheckCliArgs {
+    /// Compile arguments. If no input is given, the entries of the project
+    /// manifest in the root are checked.
+    #[clap(flatten)]
+    pub compile: CompileOnceArgs,
+
+    /// The format of the reported diagnostics
+    #[clap(long, value_enum, default_value_t = CheckFormat::Json)]
+    pub format: CheckFormat,
+
+    /// Treats warnings as errors when determining the exit code
+    #[clap(long)]
+    pub deny_warnings: bool,
+}
+
+/// The diagnostics of the checked documents, ordered by document.
+pub(crate) type CheckReport = BTreeMap<Url, Vec<Diagnostic>>;
+
+/// Entry point of the check tool.
+pub fn check_main(args: CheckCliArgs) -> anyhow::Result<()> {
+    log::info!("Arguments: {args:#?}");
+
+    let mut report = CheckReport::new();
+    for compile in check_entries(&args.compile)? {
+        let world = compile.resolve()?.snapshot();
+        log::info!("checking {:?}", compile.input);
+
+        let analysis = Analysis {
+            position_encoding: PositionEncoding::Utf16,
+            ..Analysis::default()
+        };
+        let mut ctx = analysis.snapshot(world);
+
+        let mut diagnostics = compile_diagnostics(&ctx.world, PositionEncoding::Utf16);
+        let lints = lint_diagnostics(&mut ctx);
+        merge_lint_diagnostics(&mut diagnostics, lints);
+        for (uri, items) in diagnostics {
+            let entry = report.entry(uri).or_default();
+            for item in items {
+                // Files shared by entries are reported once.
+                if !entry.contains(&item) {
+                    entry.push(item);
+                }
+            }
+        }
+    }
+
+    let output = match args.format {
+        CheckFormat::Json => serde_json::to_string_pretty(&report)?,
+        CheckFormat::Sarif => serde_json::to_string_pretty(&sarif_report(&report))?,
+        CheckFormat::Github => github_report(&report),
+    };
+    println!("{output}");
+
+    let is_failure = |diag: &Diagnostic| match diag.severity {
+        Some(DiagnosticSeverity::ERROR) => true,
+        Some(DiagnosticSeverity::WARNING) => args.deny_warnings,
+        _ => false,
+    };
+    let failures = report.values().flatten().filter(|d| is_failure(d)).count();
+    if failures > 0 {
+        anyhow::bail!("found {failures} problem(s)");
+    }
+
+    Ok(())
+}
+
+/// Determines the compile arguments of the entries to check.
+fn check_entries(args: &CompileOnceArgs) -> anyhow::Result<Vec<CompileOnceArgs>> {
+    if args.input.is_some() {
+        return Ok(vec![args.clone()]);
+    }
+
+    let root = match &args.root {
+        Some(root) => std::env::current_dir()?.join(root),
+        None => std::env::current_dir()?,
+    };
+    let Some(project) = Project::discover([&ImmutPath::from(root.clone())]) else {
+        anyhow::bail!("neither an input file nor a {PROJECT_MANIFEST} in {root:?} is given");
+    };
+
+    let entries = project.entries.iter().map(|entry| {
+        let mut compile = args.clone();
+        compile.input = Some(entry.main.to_string_lossy().into_owned());
+        compile.root = Some(root.clone());
+        compile.font.font_paths = (entry.font_paths.iter().cloned())
+            .chain(args.font.font_paths.iter().cloned())
+            .collect();
+        compile.inputs.extend(entry.inputs.clone());
+        compile
+    });
+
+    Ok(entries.collect())
+}
+
+/// Converts the diagnostics to a SARIF log.
+fn sarif_report(report: &CheckReport) -> JsonValue {
+    let results = report
+        .iter()
+        .flat_map(|(uri, items)| items.iter().map(move |diag| (uri, diag)))
+        .map(|(uri, diag)| {
+            let range = diag.range;
+            let mut result = json!({
+                "level": sarif_level(diag.severity),
+                "message": { "text": diag.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri.as_str() },
+                        "region": {
+                            "startLine": range.start.line + 1,
+                            "startColumn": range.start.character + 1,
+                            "endLine": range.end.line + 1,
+                            "endColumn": range.end.character + 1,
+                        },
+                    },
+                }],
+            });
+            if let Some(source) = &diag.source {
+                result["ruleId"] = source.as_str().into();
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "tinymist",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/Myriad-Dreamin/tinymist",
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_level(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        _ => "note",
+    }
+}
+
+/// Converts the diagnostics to GitHub workflow commands, one per line.
+fn github_report(report: &CheckReport) -> String {
+    report_lines(report, github_annotation)
+}
+
+/// Converts the diagnostics to human-readable messages for the console, e.g.
+/// `main.typ:2:3: error: unknown variable: x`.
+pub(crate) fn console_report(report: &CheckReport) -> String {
+    report_lines(report, console_message)
+}
+
+fn report_lines(report: &CheckReport, f: impl Fn(&Path, &Diagnostic) -> String) -> String {
+    let cwd = std::env::current_dir().unwrap_or_default();
+
+    let mut lines = vec![];
+    for (uri, items) in report {
+        let path = uri
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(uri.path()));
+        let path = path.strip_prefix(&cwd).unwrap_or(&path);
+        for diag in items {
+            lines.push(f(path, diag));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn console_message(path: &Path, diag: &Diagnostic) -> String {
+    let severity = match diag.severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        _ => "info",
+    };
+
+    // The hints are put on their own lines after the message.
+    let mut lines = diag.message.lines().filter(|line| !line.is_empty());
+    let message = lines.next().unwrap_or_default();
+    let mut res = format!(
+        "{}:{}:{}: {severity}: {message}",
+        path.display(),
+        diag.range.start.line + 1,
+        diag.range.start.character + 1,
+    );
+    for line in lines {
+        res.push_str("\n  ");
+        res.push_str(line);
+    }
+    res
+}
+
+fn github_annotation(path: &Path, diag: &Diagnostic) -> String {
+    let command = match diag.severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        _ => "notice",
+    };
+    let title = match &diag.code {
+        Some(NumberOrString::String(code)) => code.clone(),
+        Some(NumberOrString::Number(code)) => code.to_string(),
+        None => diag.source.clone().unwrap_or_else(|| "typst".to_owned()),
+    };
+
+    let range = diag.range;
+    format!(
+        "::{command} file={},line={},col={},endLine={},endColumn={},title={}::{}",
+        escape_property(&path.to_string_lossy()),
+        range.start.line + 1,
+        range.start.character + 1,
+        range.end.line + 1,
+        range.end.character + 1,
+        escape_property(&title),
+        escape_data(&diag.message),
+    )
+}
+
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{Position, Range};
+
+    fn diagnostic(severity: DiagnosticSeverity, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(1, 2), Position::new(1, 5)),
+            severity: Some(severity),
+            source: Some("typst".to_owned()),
+            message: message.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_github_annotation() {
+        let diag = diagnostic(DiagnosticSeverity::ERROR, "unknown variable: x\nhint: 100%");
+        assert_eq!(
+            github_annotation(Path::new("a,b.typ"), &diag),
+            "::error file=a%2Cb.typ,line=2,col=3,endLine=2,endColumn=6,title=typst::unknown variable: x%0Ahint: 100%25"
+        );
+    }
+
+    #[test]
+    fn test_console_message() {
+        let diag = diagnostic(
+            DiagnosticSeverity::WARNING,
+            "unused variable: `x`\n\nHint: prefix it with an underscore",
+        );
+        assert_eq!(
+            console_message(Path::new("main.typ"), &diag),
+            "main.typ:2:3: warning: unused variable: `x`\n  Hint: prefix it with an underscore"
+        );
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let uri = Url::parse("file:///main.typ").unwrap();
+        let report = CheckReport::from([(
+            uri,
+            vec![diagnostic(DiagnosticSeverity::WARNING, "unused variable")],
+        )]);
+
+        let sarif = sarif_report(&report);
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!(result["level"], "warning");
+        assert_eq!(result["ruleId"], "typst");
+        let region = &result["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startLine"], 2);
+        assert_eq!(region["endColumn"], 6);
+    }
+}
@@ -0,0 +1,265 @@
+//! Headless compiler for Typst, which exports documents from the shell in the
+//! same way as the language server does.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use reflexo_typst::EntryReader;
+use tinymist_query::{ExportKind, PageSelection, PositionEncoding};
+use tokio::sync::mpsc;
+
+use crate::actor::editor::EditorRequest;
+use crate::task::{ExportConfig, ExportTask, ExportUserConfig};
+use crate::tool::check::{console_report, CheckReport};
+use crate::*;
+use actor::typ_client::CompileHandler;
+use actor::typ_server::{CompileServerActor, CompileServerOpts};
+
+/// The format of the exported artifact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum CompileFormat {
+    /// Portable Document Format.
+    #[default]
+    Pdf,
+    /// Scalable Vector Graphics.
+    Svg,
+    /// Portable Network Graphics.
+    Png,
+    /// HTML with the pages embedded as SVG.
+    Html,
+    /// Markdown converted by typlite.
+    Markdown,
+    /// Plain text of the document.
+    Text,
+    /// Elements queried from the document.
+    Query,
+}
+
+/// The pages to export for image formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum CompilePages {
+    /// Exports the first page.
+    #[default]
+    First,
+    /// Exports all pages merged into a single image.
+    Merged,
+}
+
+/// CLI Arguments for the compile tool.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct CompileCliArgs {
+    /// Compile arguments
+    #[clap(flatten)]
+    pub compile: CompileOnceArgs,
+
+    /// The format of the exported artifact
+    #[clap(long, value_enum, default_value_t = CompileFormat::Pdf)]
+    pub format: CompileFormat,
+
+    /// The path pattern of the exported artifact, in which `$root`, `$dir`
+    /// and `$name` are substituted. The extension is determined by the format.
+    #[clap(long, default_value = "$root/$dir/$name", value_name = "PATTERN")]
+    pub output: String,
+
+    /// The pages to export for SVG and PNG
+    #[clap(long, value_enum, default_value_t = CompilePages::First)]
+    pub pages: CompilePages,
+
+    /// The gap between merged pages, e.g. `10pt`
+    #[clap(long, value_name = "LENGTH")]
+    pub gap: Option<String>,
+
+    /// The pixels per inch for PNG
+    #[clap(long)]
+    pub ppi: Option<f64>,
+
+    /// The background color for merged PNG, e.g. `white` or `#ffffff`
+    #[clap(long, value_name = "COLOR")]
+    pub fill: Option<String>,
+
+    /// The selector of the elements to query
+    #[clap(long, required_if_eq("format", "query"))]
+    pub selector: Option<String>,
+
+    /// The field of the queried elements to extract
+    #[clap(long)]
+    pub field: Option<String>,
+
+    /// Expects and retrieves exactly one element
+    #[clap(long)]
+    pub one: bool,
+
+    /// The serialization format of the queried elements
+    #[clap(long, default_value = "json", value_name = "FORMAT")]
+    pub query_format: String,
+
+    /// The extension of the queried output, which defaults to the
+    /// serialization format
+    #[clap(long, value_name = "EXT")]
+    pub query_extension: Option<String>,
+
+    /// Pretty-prints the queried elements
+    #[clap(long)]
+    pub pretty: bool,
+
+    /// Watches the dependencies and exports the document on changes
+    #[clap(long, short)]
+    pub watch: bool,
+}
+
+impl CompileCliArgs {
+    /// The kind of export requested by the arguments.
+    pub fn export_kind(&self) -> ExportKind {
+        let page = match self.pages {
+            CompilePages::First => PageSelection::First,
+            CompilePages::Merged => PageSelection::Merged {
+                gap: self.gap.clone(),
+            },
+        };
+
+        match self.format {
+            CompileFormat::Pdf => ExportKind::Pdf {
+                creation_timestamp: self.compile.creation_timestamp,
+            },
+            CompileFormat::Svg => ExportKind::Svg { page },
+            CompileFormat::Png => ExportKind::Png {
+                ppi: self.ppi,
+                fill: self.fill.clone(),
+                page,
+            },
+            CompileFormat::Html => ExportKind::Html {},
+            CompileFormat::Markdown => ExportKind::Markdown {},
+            CompileFormat::Text => ExportKind::Text {},
+            CompileFormat::Query => ExportKind::Query {
+                format: self.query_format.clone(),
+                output_extension: self.query_extension.clone(),
+                strict: true,
+                selector: self.selector.clone().unwrap_or_default(),
+                field: self.field.clone(),
+                one: self.one,
+                pretty: self.pretty,
+            },
+        }
+    }
+
+    /// The output path pattern, where a relative pattern is resolved against
+    /// the current working directory.
+    pub fn output_pattern(&self) -> anyhow::Result<PathPattern> {
+        let output = &self.output;
+        if output.starts_with('$') || Path::new(output).is_absolute() {
+            return Ok(PathPattern::new(output));
+        }
+
+        let cwd = std::env::current_dir()?;
+        Ok(PathPattern::new(&cwd.join(output).to_string_lossy()))
+    }
+}
+
+/// Entry point of the compile tool.
+pub async fn compile_main(args: CompileCliArgs) -> anyhow::Result<()> {
+    log::info!("Arguments: {args:#?}");
+
+    let kind = args.export_kind();
+    let output = args.output_pattern()?;
+    let verse = args.compile.resolve()?;
+
+    // The initial compilation is always exported, and the following ones are
+    // exported when the dependencies change on disk.
+    let mode = if args.watch {
+        ExportMode::OnSave
+    } else {
+        ExportMode::Never
+    };
+    let export = ExportTask::new(ExportConfig {
+        group: "main".to_owned(),
+        editor_tx: None,
+        config: ExportUserConfig { output, mode },
+        kind,
+        count_words: false,
+        last_exported: Default::default(),
+    });
+
+    let (editor_tx, mut editor_rx) = mpsc::unbounded_channel();
+    let (intr_tx, intr_rx) = mpsc::unbounded_channel();
+
+    let handle = Arc::new(CompileHandler {
+        #[cfg(feature = "preview")]
+        inner: Default::default(),
+        diag_group: "main".to_owned(),
+        intr_tx: intr_tx.clone(),
+        export: export.clone(),
+        editor_tx,
+        analysis: Arc::default(),
+        stats: Default::default(),
+        notified_revision: Arc::new(parking_lot::Mutex::new(0)),
+    });
+
+    // Prints the diagnostics of the watched builds. The diagnostics of a revision are
+    // printed once, as they are pushed again along with the lints.
+    let watch = args.watch;
+    tokio::spawn(async move {
+        let mut printed = None;
+        while let Some(req) = editor_rx.recv().await {
+            let EditorRequest::Diag(dv, Some(diagnostics)) = req else {
+                continue;
+            };
+            if !watch || printed == Some(dv.revision) {
+                continue;
+            }
+            printed = Some(dv.revision);
+            print_diagnostics(diagnostics);
+        }
+    });
+
+    let mut service = CompileServerActor::new_with(
+        verse,
+        intr_tx,
+        intr_rx,
+        CompileServerOpts {
+            compile_handle: handle,
+            ..Default::default()
+        },
+    )
+    .with_watch(args.watch);
+
+    if args.watch {
+        tokio::spawn(async move {
+            let _ = tokio::signal::ctrl_c().await;
+            log::info!("Ctrl-C received, exiting");
+            std::process::exit(0);
+        });
+
+        service.run().await;
+        return Ok(());
+    }
+
+    let artifact = service.compile_once().await;
+    let errors = artifact.doc.as_ref().err().cloned().unwrap_or_default();
+    let diagnostics = tinymist_query::convert_diagnostics(
+        &artifact.world,
+        errors.iter().chain(artifact.warnings.iter()),
+        PositionEncoding::Utf16,
+    );
+    print_diagnostics(diagnostics);
+    if artifact.doc.is_err() {
+        let main = artifact.world.entry_state().main();
+        anyhow::bail!("failed to compile {main:?}");
+    }
+
+    match export.factory.export_artifact(artifact).await? {
+        Some(path) => log::info!("exported to {path:?}"),
+        None => anyhow::bail!("the document cannot be exported to {:?}", args.output),
+    }
+
+    Ok(())
+}
+
+/// Prints the diagnostics to stderr.
+fn print_diagnostics(diagnostics: tinymist_query::DiagnosticsMap) {
+    if !diagnostics.is_empty() {
+        let report = diagnostics.into_iter().collect::<CheckReport>();
+        eprintln!("{}", console_report(&report));
+    }
+}
@@ -1,5 +1,7 @@
 //! All the language tools provided by the `tinymist` crate.
 
+pub mod check;
+pub mod compile;
 pub mod package;
 pub mod text;
 pub mod word_count;
@@ -1,7 +1,15 @@
 //! Document preview tool for Typst
 
-use std::num::NonZeroUsize;
-use std::{collections::HashMap, net::SocketAddr, path::Path, sync::Arc};
+mod access;
+mod diff;
+mod serve_dir;
+
+pub use access::{PreviewAccess, PreviewAccessArgs};
+pub(crate) use diff::set_diff_baseline;
+pub use diff::DiffBaseline;
+
+use std::path::{Path, PathBuf};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 use futures::{SinkExt, StreamExt, TryStreamExt};
 use hyper::service::service_fn;
@@ -17,27 +25,32 @@ use serde_json::Value as JsonValue;
 use sync_lsp::just_ok;
 use tinymist_assets::TYPST_PREVIEW_HTML;
 use tokio::sync::{mpsc, oneshot};
-use typst::layout::{Frame, FrameItem, Point, Position};
+use typst::layout::Position;
 use typst::syntax::{LinkedNode, Source, Span, SyntaxKind, VirtualPath};
 use typst::World;
 pub use typst_preview::CompileStatus;
 use typst_preview::{
     frontend_html, CompileHost, ControlPlaneMessage, ControlPlaneResponse, ControlPlaneRx,
-    ControlPlaneTx, DocToSrcJumpInfo, EditorServer, Location, MemoryFiles, MemoryFilesShort,
-    PreviewArgs, PreviewBuilder, PreviewMode, Previewer, SourceFileServer, WsMessage,
+    ControlPlaneTx, DiffHandle, DocToSrcJumpInfo, EditorServer, Location, MemoryFiles,
+    MemoryFilesShort, PreviewArgs, PreviewBuilder, PreviewMode, Previewer, SourceFileServer,
+    WsMessage,
 };
 use typst_shim::syntax::LinkedNodeExt;
 
-use crate::world::{LspCompilerFeat, LspWorld};
+use crate::world::{LspCompilerFeat, LspUniverse, LspWorld};
 use crate::*;
 use actor::preview::{PreviewActor, PreviewRequest, PreviewTab};
 use actor::typ_client::CompileHandler;
-use actor::typ_server::{CompileServerActor, CompileServerOpts, SucceededArtifact};
+use actor::typ_server::{CompileServerActor, CompileServerOpts, Interrupt, SucceededArtifact};
 
 impl CompileHost for CompileHandler {}
 
 impl CompileHandler {
-    fn resolve_source_span(world: &LspWorld, loc: Location) -> Option<SourceSpanOffset> {
+    fn resolve_source_span(
+        world: &LspWorld,
+        doc: Option<&TypstDocument>,
+        loc: Location,
+    ) -> Option<SourceSpanOffset> {
         let Location::Src(loc) = loc;
 
         let filepath = Path::new(&loc.filepath);
@@ -49,7 +62,9 @@ impl CompileHandler {
 
         let node = LinkedNode::new(source.root()).leaf_at_compat(cursor)?;
         if node.kind() != SyntaxKind::Text {
-            return None;
+            // Highlight the innermost element laid out, e.g. an equation.
+            let span = tinymist_query::jump_span_from_cursor(doc?, &source, cursor)?;
+            return Some(SourceSpanOffset { span, offset: 0 });
         }
         let span = node.span();
         // todo: unicode char
@@ -88,7 +103,7 @@ impl CompileHandler {
             return vec![];
         };
 
-        jump_from_cursor(doc, &source, cursor)
+        tinymist_query::jump_from_cursor(doc, &source, cursor)
     }
 
     fn resolve_source_location(
@@ -119,8 +134,9 @@ impl SourceFileServer for CompileHandler {
     /// fixme: character is 0-based, UTF-16 code unit.
     /// We treat it as UTF-8 now.
     async fn resolve_source_span(&self, loc: Location) -> Result<Option<SourceSpanOffset>, Error> {
-        let snap = self.snapshot()?.receive().await?;
-        Ok(Self::resolve_source_span(&snap.world, loc))
+        let snap = self.artifact()?.receive().await?;
+        let doc = snap.success_doc();
+        Ok(Self::resolve_source_span(snap.world(), doc.as_deref(), loc))
     }
 
     /// fixme: character is 0-based, UTF-16 code unit.
@@ -188,6 +204,10 @@ pub struct PreviewCliArgs {
     #[clap(flatten)]
     pub compile: CompileOnceArgs,
 
+    /// Access arguments
+    #[clap(flatten)]
+    pub access: PreviewAccessArgs,
+
     /// Preview mode
     #[clap(long = "preview-mode", default_value = "document", value_name = "MODE")]
     pub preview_mode: PreviewMode,
@@ -221,6 +241,11 @@ pub struct PreviewCliArgs {
     )]
     pub static_file_host: String,
 
+    /// Only forward the responses of the previewer to the control plane, and
+    /// reject the requests changing the files from the control plane.
+    #[clap(long = "control-plane-read-only")]
+    pub control_plane_read_only: bool,
+
     /// Let it not be the primary instance.
     #[clap(long = "not-primary", hide(true))]
     pub not_as_primary: bool,
@@ -228,6 +253,18 @@ pub struct PreviewCliArgs {
     /// Don't open the preview in the browser after compilation.
     #[clap(long = "no-open")]
     pub dont_open_in_browser: bool,
+
+    /// Serve all the documents in the directory, which are listed on an index
+    /// page and previewed under `/doc/<path>`, instead of the input file.
+    #[clap(long = "serve-dir", value_name = "DIR")]
+    pub serve_dir: Option<PathBuf>,
+
+    /// Compare the document against a baseline, which is shown at `/diff`.
+    /// The baseline is `snapshot` (the first compiled document), `export`
+    /// (the last exported document), `git:<rev>` or `file:<path>` (a snapshot
+    /// of the main file).
+    #[clap(long = "diff-baseline", value_name = "BASELINE")]
+    pub diff_baseline: Option<DiffBaseline>,
 }
 
 /// The global state of the preview tool.
@@ -281,6 +318,7 @@ impl PreviewState {
         if !args.static_file_host.is_empty() && (args.static_file_host != args.data_plane_host) {
             return Err(internal_error("--static-file-host is removed"));
         }
+        let access = PreviewAccess::new(&args.access).map_err(|e| invalid_params(e.to_string()))?;
 
         let (lsp_tx, lsp_rx) = ControlPlaneTx::new(false);
         let ControlPlaneRx {
@@ -309,6 +347,7 @@ impl PreviewState {
                     }
                     EditorScrollTo(s) => client.send_notification::<ScrollSource>(s),
                     Outline(s) => client.send_notification::<NotifDocumentOutline>(s),
+                    SlideState(s) => client.send_notification::<NotifSlideState>(s),
                 }
             }
 
@@ -340,10 +379,22 @@ impl PreviewState {
             // The fence must be put after the previewer is initialized.
             compile_handler.flush_compile();
 
+            let diff = previewer.diff_handle();
+            if let Some(baseline) = args.diff_baseline {
+                diff::spawn_diff_baseline(diff.clone(), compile_handler.clone(), baseline);
+            }
+
             // Replace the data plane port in the html to self
             let frontend_html = frontend_html(TYPST_PREVIEW_HTML, args.preview_mode, "/");
 
-            let srv = make_http_server(frontend_html, args.data_plane_host, websocket_tx).await;
+            let srv = make_http_server(
+                frontend_html,
+                args.data_plane_host,
+                websocket_tx,
+                access,
+                Some(diff),
+            )
+            .await;
             let addr = srv.addr;
             log::info!("PreviewTask({task_id}): preview server listening on: {addr}");
 
@@ -378,6 +429,34 @@ impl PreviewState {
         just_future(async move { rx.await.map_err(|_| internal_error("cancelled"))? })
     }
 
+    /// Export the previewed document to a static site in the directory.
+    pub fn export_site(&self, task_id: String, dir: PathBuf) -> AnySchedulableResponse {
+        let (tx, rx) = oneshot::channel();
+
+        let sent = self
+            .preview_tx
+            .send(PreviewRequest::ExportSite(task_id, dir, tx));
+        sent.map_err(|_| internal_error("failed to send export request"))?;
+
+        just_future(async move { rx.await.map_err(|_| internal_error("cancelled"))? })
+    }
+
+    /// Set the baseline that the previewed document is compared against.
+    pub fn set_diff_baseline(
+        &self,
+        task_id: String,
+        baseline: DiffBaseline,
+    ) -> AnySchedulableResponse {
+        let (tx, rx) = oneshot::channel();
+
+        let sent = self
+            .preview_tx
+            .send(PreviewRequest::SetDiffBaseline(task_id, baseline, tx));
+        sent.map_err(|_| internal_error("failed to send diff request"))?;
+
+        just_future(async move { rx.await.map_err(|_| internal_error("cancelled"))? })
+    }
+
     /// Scroll the preview to a given position.
     pub fn scroll(&self, task_id: String, req: ControlPlaneMessage) -> AnySchedulableResponse {
         let sent = self.preview_tx.send(PreviewRequest::Scroll(task_id, req));
@@ -397,62 +476,160 @@ pub struct HttpServer {
     pub join: tokio::task::JoinHandle<()>,
 }
 
+/// The pages and the websocket channels served by a preview server.
+#[async_trait::async_trait]
+pub trait PreviewRoutes: Send + Sync + 'static {
+    /// Get the page at the path. Other paths are redirected to `/`.
+    async fn page(&self, path: &str) -> Option<hyper::body::Bytes>;
+
+    /// Get the channel accepting the websockets connected at the path.
+    fn websocket(&self, path: &str) -> Option<mpsc::UnboundedSender<HyperWebsocket>>;
+}
+
+/// Serves a single preview at `/`, and its visual diff at `/diff`.
+struct SinglePreview {
+    frontend_html: hyper::body::Bytes,
+    websocket_tx: mpsc::UnboundedSender<HyperWebsocket>,
+    diff: Option<DiffHandle>,
+}
+
+#[async_trait::async_trait]
+impl PreviewRoutes for SinglePreview {
+    async fn page(&self, path: &str) -> Option<hyper::body::Bytes> {
+        match path {
+            "/" => Some(self.frontend_html.clone()),
+            "/diff" => {
+                // Rendering the pages of both documents blocks for a while.
+                let diff = self.diff.clone()?;
+                let page = tokio::task::spawn_blocking(move || diff.render()).await;
+                Some(page.ok()?.into())
+            }
+            _ => None,
+        }
+    }
+
+    fn websocket(&self, _path: &str) -> Option<mpsc::UnboundedSender<HyperWebsocket>> {
+        Some(self.websocket_tx.clone())
+    }
+}
+
 /// Create a http server for the previewer.
 pub async fn make_http_server(
     frontend_html: String,
     static_file_addr: String,
     websocket_tx: mpsc::UnboundedSender<HyperWebsocket>,
+    access: PreviewAccess,
+    diff: Option<DiffHandle>,
 ) -> HttpServer {
+    let routes = SinglePreview {
+        frontend_html: frontend_html.into(),
+        websocket_tx,
+        diff,
+    };
+    make_routed_http_server(Arc::new(routes), static_file_addr, access).await
+}
+
+/// Create a http server serving the pages and the websockets of the routes.
+pub async fn make_routed_http_server(
+    routes: Arc<dyn PreviewRoutes>,
+    static_file_addr: String,
+    access: PreviewAccess,
+) -> HttpServer {
+    use access::{Authorized, BoxedIo};
     use http_body_util::Full;
     use hyper::body::{Bytes, Incoming};
     type Server = hyper_util::server::conn::auto::Builder<hyper_util::rt::TokioExecutor>;
 
-    let frontend_html = hyper::body::Bytes::from(frontend_html);
-    let make_service = move || {
-        let frontend_html = frontend_html.clone();
-        let websocket_tx = websocket_tx.clone();
-        service_fn(move |mut req: hyper::Request<Incoming>| {
-            let frontend_html = frontend_html.clone();
-            let websocket_tx = websocket_tx.clone();
-            async move {
-                // Check if the request is a websocket upgrade request.
-                if hyper_tungstenite::is_upgrade_request(&req) {
-                    let (response, websocket) = hyper_tungstenite::upgrade(&mut req, None)
-                        .map_err(|e| {
-                            log::error!("Error in websocket upgrade: {e}");
-                            // let e = Error::new(e);
-                        })
-                        .unwrap();
-
-                    let _ = websocket_tx.send(websocket);
-
-                    // Return the response so the spawned future can continue.
-                    Ok(response)
-                } else if req.uri().path() == "/" {
-                    // log::debug!("Serve frontend: {mode:?}");
-                    let res = hyper::Response::builder()
-                        .header(hyper::header::CONTENT_TYPE, "text/html")
-                        .body(Full::<Bytes>::from(frontend_html))
-                        .unwrap();
-                    Ok::<_, std::convert::Infallible>(res)
-                } else {
-                    // jump to /
-                    let res = hyper::Response::builder()
-                        .status(hyper::StatusCode::FOUND)
-                        .header(hyper::header::LOCATION, "/")
-                        .body(Full::<Bytes>::default())
-                        .unwrap();
-                    Ok(res)
-                }
-            }
-        })
-    };
-
     let listener = tokio::net::TcpListener::bind(&static_file_addr)
         .await
         .unwrap();
     let addr = listener.local_addr().unwrap();
-    log::info!("preview server listening on http://{addr}");
+    log::info!("preview server listening on {}://{addr}", access.scheme());
+
+    // The cookies are shared by the ports of a host, so the port is included
+    // to distinguish the tokens of the servers.
+    let cookie: Arc<str> = format!("tinymist-preview-token-{}", addr.port()).into();
+    let access = Arc::new(access);
+
+    let make_service = {
+        let access = access.clone();
+        move || {
+            let routes = routes.clone();
+            let access = access.clone();
+            let cookie = cookie.clone();
+            service_fn(move |mut req: hyper::Request<Incoming>| {
+                let routes = routes.clone();
+                let access = access.clone();
+                let cookie = cookie.clone();
+                async move {
+                    let path = req.uri().path().to_owned();
+
+                    let authorized = access.authorize(&req, &cookie);
+                    if authorized == Authorized::No {
+                        let res = hyper::Response::builder()
+                            .status(hyper::StatusCode::UNAUTHORIZED)
+                            .header(hyper::header::CONTENT_TYPE, "text/plain")
+                            .body(Full::<Bytes>::from("a valid token is required"))
+                            .unwrap();
+                        return Ok::<_, std::convert::Infallible>(res);
+                    }
+
+                    let is_upgrade = hyper_tungstenite::is_upgrade_request(&req);
+                    // Keep the token in the browser for the websockets and the
+                    // later visits, and redirect to the path without the
+                    // token, which then stays out of the history and the
+                    // `Referer` header.
+                    if authorized == Authorized::ByQuery && !is_upgrade {
+                        let mut res = hyper::Response::builder()
+                            .status(hyper::StatusCode::FOUND)
+                            .header(hyper::header::LOCATION, path.as_str());
+                        if let Some(set_cookie) = access.set_cookie(&cookie) {
+                            res = res.header(hyper::header::SET_COOKIE, set_cookie);
+                        }
+                        let res = res.body(Full::<Bytes>::default()).unwrap();
+                        return Ok(res);
+                    }
+
+                    // Check if the request is a websocket upgrade request.
+                    if is_upgrade {
+                        let Some(websocket_tx) = routes.websocket(&path) else {
+                            let res = hyper::Response::builder()
+                                .status(hyper::StatusCode::NOT_FOUND)
+                                .body(Full::<Bytes>::default())
+                                .unwrap();
+                            return Ok(res);
+                        };
+
+                        let (response, websocket) = hyper_tungstenite::upgrade(&mut req, None)
+                            .map_err(|e| {
+                                log::error!("Error in websocket upgrade: {e}");
+                                // let e = Error::new(e);
+                            })
+                            .unwrap();
+
+                        let _ = websocket_tx.send(websocket);
+
+                        // Return the response so the spawned future can continue.
+                        Ok(response)
+                    } else if let Some(page) = routes.page(&path).await {
+                        let res = hyper::Response::builder()
+                            .header(hyper::header::CONTENT_TYPE, "text/html")
+                            .body(Full::<Bytes>::from(page))
+                            .unwrap();
+                        Ok(res)
+                    } else {
+                        // jump to /
+                        let res = hyper::Response::builder()
+                            .status(hyper::StatusCode::FOUND)
+                            .header(hyper::header::LOCATION, "/")
+                            .body(Full::<Bytes>::default())
+                            .unwrap();
+                        Ok(res)
+                    }
+                }
+            })
+        }
+    };
 
     let (shutdown_tx, rx) = tokio::sync::oneshot::channel();
     let (final_tx, final_rx) = tokio::sync::oneshot::channel();
@@ -460,7 +637,20 @@ pub async fn make_http_server(
     // the graceful watcher
     let graceful = hyper_util::server::graceful::GracefulShutdown::new();
 
-    let serve_conn = move |server: &Server, graceful: &GracefulShutdown, conn| {
+    let serve_conn = move |server: &Server, graceful: &GracefulShutdown, io: BoxedIo| {
+        let conn = server.serve_connection_with_upgrades(TokioIo::new(io), make_service());
+        let conn = graceful.watch(conn.into_owned());
+        tokio::spawn(async move {
+            if let Err(err) = conn.await {
+                log::error!("Error serving connection: {err:?}");
+            }
+        });
+    };
+
+    // The connections are accepted in other tasks, as the TLS handshakes may
+    // take a while.
+    let (accepted_tx, mut accepted_rx) = mpsc::unbounded_channel();
+    let accept_conn = move |conn: std::io::Result<_>| {
         let (stream, _peer_addr) = match conn {
             Ok(conn) => conn,
             Err(e) => {
@@ -469,11 +659,11 @@ pub async fn make_http_server(
             }
         };
 
-        let conn = server.serve_connection_with_upgrades(TokioIo::new(stream), make_service());
-        let conn = graceful.watch(conn.into_owned());
+        let access = access.clone();
+        let accepted_tx = accepted_tx.clone();
         tokio::spawn(async move {
-            if let Err(err) = conn.await {
-                log::error!("Error serving connection: {err:?}");
+            if let Some(io) = access.accept(stream).await {
+                let _ = accepted_tx.send(io);
             }
         });
     };
@@ -487,7 +677,8 @@ pub async fn make_http_server(
 
         loop {
             tokio::select! {
-                conn = listener.accept() => serve_conn(&server, &graceful, conn),
+                conn = listener.accept() => accept_conn(conn),
+                Some(io) = accepted_rx.recv() => serve_conn(&server, &graceful, io),
                 Ok(_) = &mut signal => {
                     log::info!("graceful shutdown signal received");
                     break;
@@ -521,62 +712,43 @@ pub async fn make_http_server(
 pub async fn preview_main(args: PreviewCliArgs) -> anyhow::Result<()> {
     log::info!("Arguments: {args:#?}");
 
-    let static_file_host =
-        if args.static_file_host == args.data_plane_host || !args.static_file_host.is_empty() {
-            Some(args.static_file_host)
-        } else {
-            None
-        };
-
     tokio::spawn(async move {
         let _ = tokio::signal::ctrl_c().await;
         log::info!("Ctrl-C received, exiting");
         std::process::exit(0);
     });
 
-    let verse = args.compile.resolve()?;
-
-    let (service, handle) = {
-        // type EditorSender = mpsc::UnboundedSender<EditorRequest>;
-        let (editor_tx, mut editor_rx) = mpsc::unbounded_channel();
-        let (intr_tx, intr_rx) = mpsc::unbounded_channel();
-
-        let handle = Arc::new(CompileHandler {
-            inner: Default::default(),
-            diag_group: "main".to_owned(),
-            intr_tx: intr_tx.clone(),
-            // export_tx,
-            export: Default::default(),
-            editor_tx,
-            analysis: Arc::default(),
-            stats: Default::default(),
-            notified_revision: parking_lot::Mutex::new(0),
-        });
+    let static_file_host =
+        if args.static_file_host == args.data_plane_host || !args.static_file_host.is_empty() {
+            Some(args.static_file_host.clone())
+        } else {
+            None
+        };
 
-        // Consume editor_rx
-        tokio::spawn(async move { while editor_rx.recv().await.is_some() {} });
+    if let Some(dir) = args.serve_dir.clone() {
+        return serve_dir::serve_dir_main(args, &dir, static_file_host).await;
+    }
 
-        let service = CompileServerActor::new_with(
-            verse,
-            intr_tx,
-            intr_rx,
-            CompileServerOpts {
-                compile_handle: handle.clone(),
-                ..Default::default()
-            },
-        )
-        .with_watch(true);
+    let verse = args.compile.resolve()?;
+    let access = PreviewAccess::new(&args.access)?;
 
-        (service, handle)
-    };
+    let (service, handle) = compile_service(verse);
 
     let (lsp_tx, mut lsp_rx) = ControlPlaneTx::new(true);
 
+    let read_only = args.control_plane_read_only;
+    let control_access = access.clone();
     let control_plane_server_handle = tokio::spawn(async move {
         let (control_sock_tx, mut control_sock_rx) = mpsc::unbounded_channel();
 
-        let srv =
-            make_http_server(String::default(), args.control_plane_host, control_sock_tx).await;
+        let srv = make_http_server(
+            String::default(),
+            args.control_plane_host,
+            control_sock_tx,
+            control_access,
+            None,
+        )
+        .await;
         log::info!("Control panel server listening on: {}", srv.addr);
 
         let control_websocket = control_sock_rx.recv().await.unwrap();
@@ -617,6 +789,10 @@ pub async fn preview_main(args: PreviewCliArgs) -> anyhow::Result<()> {
                             log::warn!("failed to parse control plane request: {msg:?}");
                             break;
                         };
+                        if read_only && msg.changes_files() {
+                            log::warn!("rejected control plane request changing files in read-only mode");
+                            continue;
+                        }
 
                         lsp_rx.ctl_tx.send(msg).unwrap();
                     } else {
@@ -641,24 +817,37 @@ pub async fn preview_main(args: PreviewCliArgs) -> anyhow::Result<()> {
 
     bind_streams(&mut previewer, websocket_rx);
 
+    let diff = previewer.diff_handle();
+    if let Some(baseline) = args.diff_baseline {
+        diff::spawn_diff_baseline(diff.clone(), handle.clone(), baseline);
+    }
+
     let frontend_html = frontend_html(TYPST_PREVIEW_HTML, args.preview_mode, "/");
 
     let static_server = if let Some(static_file_host) = static_file_host {
         log::warn!("--static-file-host is deprecated, which will be removed in the future. Use --data-plane-host instead.");
         let html = frontend_html.clone();
-        Some(make_http_server(html, static_file_host, websocket_tx.clone()).await)
+        let (access, websocket_tx, diff) = (access.clone(), websocket_tx.clone(), diff.clone());
+        Some(make_http_server(html, static_file_host, websocket_tx, access, Some(diff)).await)
     } else {
         None
     };
 
-    let srv = make_http_server(frontend_html, args.data_plane_host, websocket_tx).await;
+    let srv = make_http_server(
+        frontend_html,
+        args.data_plane_host,
+        websocket_tx,
+        access.clone(),
+        Some(diff),
+    )
+    .await;
     log::info!("Data plane server listening on: {}", srv.addr);
 
     let static_server_addr = static_server.as_ref().map(|s| s.addr).unwrap_or(srv.addr);
     log::info!("Static file server listening on: {static_server_addr}");
 
     if !args.dont_open_in_browser {
-        if let Err(e) = open::that_detached(format!("http://{static_server_addr}")) {
+        if let Err(e) = open::that_detached(access.url(static_server_addr)) {
             log::error!("failed to open browser: {e}");
         };
     }
@@ -670,6 +859,54 @@ pub async fn preview_main(args: PreviewCliArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Create a compiler watching the universe, whose results are consumed by the
+/// handler.
+fn compile_service(
+    verse: LspUniverse,
+) -> (CompileServerActor<LspCompilerFeat>, Arc<CompileHandler>) {
+    let (intr_tx, intr_rx) = mpsc::unbounded_channel();
+    let handle = compile_handler(intr_tx.clone());
+
+    let service = CompileServerActor::new_with(
+        verse,
+        intr_tx,
+        intr_rx,
+        CompileServerOpts {
+            compile_handle: handle.clone(),
+            ..Default::default()
+        },
+    )
+    .with_watch(true);
+
+    (service, handle)
+}
+
+/// Create a handler consuming the results of the compiler interrupted by
+/// `intr_tx`.
+fn compile_handler(
+    intr_tx: mpsc::UnboundedSender<Interrupt<LspCompilerFeat>>,
+) -> Arc<CompileHandler> {
+    // type EditorSender = mpsc::UnboundedSender<EditorRequest>;
+    let (editor_tx, mut editor_rx) = mpsc::unbounded_channel();
+
+    let handle = Arc::new(CompileHandler {
+        inner: Default::default(),
+        diag_group: "main".to_owned(),
+        intr_tx,
+        // export_tx,
+        export: Default::default(),
+        editor_tx,
+        analysis: Arc::default(),
+        stats: Default::default(),
+        notified_revision: Arc::new(parking_lot::Mutex::new(0)),
+    });
+
+    // Consume editor_rx
+    tokio::spawn(async move { while editor_rx.recv().await.is_some() {} });
+
+    handle
+}
+
 struct ScrollSource;
 
 impl Notification for ScrollSource {
@@ -684,61 +921,11 @@ impl Notification for NotifDocumentOutline {
     const METHOD: &'static str = "tinymist/documentOutline";
 }
 
-/// Find the output location in the document for a cursor position.
-fn jump_from_cursor(document: &TypstDocument, source: &Source, cursor: usize) -> Vec<Position> {
-    let Some(node) = LinkedNode::new(source.root())
-        .leaf_at_compat(cursor)
-        .filter(|node| node.kind() == SyntaxKind::Text)
-    else {
-        return vec![];
-    };
-
-    let mut p = Point::default();
-
-    let span = node.span();
-    let mut positions: Vec<Position> = vec![];
-    for (i, page) in document.pages.iter().enumerate() {
-        let mut min_dis = u64::MAX;
-        if let Some(pos) = find_in_frame(&page.frame, span, &mut min_dis, &mut p) {
-            if let Some(page) = NonZeroUsize::new(i + 1) {
-                positions.push(Position { page, point: pos });
-            }
-        }
-    }
-
-    log::info!("jump_from_cursor: {positions:#?}");
-
-    positions
-}
-
-/// Find the position of a span in a frame.
-fn find_in_frame(frame: &Frame, span: Span, min_dis: &mut u64, p: &mut Point) -> Option<Point> {
-    for (mut pos, item) in frame.items() {
-        if let FrameItem::Group(group) = item {
-            // TODO: Handle transformation.
-            if let Some(point) = find_in_frame(&group.frame, span, min_dis, p) {
-                return Some(point + pos);
-            }
-        }
-
-        if let FrameItem::Text(text) = item {
-            for glyph in &text.glyphs {
-                if glyph.span.0 == span {
-                    return Some(pos);
-                }
-                if glyph.span.0.id() == span.id() {
-                    let dis = glyph.span.0.number().abs_diff(span.number());
-                    if dis < *min_dis {
-                        *min_dis = dis;
-                        *p = pos;
-                    }
-                }
-                pos.x += glyph.x_advance.at(text.size);
-            }
-        }
-    }
+struct NotifSlideState;
 
-    None
+impl Notification for NotifSlideState {
+    type Params = typst_preview::SlideState;
+    const METHOD: &'static str = "tinymist/preview/slideState";
 }
 
 fn bind_streams(previewer: &mut Previewer, websocket_rx: mpsc::UnboundedReceiver<HyperWebsocket>) {
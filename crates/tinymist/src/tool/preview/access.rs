@@ -0,0 +1,290 @@
+//! Controls the access to the preview servers, which are possibly exposed to
+//! other machines.
+
+use std::io::BufReader;
+use std::net::SocketAddr;
+
+use anyhow::Context;
+use hyper::header::{HeaderValue, COOKIE};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+use super::*;
+
+/// The time to wait for a TLS handshake, after which the connection is dropped.
+const HANDSHAKE_TIMEOUT: reflexo::time::Duration = reflexo::time::Duration::from_secs(10);
+
+/// CLI Arguments controlling the access to the preview servers.
+#[derive(Debug, Clone, Default, clap::Parser)]
+pub struct PreviewAccessArgs {
+    /// Require a token to access the preview servers. The token is passed by
+    /// the `token` query of the url, e.g. `http://127.0.0.1:23625/?token=xxx`,
+    /// and then kept by a cookie in the browser.
+    #[clap(long = "token", value_name = "TOKEN", env = "TINYMIST_PREVIEW_TOKEN")]
+    pub token: Option<String>,
+
+    /// Serve the preview over TLS with the certificate chain in the PEM file.
+    #[clap(long = "tls-cert", value_name = "FILE", requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// Serve the preview over TLS with the private key in the PEM file.
+    #[clap(long = "tls-key", value_name = "FILE", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+}
+
+/// The access control of a preview server.
+#[derive(Default, Clone)]
+pub struct PreviewAccess {
+    /// The token required to access the server.
+    token: Option<String>,
+    /// The acceptor of the TLS connections.
+    tls: Option<TlsAcceptor>,
+}
+
+/// The result of authorizing a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Authorized {
+    /// The request is authorized by the cookie or no token is required.
+    Yes,
+    /// The request is authorized by the token in the query, which should be
+    /// kept by a cookie.
+    ByQuery,
+    /// The request is not authorized.
+    No,
+}
+
+impl PreviewAccess {
+    /// Create the access control from the arguments.
+    pub fn new(args: &PreviewAccessArgs) -> anyhow::Result<Self> {
+        let tls = match (&args.tls_cert, &args.tls_key) {
+            (Some(cert), Some(key)) => Some(tls_acceptor(cert, key)?),
+            (None, None) => None,
+            _ => anyhow::bail!("both --tls-cert and --tls-key are required to enable TLS"),
+        };
+        let token = args.token.clone().filter(|token| !token.is_empty());
+
+        Ok(Self { token, tls })
+    }
+
+    /// The scheme of the urls to the server.
+    pub fn scheme(&self) -> &'static str {
+        if self.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// The url to open the server in the browser, which carries the token.
+    pub fn url(&self, addr: SocketAddr) -> String {
+        let scheme = self.scheme();
+        match &self.token {
+            Some(token) => {
+                let token = utf8_percent_encode(token, NON_ALPHANUMERIC);
+                format!("{scheme}://{addr}/?token={token}")
+            }
+            None => format!("{scheme}://{addr}"),
+        }
+    }
+
+    /// Accept a connection to the server, which performs the TLS handshake if
+    /// TLS is enabled. The peers not finishing the handshake in time are
+    /// dropped.
+    pub(super) async fn accept(&self, stream: tokio::net::TcpStream) -> Option<BoxedIo> {
+        let Some(tls) = &self.tls else {
+            return Some(Box::new(stream));
+        };
+
+        match tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.accept(stream)).await {
+            Ok(Ok(stream)) => Some(Box::new(stream)),
+            Ok(Err(err)) => {
+                log::error!("TLS handshake error: {err}");
+                None
+            }
+            Err(..) => {
+                log::warn!("TLS handshake timed out after {HANDSHAKE_TIMEOUT:?}");
+                None
+            }
+        }
+    }
+
+    /// Check whether the request carries the token in the query or the cookie.
+    pub(super) fn authorize<B>(&self, req: &hyper::Request<B>, cookie: &str) -> Authorized {
+        let Some(token) = &self.token else {
+            return Authorized::Yes;
+        };
+
+        let by_cookie = req
+            .headers()
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .any(|(name, value)| name == cookie && token_eq(value, token));
+        if by_cookie {
+            return Authorized::Yes;
+        }
+
+        let by_query = req
+            .uri()
+            .query()
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .filter_map(|pair| pair.split_once('='))
+            .any(|(name, value)| name == "token" && token_eq(value, token));
+        if by_query {
+            return Authorized::ByQuery;
+        }
+
+        Authorized::No
+    }
+
+    /// The `Set-Cookie` header keeping the token in the browser.
+    pub(super) fn set_cookie(&self, cookie: &str) -> Option<HeaderValue> {
+        let token = self.token.as_ref()?;
+        let token = utf8_percent_encode(token, NON_ALPHANUMERIC);
+        let secure = if self.tls.is_some() { "; Secure" } else { "" };
+        let value = format!("{cookie}={token}; Path=/; HttpOnly; SameSite=Strict{secure}");
+        HeaderValue::from_str(&value).ok()
+    }
+}
+
+/// A connection accepted by the server.
+pub(super) trait PreviewIo: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> PreviewIo for T {}
+
+/// A boxed connection accepted by the server, which is either a TCP or a TLS
+/// stream.
+pub(super) type BoxedIo = Box<dyn PreviewIo>;
+
+/// Compare the percent-encoded value with the token in constant time.
+fn token_eq(value: &str, token: &str) -> bool {
+    let value = percent_decode_str(value).collect::<Vec<_>>();
+    let token = token.as_bytes();
+    value.len() == token.len() && value.iter().zip(token).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Load the certificate chain and the private key for TLS.
+fn tls_acceptor(cert: &Path, key: &Path) -> anyhow::Result<TlsAcceptor> {
+    let open = |path: &Path| {
+        std::fs::File::open(path)
+            .map(BufReader::new)
+            .with_context(|| format!("cannot open {path:?}"))
+    };
+
+    let certs = rustls_pemfile::certs(&mut open(cert)?)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("cannot read the certificates in {cert:?}"))?;
+    let key = rustls_pemfile::private_key(&mut open(key)?)
+        .with_context(|| format!("cannot read the private key in {key:?}"))?
+        .with_context(|| format!("no private key is found in {key:?}"))?;
+
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("invalid certificate or private key")?;
+    // The websocket connections are upgraded from HTTP/1.1.
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_rustls::rustls::server::ResolvesServerCertUsingSni;
+
+    use super::*;
+
+    const COOKIE_NAME: &str = "tinymist-preview-token";
+
+    fn access(token: Option<&str>) -> PreviewAccess {
+        PreviewAccess {
+            token: token.map(ToOwned::to_owned),
+            tls: None,
+        }
+    }
+
+    fn authorize(access: &PreviewAccess, uri: &str, cookie: Option<&str>) -> Authorized {
+        let mut req = hyper::Request::builder().uri(uri);
+        if let Some(cookie) = cookie {
+            req = req.header(COOKIE, cookie);
+        }
+        access.authorize(&req.body(()).unwrap(), COOKIE_NAME)
+    }
+
+    #[test]
+    fn test_token_eq() {
+        assert!(token_eq("secret", "secret"));
+        assert!(token_eq("a%20b%26c", "a b&c"));
+        assert!(!token_eq("secreT", "secret"));
+        assert!(!token_eq("secret1", "secret"));
+        assert!(!token_eq("", "secret"));
+    }
+
+    #[test]
+    fn test_authorize() {
+        let access = access(Some("secret"));
+        let cookie = format!("theme=dark; {COOKIE_NAME}=secret");
+        assert_eq!(authorize(&access, "/", Some(&cookie)), Authorized::Yes);
+        assert_eq!(
+            authorize(&access, "/?token=secret", None),
+            Authorized::ByQuery
+        );
+        // The cookie takes precedence over the query.
+        let res = authorize(&access, "/?token=secret", Some(&cookie));
+        assert_eq!(res, Authorized::Yes);
+
+        assert_eq!(authorize(&access, "/", None), Authorized::No);
+        assert_eq!(authorize(&access, "/?token=secreT", None), Authorized::No);
+        assert_eq!(authorize(&access, "/?token=secre", None), Authorized::No);
+        let cookie = format!("{COOKIE_NAME}=secret1");
+        assert_eq!(authorize(&access, "/", Some(&cookie)), Authorized::No);
+        let res = authorize(&access, "/", Some("other-token=secret"));
+        assert_eq!(res, Authorized::No);
+    }
+
+    #[test]
+    fn test_authorize_encoded_token() {
+        let access = access(Some("a b&c"));
+        let res = authorize(&access, "/?token=a%20b%26c", None);
+        assert_eq!(res, Authorized::ByQuery);
+        let cookie = format!("{COOKIE_NAME}=a%20b%26c");
+        assert_eq!(authorize(&access, "/", Some(&cookie)), Authorized::Yes);
+        assert_eq!(authorize(&access, "/?token=a%20b&c", None), Authorized::No);
+    }
+
+    #[test]
+    fn test_authorize_without_token() {
+        let access = access(None);
+        assert_eq!(authorize(&access, "/", None), Authorized::Yes);
+        assert_eq!(authorize(&access, "/?token=secret", None), Authorized::Yes);
+        assert_eq!(access.set_cookie(COOKIE_NAME), None);
+    }
+
+    #[test]
+    fn test_set_cookie() {
+        let plain = access(Some("a b"));
+        let cookie = plain.set_cookie(COOKIE_NAME).unwrap();
+        assert_eq!(
+            cookie,
+            "tinymist-preview-token=a%20b; Path=/; HttpOnly; SameSite=Strict"
+        );
+
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(ResolvesServerCertUsingSni::new()));
+        let tls = PreviewAccess {
+            tls: Some(TlsAcceptor::from(Arc::new(config))),
+            ..plain
+        };
+        let cookie = tls.set_cookie(COOKIE_NAME).unwrap();
+        assert_eq!(
+            cookie,
+            "tinymist-preview-token=a%20b; Path=/; HttpOnly; SameSite=Strict; Secure"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Resolves the baselines that the previewed document is compared against.
+
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use reflexo_typst::{Compiler, ShadowApi};
+use serde::Deserialize;
+use typst_preview::DiffHandle;
+
+use super::*;
+
+/// The baseline of the visual diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DiffBaseline {
+    /// The document currently previewed.
+    Snapshot,
+    /// The document last exported by the compiler.
+    Export,
+    /// The document compiled from the files at a git revision.
+    Git {
+        /// The revision, e.g. `HEAD` or `main~1`.
+        rev: String,
+    },
+    /// The document compiled with a snapshot of the main file.
+    File {
+        /// The path to the snapshot.
+        path: PathBuf,
+    },
+}
+
+impl FromStr for DiffBaseline {
+    type Err = String;
+
+    /// Parse `snapshot`, `export`, `git:<rev>` or `file:<path>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            _ if s == "snapshot" => Ok(Self::Snapshot),
+            _ if s == "export" => Ok(Self::Export),
+            Some(("git", rev)) if !rev.is_empty() && !rev.starts_with('-') => Ok(Self::Git {
+                rev: rev.to_owned(),
+            }),
+            Some(("file", path)) if !path.is_empty() => Ok(Self::File { path: path.into() }),
+            _ => Err(format!(
+                "invalid diff baseline {s:?}, expected snapshot, export, git:<rev> or file:<path>"
+            )),
+        }
+    }
+}
+
+/// Resolve the baseline and set it to the diff of the previewer.
+pub(crate) async fn set_diff_baseline(
+    diff: &DiffHandle,
+    handler: &CompileHandler,
+    baseline: DiffBaseline,
+) -> anyhow::Result<()> {
+    let document = match baseline {
+        DiffBaseline::Snapshot => {
+            if !diff.snapshot() {
+                bail!("the document is not compiled yet");
+            }
+            return Ok(());
+        }
+        DiffBaseline::Export => handler
+            .last_exported()
+            .context("no document has been exported yet")?,
+        DiffBaseline::Git { .. } | DiffBaseline::File { .. } => {
+            let snap = handler.snapshot();
+            let snap = snap.map_err(|err| anyhow::anyhow!("failed to snapshot: {err}"))?;
+            let snap = snap.receive().await;
+            let snap = snap.map_err(|err| anyhow::anyhow!("failed to snapshot: {err}"))?;
+            tokio::task::spawn_blocking(move || compile_baseline(&snap.world, &baseline)).await??
+        }
+    };
+
+    diff.set_baseline(Some(document));
+    Ok(())
+}
+
+/// Set the baseline given by the arguments once the previewer starts. The
+/// snapshot is taken when the document is compiled for the first time.
+pub(crate) fn spawn_diff_baseline(
+    diff: DiffHandle,
+    handler: Arc<CompileHandler>,
+    baseline: DiffBaseline,
+) {
+    tokio::spawn(async move {
+        if baseline == DiffBaseline::Snapshot {
+            while !diff.snapshot() {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+            return;
+        }
+
+        if let Err(err) = set_diff_baseline(&diff, &handler, baseline).await {
+            log::error!("failed to set the diff baseline: {err:#}");
+        }
+    });
+}
+
+/// Compile the document with the files shadowed by the baseline. A git
+/// baseline is compiled from the files at the revision, while a file baseline
+/// keeps the unsaved changes of the other files.
+fn compile_baseline(
+    world: &LspWorld,
+    baseline: &DiffBaseline,
+) -> anyhow::Result<Arc<TypstDocument>> {
+    let entry = world.entry_state();
+    let root = entry.root().context("no root is set for the document")?;
+    let main = entry
+        .main()
+        .context("no main file is set for the document")?;
+    let main = world
+        .path_for_id(main)
+        .context("cannot resolve the main file")?;
+
+    let mut forked = world.task(Default::default());
+    match baseline {
+        DiffBaseline::Snapshot | DiffBaseline::Export => {
+            bail!("{baseline:?} is not compiled from files")
+        }
+        DiffBaseline::File { path } => {
+            let path = root.join(path);
+            let content =
+                std::fs::read(&path).with_context(|| format!("cannot read snapshot {path:?}"))?;
+            forked
+                .map_shadow(&main, content.into())
+                .map_err(|err| anyhow::anyhow!("cannot shadow {main:?}: {err}"))?;
+        }
+        DiffBaseline::Git { rev } => {
+            // The baseline is built from the files on disk, so the unsaved
+            // changes in the editor don't leak into it.
+            forked.reset_shadow();
+            for (path, content) in git_changes(&root, rev)? {
+                // The files added after the revision are kept.
+                let Some(content) = content else {
+                    continue;
+                };
+                let path = root.join(path);
+                forked
+                    .map_shadow(&path, content.into())
+                    .map_err(|err| anyhow::anyhow!("cannot shadow {path:?}: {err}"))?;
+            }
+        }
+    }
+
+    let warned = std::marker::PhantomData.compile(&forked, &mut Default::default());
+    let warned = warned.map_err(|err| anyhow::anyhow!("cannot compile the baseline: {err:?}"))?;
+    Ok(warned.output)
+}
+
+/// Get the files under the root that are changed since the revision, with
+/// their contents at the revision if they exist.
+fn git_changes(root: &Path, rev: &str) -> anyhow::Result<Vec<(PathBuf, Option<Vec<u8>>)>> {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .output()
+            .context("cannot run git")
+    };
+
+    let output = git(&[
+        "diff",
+        "--name-only",
+        "--relative",
+        "--no-renames",
+        "-z",
+        rev,
+    ])?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!(
+            "cannot diff against git revision {rev:?}: {}",
+            stderr.trim()
+        );
+    }
+
+    let paths = String::from_utf8(output.stdout).context("invalid path from git")?;
+    let paths = paths.split('\0').filter(|path| !path.is_empty());
+    let changes = paths.map(|path| {
+        let output = git(&["show", &format!("{rev}:./{path}")])?;
+        let content = output.status.success().then_some(output.stdout);
+        Ok((PathBuf::from(path), content))
+    });
+
+    changes.collect()
+}
@@ -0,0 +1,437 @@
+//! Previews all the documents in a directory from one server.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Context;
+use hyper::body::Bytes;
+use parking_lot::Mutex;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use reflexo::path::unix_slash;
+use reflexo_typst::{CompileReport, EntryState, ImmutPath, TaskInputs, WorldDeps};
+
+use super::*;
+use crate::actor::typ_server::{CompilationHandle, CompileSnapshot, CompiledArtifact};
+use crate::world::LspUniverseBuilder;
+
+/// The prefix of the paths previewing the documents.
+const DOC_PREFIX: &str = "/doc/";
+
+/// The characters escaped in the paths of the documents.
+const PATH_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Serves the documents in a directory. The preview of a document is started
+/// when it is first visited, and stopped when it has been idle.
+struct ServeDir {
+    /// The directory, which is also the root of the documents.
+    root: ImmutPath,
+    /// The arguments of the preview tool.
+    args: PreviewCliArgs,
+    /// The compiler of the documents.
+    compiler: DocumentCompiler,
+}
+
+/// The previewed documents, by their paths.
+type Previews = Mutex<HashMap<String, Arc<DocumentPreview>>>;
+
+/// A previewed document, compiled in a task forked from the shared universe.
+struct DocumentPreview {
+    /// The entry of the document.
+    entry: EntryState,
+    /// The handler notifying the previewer of the compilations.
+    handle: Arc<CompileHandler>,
+    /// The websocket channel of the previewer.
+    websocket_tx: mpsc::UnboundedSender<HyperWebsocket>,
+    /// The files depended by the last compilation.
+    deps: Mutex<Vec<ImmutPath>>,
+    /// The count of the requested compilations, which identifies the latest
+    /// one.
+    requested: AtomicUsize,
+    /// Serializes the compilations of the document.
+    compiling: tokio::sync::Mutex<()>,
+}
+
+impl ServeDir {
+    /// Check whether the path is one of the listed documents, without listing
+    /// the directory.
+    fn is_document(&self, path: &str) -> bool {
+        let relative = Path::new(path);
+        let is_listed = relative.extension().is_some_and(|ext| ext == "typ")
+            && relative.components().all(|component| match component {
+                std::path::Component::Normal(name) => !name.to_string_lossy().starts_with('.'),
+                _ => false,
+            });
+        if !is_listed {
+            return false;
+        }
+
+        // The listed documents are not reached through symbolic links, so that
+        // their canonical paths are still in the directory.
+        let path = self.root.join(relative);
+        path.canonicalize().is_ok_and(|canonical| canonical == path) && path.is_file()
+    }
+
+    /// Get the websocket channel of the document previewed at the path, and
+    /// start the preview if it is not running.
+    fn preview(&self, path: &str) -> Option<mpsc::UnboundedSender<HyperWebsocket>> {
+        let path = path.strip_prefix(DOC_PREFIX)?;
+        let path = percent_decode_str(path).decode_utf8().ok()?;
+
+        let previews = &self.compiler.previews;
+        if let Some(preview) = previews.lock().get(&*path) {
+            return Some(preview.websocket_tx.clone());
+        }
+
+        // Only the listed documents are served, which also rejects the paths
+        // out of the directory. The file system is checked without holding
+        // the lock.
+        if !self.is_document(&path) {
+            return None;
+        }
+
+        let mut previews = previews.lock();
+        // The preview may be started by another request in the meantime.
+        if let Some(preview) = previews.get(&*path) {
+            return Some(preview.websocket_tx.clone());
+        }
+
+        let main = TypstFileId::new(None, VirtualPath::new(&*path));
+        let (websocket_tx, websocket_rx) = mpsc::unbounded_channel();
+        let preview = Arc::new(DocumentPreview {
+            entry: EntryState::new_rooted(self.root.clone(), Some(main)),
+            handle: compile_handler(self.compiler.intr_tx.clone()),
+            websocket_tx: websocket_tx.clone(),
+            deps: Mutex::default(),
+            requested: AtomicUsize::default(),
+            compiling: tokio::sync::Mutex::default(),
+        });
+        previews.insert(path.to_string(), preview.clone());
+
+        let args = PreviewArgs {
+            task_id: path.to_string(),
+            ..self.args.preview.clone()
+        };
+        let compiler = self.compiler.clone();
+        tokio::spawn(preview_document(compiler, preview, args, websocket_rx));
+
+        log::info!("PreviewTask({path}): started");
+        Some(websocket_tx)
+    }
+}
+
+#[async_trait::async_trait]
+impl PreviewRoutes for ServeDir {
+    async fn page(&self, path: &str) -> Option<Bytes> {
+        if path == "/" {
+            // Listing the directory blocks for a while.
+            let root = self.root.clone();
+            let page = tokio::task::spawn_blocking(move || index(&root)).await;
+            return Some(page.ok()?.into());
+        }
+
+        self.preview(path)?;
+        let frontend_html = frontend_html(TYPST_PREVIEW_HTML, self.args.preview_mode, path);
+        Some(frontend_html.into())
+    }
+
+    fn websocket(&self, path: &str) -> Option<mpsc::UnboundedSender<HyperWebsocket>> {
+        self.preview(path)
+    }
+}
+
+/// Compiles the previewed documents whenever the shared universe is revised.
+#[derive(Clone)]
+struct DocumentCompiler {
+    /// The compiler watching the universe shared by the documents.
+    intr_tx: mpsc::UnboundedSender<Interrupt<LspCompilerFeat>>,
+    /// The previewed documents.
+    previews: Arc<Previews>,
+}
+
+impl CompilationHandle<LspCompilerFeat> for DocumentCompiler {
+    fn status(&self, _revision: usize, _rep: CompileReport) {}
+
+    fn notify_compile(&self, _res: &CompiledArtifact<LspCompilerFeat>, _rep: CompileReport) {}
+
+    fn notify_suspended(&self, snap: &CompileSnapshot<LspCompilerFeat>) {
+        let previews = self.previews.lock().values().cloned().collect::<Vec<_>>();
+        for preview in previews {
+            tokio::spawn(self.clone().compile(preview, snap.clone()));
+        }
+    }
+}
+
+impl DocumentCompiler {
+    /// Request a compilation of the document, which is run by the returned
+    /// future.
+    fn compile(
+        self,
+        preview: Arc<DocumentPreview>,
+        snap: CompileSnapshot<LspCompilerFeat>,
+    ) -> impl std::future::Future<Output = ()> {
+        // The compilation is requested before the task is spawned, as the
+        // spawned tasks may start in any order.
+        let requested = preview.requested.fetch_add(1, Ordering::SeqCst) + 1;
+        async move { self.compile_latest(&preview, snap, requested).await }
+    }
+
+    /// Compile the document in a task forked from the snapshot of the shared
+    /// universe, and watch the files depended by all the documents.
+    ///
+    /// The compilations of a document run one after another, and are skipped
+    /// once a later one is requested, so that a stale result never replaces a
+    /// newer one.
+    async fn compile_latest(
+        &self,
+        preview: &DocumentPreview,
+        snap: CompileSnapshot<LspCompilerFeat>,
+        requested: usize,
+    ) {
+        let is_latest = || preview.requested.load(Ordering::SeqCst) == requested;
+
+        let _compiling = preview.compiling.lock().await;
+        if !is_latest() {
+            return;
+        }
+
+        let snap = snap.task(TaskInputs {
+            entry: Some(preview.entry.clone()),
+            ..Default::default()
+        });
+        let Some(id) = snap.world.main_id() else {
+            return;
+        };
+
+        let start = reflexo::time::now();
+        let compiled = snap.compile().await;
+        if !is_latest() {
+            return;
+        }
+        let elapsed = start.elapsed().unwrap_or_default();
+        let rep = match &compiled.doc {
+            Ok(..) => CompileReport::CompileSuccess(id, compiled.warnings.clone(), elapsed),
+            Err(err) => CompileReport::CompileError(id, err.clone(), elapsed),
+        };
+        preview.handle.notify_compile(&compiled, rep);
+
+        let mut deps = vec![];
+        compiled
+            .world
+            .iter_dependencies(&mut |dep| deps.push(dep.clone()));
+        *preview.deps.lock() = deps;
+
+        // The dependencies are collected and sent under the lock, so that the
+        // ones collected earlier never arrive later.
+        let previews = self.previews.lock();
+        let mut deps = vec![];
+        for preview in previews.values() {
+            deps.extend(preview.deps.lock().iter().cloned());
+        }
+        deps.sort();
+        deps.dedup();
+        let _ = self.intr_tx.send(Interrupt::Dependencies(deps));
+    }
+}
+
+/// Preview a document until the previewer has been idle.
+async fn preview_document(
+    compiler: DocumentCompiler,
+    preview: Arc<DocumentPreview>,
+    args: PreviewArgs,
+    websocket_rx: mpsc::UnboundedReceiver<HyperWebsocket>,
+) {
+    let task_id = args.task_id.clone();
+
+    // There is no editor to sync with.
+    let (lsp_tx, lsp_rx) = ControlPlaneTx::new(false);
+    let ControlPlaneRx {
+        mut resp_rx,
+        ctl_tx,
+        shutdown_rx,
+    } = lsp_rx;
+
+    let (idle_tx, mut idle_rx) = mpsc::channel(1);
+    let previewer = PreviewBuilder::new(args).with_shutdown_tx(idle_tx);
+    let handle = preview.handle.clone();
+    let registered = handle.register_preview(previewer.compile_watcher());
+    assert!(registered, "failed to register preview");
+    let mut previewer = previewer.build(lsp_tx, handle).await;
+
+    bind_streams(&mut previewer, websocket_rx);
+
+    // Compile the document from the latest snapshot of the shared universe.
+    match preview.handle.snapshot() {
+        Ok(snap) => {
+            if let Ok(mut snap) = snap.receive().await {
+                snap.signal.by_entry_update = true;
+                tokio::spawn(compiler.clone().compile(preview.clone(), snap));
+            }
+        }
+        Err(err) => log::error!("PreviewTask({task_id}): failed to snapshot: {err:?}"),
+    }
+
+    // Drop the responses to the editor.
+    let responses = tokio::spawn(async move {
+        let _keep = (ctl_tx, shutdown_rx);
+        while resp_rx.recv().await.is_some() {}
+    });
+
+    // The data plane notifies when no webview has been connected for a while.
+    let _ = idle_rx.recv().await;
+    compiler.previews.lock().remove(&task_id);
+    previewer.stop().await;
+    previewer.join().await;
+    responses.abort();
+    log::info!("PreviewTask({task_id}): stopped as idle");
+}
+
+/// List the documents in the directory, skipping the hidden directories.
+fn documents(root: &Path) -> Vec<String> {
+    let entries = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(Result::ok);
+
+    let mut documents = entries
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "typ"))
+        .filter_map(|entry| Some(unix_slash(entry.path().strip_prefix(root).ok()?)))
+        .collect::<Vec<_>>();
+    documents.sort();
+    documents
+}
+
+/// Render the index page listing the documents.
+fn index(root: &Path) -> String {
+    let mut items = String::new();
+    for document in documents(root) {
+        let href = utf8_percent_encode(&document, PATH_SET);
+        let name = escape_html(&document);
+        let _ = writeln!(items, r#"<li><a href="{DOC_PREFIX}{href}">{name}</a></li>"#);
+    }
+    if items.is_empty() {
+        items.push_str("<li>No documents found.</li>\n");
+    }
+
+    let root = escape_html(&root.to_string_lossy());
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Typst Documents</title>
+</head>
+<body>
+<h1>Typst Documents</h1>
+<p>{root}</p>
+<ul>
+{items}</ul>
+</body>
+</html>
+"#
+    )
+}
+
+/// Entry point of the preview tool serving a directory.
+pub(super) async fn serve_dir_main(
+    args: PreviewCliArgs,
+    dir: &Path,
+    static_file_host: Option<String>,
+) -> anyhow::Result<()> {
+    let root = dir
+        .canonicalize()
+        .with_context(|| format!("cannot find the directory {dir:?}"))?;
+    let root: ImmutPath = root.as_path().into();
+    let access = PreviewAccess::new(&args.access)?;
+
+    // The documents are compiled in the tasks forked from the universe, whose
+    // own entry is never compiled.
+    let compile = &args.compile;
+    let fonts = LspUniverseBuilder::resolve_fonts(compile.font.clone())?;
+    let package = LspUniverseBuilder::resolve_package(
+        compile.cert.as_deref().map(From::from),
+        Some(&compile.package),
+    );
+    let entry = EntryState::new_rooted(root.clone(), None);
+    let inputs = compile.resolve_inputs();
+    let verse = LspUniverseBuilder::build(entry, inputs, Arc::new(fonts), package)
+        .context("failed to create universe")?;
+
+    let (intr_tx, intr_rx) = mpsc::unbounded_channel();
+    let compiler = DocumentCompiler {
+        intr_tx: intr_tx.clone(),
+        previews: Arc::default(),
+    };
+    let service = CompileServerActor::new_with(
+        verse,
+        intr_tx,
+        intr_rx,
+        CompileServerOpts {
+            compile_handle: Arc::new(compiler.clone()),
+            ..Default::default()
+        },
+    )
+    .with_watch(true);
+    tokio::spawn(service.run());
+
+    let host = args.data_plane_host.clone();
+    let dont_open_in_browser = args.dont_open_in_browser;
+    let serve_dir: Arc<dyn PreviewRoutes> = Arc::new(ServeDir {
+        root: root.clone(),
+        args,
+        compiler,
+    });
+
+    let static_server = match static_file_host {
+        Some(static_file_host) if static_file_host != host => {
+            let (serve_dir, access) = (serve_dir.clone(), access.clone());
+            Some(make_routed_http_server(serve_dir, static_file_host, access).await)
+        }
+        _ => None,
+    };
+
+    let srv = make_routed_http_server(serve_dir, host, access.clone()).await;
+    let addr = static_server.as_ref().map_or(srv.addr, |s| s.addr);
+    log::info!(
+        "Serving the documents in {root:?} on: {}://{addr}",
+        access.scheme(),
+    );
+
+    if !dont_open_in_browser {
+        if let Err(e) = open::that_detached(access.url(addr)) {
+            log::error!("failed to open browser: {e}");
+        };
+    }
+
+    let _ = srv.join.await;
+    // Assert that the static server's lifetime is longer than the previewer.
+    let _s = static_server;
+
+    Ok(())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,475 @@
+//! # Typlite Content
+//!
+//! Converts the content evaluated by typst to the output format. Unlike the syntactic
+//! conversion, user functions and show rules are applied before the content is
+//! converted, so documents written with templates are exported faithfully.
+
+use comemo::Track;
+use typst::engine::{Engine, Route, Sink, Traced};
+use typst::foundations::{
+    Content, Context, ContextElem, Packed, Recipe, SequenceElem, Smart, Style, StyleChain,
+    StyledElem, Value as TypstValue,
+};
+use typst::introspection::Introspector;
+use typst::layout::{GridChild, GridElem, GridItem, HElem};
+use typst::math::EquationElem;
+use typst::model::{
+    BibliographyElem, CiteElem, Destination, EmphElem, EnumElem, EnumItem, FigureElem,
+    FootnoteBody, FootnoteElem, HeadingElem, LinkElem, LinkTarget, ListElem, ListItem,
+    ParbreakElem, RefElem, StrongElem, TableChild, TableElem, TableItem, TermItem, TermsElem,
+};
+use typst::text::{LinebreakElem, RawElem, SmartQuoteElem, SpaceElem, TextElem};
+use typst::visualize::ImageElem;
+use typst_syntax::Span;
+
+use crate::notes::Bibliography;
+use crate::value::{Table, TableCell};
+use crate::writer::ListKind;
+use crate::*;
+
+/// Evaluate the main file and convert the resulting content.
+pub(crate) fn convert(worker: TypliteWorker, main: &Source) -> Result<EcoString> {
+    let world = worker.world.clone();
+    let tracked = (world.as_ref() as &dyn World).track();
+
+    // The document is compiled so that the queries in show rules are resolved.
+    let introspector = typst::compile(world.as_ref())
+        .output
+        .map(|document| document.introspector)
+        .unwrap_or_default();
+
+    let traced = Traced::default();
+    let mut sink = Sink::new();
+    let module = typst::eval::eval(
+        tracked,
+        traced.track(),
+        sink.track_mut(),
+        Route::default().track(),
+        main,
+    )
+    .map_err(|err| format!("evaluating main file: {err:?}"))?;
+
+    let engine = Engine {
+        world: tracked,
+        route: Route::default(),
+        introspector: introspector.track(),
+        traced: traced.track(),
+        sink: sink.track_mut(),
+    };
+
+    let mut worker = ContentWorker {
+        worker,
+        engine,
+        recipes: vec![],
+    };
+    worker.convert(module.content())
+}
+
+/// The worker converting evaluated content.
+struct ContentWorker<'a> {
+    worker: TypliteWorker,
+    engine: Engine<'a>,
+    /// The show rules in scope and whether they are enabled. A show rule is
+    /// disabled while its own output is converted.
+    recipes: Vec<(Recipe, bool)>,
+}
+
+impl ContentWorker<'_> {
+    /// Convert the content to a trimmed string in the output format.
+    fn convert(&mut self, content: &Content) -> Result<EcoString> {
+        let mut s = EcoString::new();
+        self.content(&mut s, content)?;
+        Ok(s.trim().into())
+    }
+
+    fn content(&mut self, s: &mut EcoString, content: &Content) -> Result<()> {
+        if let Some(styled) = content.to_packed::<StyledElem>() {
+            return self.styled(s, styled);
+        }
+
+        if let Some(sequence) = content.to_packed::<SequenceElem>() {
+            return self.sequence(s, &sequence.children);
+        }
+
+        // Later show rules take precedence.
+        let recipe = self.recipes.iter().rposition(|(recipe, enabled)| {
+            *enabled
+                && recipe
+                    .selector()
+                    .is_some_and(|sel| sel.matches(content, None))
+        });
+        match recipe {
+            Some(idx) => self.show(s, idx, content),
+            None => self.elem(s, content),
+        }
+    }
+
+    fn sequence(&mut self, s: &mut EcoString, children: &[Content]) -> Result<()> {
+        // Consecutive items are grouped into lists, which are delimited in some
+        // formats.
+        let mut list = None;
+        for child in children {
+            let kind = if child.is::<ListItem>() {
+                Some(ListKind::Bullet)
+            } else if child.is::<EnumItem>() {
+                Some(ListKind::Numbered)
+            } else if child.is::<TermItem>() {
+                Some(ListKind::Terms)
+            } else {
+                None
+            };
+            let in_list = list.is_some() && child.is::<SpaceElem>();
+            if kind != list && !in_list {
+                if let Some(list) = list {
+                    line(s, &self.worker.writer.list_end(list));
+                }
+                if let Some(kind) = kind {
+                    line(s, &self.worker.writer.list_begin(kind));
+                }
+                list = kind;
+            }
+
+            self.content(s, child)?;
+        }
+        if let Some(list) = list {
+            line(s, &self.worker.writer.list_end(list));
+        }
+
+        Ok(())
+    }
+
+    fn styled(&mut self, s: &mut EcoString, styled: &Packed<StyledElem>) -> Result<()> {
+        let len = self.recipes.len();
+        for style in styled.styles.iter() {
+            if let Style::Recipe(recipe) = style {
+                self.recipes.push((recipe.clone(), true));
+            }
+        }
+
+        let res = self.content(s, &styled.child);
+        self.recipes.truncate(len);
+        res
+    }
+
+    fn show(&mut self, s: &mut EcoString, idx: usize, content: &Content) -> Result<()> {
+        let mut target = content.clone();
+        target.materialize(StyleChain::default());
+
+        let recipe = self.recipes[idx].0.clone();
+        let context = Context::none();
+        let output = recipe
+            .apply(&mut self.engine, context.track(), target)
+            .map_err(|err| format!("applying show rule: {err:?}"))?;
+
+        self.recipes[idx].1 = false;
+        let res = self.content(s, &output);
+        self.recipes[idx].1 = true;
+        res
+    }
+
+    fn elem(&mut self, s: &mut EcoString, content: &Content) -> Result<()> {
+        let styles = StyleChain::default();
+
+        let writer = self.worker.writer.clone();
+
+        if let Some(text) = content.to_packed::<TextElem>() {
+            s.push_str(&writer.text(&text.text));
+        } else if content.is::<SpaceElem>() || content.is::<HElem>() {
+            if !s.is_empty() && !s.ends_with(['\n', ' ']) {
+                s.push(' ');
+            }
+        } else if content.is::<LinebreakElem>() {
+            s.push_str(&writer.linebreak());
+        } else if content.is::<ParbreakElem>() {
+            parbreak(s);
+        } else if let Some(quote) = content.to_packed::<SmartQuoteElem>() {
+            s.push(if quote.double(styles) { '"' } else { '\'' });
+        } else if let Some(strong) = content.to_packed::<StrongElem>() {
+            s.push_str(&writer.strong(&self.convert(&strong.body)?));
+        } else if let Some(emph) = content.to_packed::<EmphElem>() {
+            s.push_str(&writer.emph(&self.convert(&emph.body)?));
+        } else if let Some(heading) = content.to_packed::<HeadingElem>() {
+            let level = heading.resolve_level(styles).get();
+            let body = self.convert(&heading.body)?;
+            block(s, &writer.heading(level, &body));
+        } else if let Some(raw) = content.to_packed::<RawElem>() {
+            let text = raw.text.get();
+            if raw.block(styles) {
+                let lang = raw.lang(styles).clone().unwrap_or_default();
+                block(s, &writer.raw_block(&lang, &text));
+            } else {
+                s.push_str(&writer.raw_inline(&text));
+            }
+        } else if let Some(link) = content.to_packed::<LinkElem>() {
+            let body = self.convert(&link.body)?;
+            match &link.dest {
+                LinkTarget::Dest(Destination::Url(url)) => {
+                    s.push_str(&writer.link(url.as_str(), &body));
+                }
+                _ => s.push_str(&body),
+            }
+        } else if let Some(list) = content.to_packed::<ListElem>() {
+            line(s, &writer.list_begin(ListKind::Bullet));
+            for item in &list.children {
+                let body = self.convert(&item.body)?;
+                line(s, &writer.list_item(&indent(&body, 2)));
+            }
+            line(s, &writer.list_end(ListKind::Bullet));
+        } else if let Some(item) = content.to_packed::<ListItem>() {
+            let body = self.convert(&item.body)?;
+            line(s, &writer.list_item(&indent(&body, 2)));
+        } else if let Some(list) = content.to_packed::<EnumElem>() {
+            line(s, &writer.list_begin(ListKind::Numbered));
+            let mut number = list.start(styles);
+            for item in &list.children {
+                number = item.number(styles).unwrap_or(number);
+                let body = self.convert(&item.body)?;
+                let width = number.to_string().len() + 2;
+                line(s, &writer.enum_item(Some(number), &indent(&body, width)));
+                number += 1;
+            }
+            line(s, &writer.list_end(ListKind::Numbered));
+        } else if let Some(item) = content.to_packed::<EnumItem>() {
+            let number = item.number(styles);
+            let body = self.convert(&item.body)?;
+            let width = number.unwrap_or(1).to_string().len() + 2;
+            line(s, &writer.enum_item(number, &indent(&body, width)));
+        } else if let Some(list) = content.to_packed::<TermsElem>() {
+            line(s, &writer.list_begin(ListKind::Terms));
+            for item in &list.children {
+                self.term_item(s, item)?;
+            }
+            line(s, &writer.list_end(ListKind::Terms));
+        } else if let Some(item) = content.to_packed::<TermItem>() {
+            self.term_item(s, item)?;
+        } else if let Some(equation) = content.to_packed::<EquationElem>() {
+            let is_block = equation.block(styles);
+            if let Some(rendered) = self.render_syntax(content.span(), !is_block)? {
+                if is_block {
+                    block(s, &rendered);
+                } else {
+                    s.push_str(&rendered);
+                }
+            }
+        } else if let Some(image) = content.to_packed::<ImageElem>() {
+            let alt = image.alt(styles).clone().unwrap_or_default();
+            s.push_str(&writer.image(&image.path, &alt, None));
+        } else if let Some(figure) = content.to_packed::<FigureElem>() {
+            self.figure(s, figure)?;
+        } else if let Some(table) = self.table(content)? {
+            block(s, &writer.table(&table, None));
+        } else if let Some(footnote) = content.to_packed::<FootnoteElem>() {
+            if let FootnoteBody::Content(body) = &footnote.body {
+                let body = self.convert(body)?;
+                s.push_str(&self.worker.footnote(body));
+            }
+        } else if let Some(cite) = content.to_packed::<CiteElem>() {
+            let key = cite.key.as_str();
+            let supplement = match cite.supplement(styles).clone() {
+                Some(supplement) => Some(self.convert(&supplement)?),
+                None => None,
+            };
+            match self.worker.cite(key, supplement) {
+                Some(citation) => s.push_str(&citation),
+                None => {
+                    let _ = write!(s, "@{key}");
+                }
+            }
+        } else if let Some(label_ref) = content.to_packed::<RefElem>() {
+            let target = label_ref.target.as_str();
+            match self.worker.cite(target, None) {
+                Some(citation) => s.push_str(&citation),
+                None => {
+                    let _ = write!(s, "@{target}");
+                }
+            }
+        } else if let Some(bibliography) = content.to_packed::<BibliographyElem>() {
+            let title = match bibliography.title(styles).clone() {
+                Smart::Auto => Some("Bibliography".into()),
+                Smart::Custom(Some(title)) => Some(self.convert(&title)?),
+                Smart::Custom(None) => None,
+            };
+            let full = bibliography.full(styles);
+            let mut notes = self.worker.notes.lock().unwrap();
+            notes.place_bibliography(Bibliography { title, full });
+        } else if content.is::<ContextElem>() {
+            // Contextual content depends on the layout, so it is rendered.
+            if let Some(rendered) = self.render_syntax(content.span(), false)? {
+                block(s, &rendered);
+            }
+        } else {
+            // Other elements, such as `align` and `block`, are transparent.
+            let fields = content.fields();
+            if let Ok(TypstValue::Content(body)) = fields.get("body") {
+                self.content(s, body)?;
+            } else if let Ok(TypstValue::Array(children)) = fields.get("children") {
+                for child in children.iter() {
+                    if let TypstValue::Content(child) = child {
+                        self.content(s, child)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn term_item(&mut self, s: &mut EcoString, item: &Packed<TermItem>) -> Result<()> {
+        let term = self.convert(&item.term)?;
+        let description = self.convert(&item.description)?;
+        let item = (self.worker.writer).term_item(&term, &indent(&description, 2));
+        line(s, &item);
+        Ok(())
+    }
+
+    fn figure(&mut self, s: &mut EcoString, figure: &Packed<FigureElem>) -> Result<()> {
+        let styles = StyleChain::default();
+        let caption = match figure.caption(styles).as_ref() {
+            Some(caption) => Some(self.convert(&caption.body)?),
+            None => None,
+        };
+
+        let writer = self.worker.writer.clone();
+        if let Some(table) = self.table(&figure.body)? {
+            block(s, &writer.table(&table, caption.as_deref()));
+        } else if let Some(image) = figure.body.to_packed::<ImageElem>() {
+            let alt = image.alt(styles).clone().unwrap_or_default();
+            block(s, &writer.image(&image.path, &alt, caption.as_deref()));
+        } else {
+            block(s, &self.convert(&figure.body)?);
+            if let Some(caption) = caption {
+                block(s, &caption);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Convert a table or a grid, yielding `None` for other content.
+    fn table(&mut self, content: &Content) -> Result<Option<Table>> {
+        let styles = StyleChain::default();
+
+        let mut header = vec![];
+        let mut body = vec![];
+        let mut footer = vec![];
+        let columns = if let Some(table) = content.to_packed::<TableElem>() {
+            let mut cells = |items: &[TableItem], cells: &mut Vec<TableCell>| {
+                for item in items {
+                    if let TableItem::Cell(cell) = item {
+                        let (colspan, rowspan) = (cell.colspan(styles), cell.rowspan(styles));
+                        cells.push(self.cell(&cell.body, colspan.get(), rowspan.get())?);
+                    }
+                }
+                Result::Ok(())
+            };
+            for child in &table.children {
+                match child {
+                    TableChild::Header(section) => cells(&section.children, &mut header)?,
+                    TableChild::Footer(section) => cells(&section.children, &mut footer)?,
+                    TableChild::Item(item) => cells(std::slice::from_ref(item), &mut body)?,
+                }
+            }
+            table.columns(styles).0.len()
+        } else if let Some(grid) = content.to_packed::<GridElem>() {
+            let mut cells = |items: &[GridItem], cells: &mut Vec<TableCell>| {
+                for item in items {
+                    if let GridItem::Cell(cell) = item {
+                        let (colspan, rowspan) = (cell.colspan(styles), cell.rowspan(styles));
+                        cells.push(self.cell(&cell.body, colspan.get(), rowspan.get())?);
+                    }
+                }
+                Result::Ok(())
+            };
+            for child in &grid.children {
+                match child {
+                    GridChild::Header(section) => cells(&section.children, &mut header)?,
+                    GridChild::Footer(section) => cells(&section.children, &mut footer)?,
+                    GridChild::Item(item) => cells(std::slice::from_ref(item), &mut body)?,
+                }
+            }
+            grid.columns(styles).0.len()
+        } else {
+            return Ok(None);
+        };
+
+        let feat = &self.worker.feat;
+        Ok(Some(Table::new(columns.max(1), header, body, footer, feat)))
+    }
+
+    fn cell(&mut self, body: &Content, colspan: usize, rowspan: usize) -> Result<TableCell> {
+        Ok(TableCell {
+            body: self.convert(body)?,
+            colspan,
+            rowspan,
+        })
+    }
+
+    /// Render the syntax from which the content is evaluated, which is used for
+    /// content that cannot be converted to markdown.
+    fn render_syntax(&mut self, span: Span, inline: bool) -> Result<Option<EcoString>> {
+        let Some(id) = span.id() else {
+            return Ok(None);
+        };
+        let source = (self.worker.world.source(id))
+            .map_err(|err| format!("getting source for rendering: {err:?}"))?;
+        let Some(node) = source.find(span) else {
+            return Ok(None);
+        };
+
+        let node = node.get();
+        let value = match node.kind() {
+            SyntaxKind::Equation => self.worker.equation(node)?,
+            _ if self.worker.feat.remove_html => self.worker.to_raw_block(node, inline)?,
+            _ => self.worker.render(node, inline)?,
+        };
+        Ok(Some(self.worker.render_value(value)))
+    }
+}
+
+/// Separate the following content by a blank line.
+fn parbreak(s: &mut EcoString) {
+    let len = s.trim_end().len();
+    s.truncate(len);
+    if !s.is_empty() {
+        s.push_str("\n\n");
+    }
+}
+
+/// Push a block, which is separated from the surrounding content by blank
+/// lines.
+fn block(s: &mut EcoString, block: &str) {
+    parbreak(s);
+    s.push_str(block);
+    s.push_str("\n\n");
+}
+
+/// Push a line, which is skipped if it is empty.
+fn line(s: &mut EcoString, line: &str) {
+    let line = line.trim_matches('\n');
+    if line.is_empty() {
+        return;
+    }
+
+    let len = s.trim_end_matches(' ').len();
+    s.truncate(len);
+    if !s.is_empty() && !s.ends_with('\n') {
+        s.push('\n');
+    }
+    s.push_str(line);
+    s.push('\n');
+}
+
+/// Indent the continuation lines of a list item by the width of its marker.
+fn indent(body: &str, width: usize) -> EcoString {
+    let mut s = EcoString::new();
+    for (idx, line) in body.lines().enumerate() {
+        if idx > 0 {
+            s.push('\n');
+            if !line.is_empty() {
+                s.push_str(&" ".repeat(width));
+            }
+        }
+        s.push_str(line);
+    }
+    s
+}
@@ -1,17 +1,24 @@
 //! # Typlite
 
+mod content;
 mod error;
 pub mod library;
+mod math;
+mod notes;
 pub mod scopes;
 pub mod value;
+pub mod writer;
 
 use core::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::{fmt::Write, sync::LazyLock};
 
 pub use error::*;
 
 use base64::Engine;
+pub use math::MathMode;
+use math::MathTranslator;
+use notes::Notes;
 use scopes::Scopes;
 use tinymist_world::reflexo_typst::path::unix_slash;
 use tinymist_world::{base::ShadowApi, EntryReader, LspWorld};
@@ -24,6 +31,8 @@ use typst::{
     World,
 };
 use value::{Args, Value};
+pub use writer::Format;
+use writer::{ListKind, Writer};
 
 use ecow::{eco_format, EcoString};
 use typst_syntax::{
@@ -58,6 +67,14 @@ pub struct TypliteFeat {
     pub soft_error: bool,
     /// Remove HTML tags from the output.
     pub remove_html: bool,
+    /// Evaluate the document and convert the evaluated content, to which user
+    /// functions and show rules are applied, instead of the syntax.
+    pub evaluate: bool,
+    /// The output format.
+    pub format: Format,
+    /// The way to convert equations in Markdown. Other formats always use
+    /// LaTeX math.
+    pub math: MathMode,
 }
 
 /// Task builder for converting a typst document to Markdown.
@@ -95,7 +112,7 @@ impl Typlite {
         self
     }
 
-    /// Convert the content to a markdown string.
+    /// Convert the content to a string in the output format.
     pub fn convert(self) -> Result<EcoString> {
         static DEFAULT_LIB: std::sync::LazyLock<Arc<Scopes<Value>>> =
             std::sync::LazyLock::new(|| Arc::new(library::library()));
@@ -108,10 +125,19 @@ impl Typlite {
             .source(current)
             .map_err(|err| format!("getting source for main file: {err:?}"))?;
 
+        // Citations may precede the bibliography, so the bibliography files are
+        // loaded before the conversion.
+        let mut notes = Notes::default();
+        notes.load_bibliography(&world, &main);
+        let notes = Arc::new(Mutex::new(notes));
+
+        let writer = self.feat.format.writer(&self.feat);
         let worker = TypliteWorker {
             current,
+            writer: writer.clone(),
             feat: self.feat,
             list_depth: 0,
+            notes: notes.clone(),
             scopes: self
                 .library
                 .as_ref()
@@ -120,7 +146,13 @@ impl Typlite {
             world,
         };
 
-        worker.sub_file(main)
+        let mut res = if worker.feat.evaluate {
+            content::convert(worker, &main)?
+        } else {
+            worker.sub_file(main)?
+        };
+        notes.lock().unwrap().render(&mut res, writer.as_ref());
+        Ok(writer.document(res))
     }
 }
 
@@ -131,14 +163,19 @@ pub struct TypliteWorker {
     scopes: Arc<Scopes<Value>>,
     world: Arc<LspWorld>,
     list_depth: usize,
+    /// The footnotes and citations shared by the included files.
+    notes: Arc<Mutex<Notes>>,
+    /// The writer of the output format.
+    writer: Arc<dyn Writer>,
     /// Features for the conversion.
     pub feat: TypliteFeat,
 }
 
 impl TypliteWorker {
-    /// Convert the content to a markdown string.
+    /// Convert the content to a string in the output format.
     pub fn convert(&mut self, node: &SyntaxNode) -> Result<EcoString> {
-        Ok(Self::value(self.eval(node)?))
+        let value = self.eval(node)?;
+        Ok(self.render_value(value))
     }
 
     /// Eval the content
@@ -173,19 +210,20 @@ impl TypliteWorker {
             }
 
             // Text nodes
-            Text | Space | Parbreak => Self::str(node),
-            Linebreak => Self::char('\n'),
+            Text => self.text(node),
+            Space | Parbreak => Self::str(node),
+            Linebreak => Ok(Value::Content(self.writer.linebreak())),
 
             // Semantic nodes
-            Escape => Self::escape(node),
+            Escape => self.escape(node),
             Shorthand => Self::shorthand(node),
             SmartQuote => Self::str(node),
             Strong => self.strong(node),
             Emph => self.emph(node),
-            Raw => Self::raw(node),
+            Raw => self.raw(node),
             Link => self.link(node),
             Label => Self::label(node),
-            Ref => Self::label_ref(node),
+            Ref => self.label_ref(node),
             RefMarker => Self::ref_marker(node),
             Heading => self.heading(node),
             HeadingMarker => Self::str(node),
@@ -311,9 +349,32 @@ impl TypliteWorker {
     fn reduce(&mut self, node: &SyntaxNode) -> Result<Value> {
         let mut s = EcoString::new();
 
+        // Consecutive items are grouped into lists, which are delimited in some
+        // formats.
+        let mut list = None;
         for child in node.children() {
+            let kind = match child.kind() {
+                SyntaxKind::ListItem => Some(ListKind::Bullet),
+                SyntaxKind::EnumItem => Some(ListKind::Numbered),
+                SyntaxKind::TermItem => Some(ListKind::Terms),
+                _ => None,
+            };
+            let in_list = list.is_some() && child.kind() == SyntaxKind::Space;
+            if kind != list && !in_list {
+                if let Some(list) = list {
+                    s.push_str(&self.writer.list_end(list));
+                }
+                if let Some(kind) = kind {
+                    s.push_str(&self.writer.list_begin(kind));
+                }
+                list = kind;
+            }
+
             // self.convert_to(child)?;
-            s.push_str(&Self::value(self.eval(child)?));
+            s.push_str(&self.convert(child)?);
+        }
+        if let Some(list) = list {
+            s.push_str(&self.writer.list_end(list));
         }
 
         Ok(Value::Content(s))
@@ -323,25 +384,14 @@ impl TypliteWorker {
         let content = node.clone().into_text();
 
         let s = if inline {
-            let mut s = EcoString::with_capacity(content.len() + 2);
-            s.push_str("`");
-            s.push_str(&content);
-            s.push_str("`");
-            s
+            self.writer.raw_inline(&content)
         } else {
-            let mut s = EcoString::with_capacity(content.len() + 15);
-            s.push_str("```");
             let lang = match node.cast::<ast::Expr>() {
                 Some(ast::Expr::Text(..) | ast::Expr::Space(..)) => "typ",
                 Some(..) => "typc",
                 None => "typ",
             };
-            s.push_str(lang);
-            s.push('\n');
-            s.push_str(&content);
-            s.push('\n');
-            s.push_str("```");
-            s
+            self.writer.raw_block(lang, &content)
         };
 
         Ok(Value::Content(s))
@@ -531,13 +581,28 @@ impl TypliteWorker {
             Value::Content(content) => content,
             Value::Str(s) => s,
             Value::Image { path, alt } => eco_format!("![{alt}]({path})"),
+            Value::Table(table) => table.render(None),
             _ => eco_format!("{res:?}"),
         }
     }
 
-    fn escape(node: &SyntaxNode) -> Result<Value> {
-        // todo: escape characters
-        Self::str(node)
+    /// Render the value in the output format.
+    pub fn render_value(&self, res: Value) -> EcoString {
+        match res {
+            Value::Image { path, alt } => self.writer.image(&path, &alt, None),
+            Value::Table(table) => self.writer.table(&table, None),
+            res => Self::value(res),
+        }
+    }
+
+    fn text(&self, node: &SyntaxNode) -> Result<Value> {
+        Ok(Value::Content(self.writer.text(node.text())))
+    }
+
+    fn escape(&self, node: &SyntaxNode) -> Result<Value> {
+        let escape = node.cast::<ast::Escape>().unwrap();
+        let s = self.writer.escape(escape.get(), node.text());
+        Ok(Value::Content(s))
     }
 
     fn shorthand(node: &SyntaxNode) -> Result<Value> {
@@ -546,75 +611,73 @@ impl TypliteWorker {
     }
 
     fn strong(&mut self, node: &SyntaxNode) -> Result<Value> {
-        let mut s = EcoString::new();
-
         let strong = node.cast::<ast::Strong>().unwrap();
-        s.push_str("**");
-        s.push_str(&Self::value(self.eval(strong.body().to_untyped())?));
-        s.push_str("**");
-
-        Ok(Value::Content(s))
+        let body = self.convert(strong.body().to_untyped())?;
+        Ok(Value::Content(self.writer.strong(&body)))
     }
 
     fn emph(&mut self, node: &SyntaxNode) -> Result<Value> {
-        let mut s = EcoString::new();
         let emph = node.cast::<ast::Emph>().unwrap();
-        s.push('_');
-        s.push_str(&Self::value(self.eval(emph.body().to_untyped())?));
-        s.push('_');
-        Ok(Value::Content(s))
+        let body = self.convert(emph.body().to_untyped())?;
+        Ok(Value::Content(self.writer.emph(&body)))
     }
 
     fn heading(&mut self, node: &SyntaxNode) -> Result<Value> {
-        let mut s = EcoString::new();
         let heading = node.cast::<ast::Heading>().unwrap();
         let level = heading.depth();
-        for _ in 0..level.get() {
-            s.push('#');
-        }
-        s.push(' ');
-        s.push_str(&Self::value(self.eval(heading.body().to_untyped())?));
-        Ok(Value::Content(s))
+        let body = self.convert(heading.body().to_untyped())?;
+        Ok(Value::Content(self.writer.heading(level.get(), &body)))
     }
 
-    fn raw(node: &SyntaxNode) -> Result<Value> {
-        let mut s = EcoString::new();
+    fn raw(&self, node: &SyntaxNode) -> Result<Value> {
         let raw = node.cast::<ast::Raw>().unwrap();
+        let lines = raw.lines().map(|line| line.get().as_str());
+        let text = lines.collect::<Vec<_>>().join("\n");
         if raw.block() {
-            s.push_str(&Self::value(Self::str(node)?));
-            return Ok(Value::Content(s));
-        }
-        s.push('`');
-        for line in raw.lines() {
-            s.push_str(&Self::value(Self::str(line.to_untyped())?));
+            let lang = raw
+                .lang()
+                .map(|lang| lang.get().as_str())
+                .unwrap_or_default();
+            return Ok(Value::Content(self.writer.raw_block(lang, &text)));
         }
-        s.push('`');
-        Ok(Value::Content(s))
+        Ok(Value::Content(self.writer.raw_inline(&text)))
     }
 
     fn link(&mut self, node: &SyntaxNode) -> Result<Value> {
-        // GFM supports autolinks
-        if self.feat.gfm {
-            // return Self::str(node, s);
-            return Self::str(node);
-        }
-        let mut s = EcoString::new();
-        s.push('[');
-        s.push_str(&Self::value(Self::str(node)?));
-        s.push(']');
-        s.push('(');
-        s.push_str(&Self::value(Self::str(node)?));
-        s.push(')');
-
-        Ok(Value::Content(s))
+        Ok(Value::Content(self.writer.url(node.text())))
     }
 
     fn label(_node: &SyntaxNode) -> Result<Value> {
         Result::Ok(Value::None)
     }
 
-    fn label_ref(node: &SyntaxNode) -> Result<Value> {
-        Self::str(node)
+    fn label_ref(&mut self, node: &SyntaxNode) -> Result<Value> {
+        let label_ref = node.cast::<ast::Ref>().unwrap();
+
+        // A reference to a bibliography entry is a citation.
+        let supplement = match label_ref.supplement() {
+            Some(supplement) => Some(self.convert(supplement.to_untyped())?),
+            None => None,
+        };
+        match self.cite(label_ref.target(), supplement) {
+            Some(citation) => Ok(Value::Content(citation)),
+            None => Self::str(node),
+        }
+    }
+
+    /// Cite a bibliography entry, yielding `None` if there is no such entry.
+    pub fn cite(&mut self, key: &str, supplement: Option<EcoString>) -> Option<EcoString> {
+        let idx = self.notes.lock().unwrap().cite(key)?;
+        Some(match supplement {
+            Some(supplement) => eco_format!("[{idx}, {supplement}]"),
+            None => eco_format!("[{idx}]"),
+        })
+    }
+
+    /// Add a footnote, yielding the reference to it.
+    pub fn footnote(&mut self, body: EcoString) -> EcoString {
+        let idx = self.notes.lock().unwrap().footnote(body.clone());
+        self.writer.footnote(idx, &body)
     }
 
     fn ref_marker(node: &SyntaxNode) -> Result<Value> {
@@ -626,46 +689,65 @@ impl TypliteWorker {
 
         let list_item = node.cast::<ast::ListItem>().unwrap();
 
-        s.push_str("- ");
         if self.feat.annotate_elem {
             let _ = write!(s, "<!-- typlite:begin:list-item {} -->", self.list_depth);
             self.list_depth += 1;
         }
-        s.push_str(&Self::value(self.eval(list_item.body().to_untyped())?));
+        s.push_str(&self.convert(list_item.body().to_untyped())?);
         if self.feat.annotate_elem {
             self.list_depth -= 1;
             let _ = write!(s, "<!-- typlite:end:list-item {} -->", self.list_depth);
         }
 
-        Ok(Value::Content(s))
+        Ok(Value::Content(self.writer.list_item(&s)))
     }
 
     fn enum_item(&mut self, node: &SyntaxNode) -> Result<Value> {
         let enum_item = node.cast::<ast::EnumItem>().unwrap();
 
-        let body = Self::value(self.eval(enum_item.body().to_untyped())?);
+        let body = self.convert(enum_item.body().to_untyped())?;
 
-        let s = if let Some(num) = enum_item.number() {
-            eco_format!("{num}. ")
-        } else {
-            "1. ".into()
-        };
-
-        Ok(Value::Content(eco_format!("{s}{body}")))
+        let s = self.writer.enum_item(enum_item.number(), &body);
+        Ok(Value::Content(s))
     }
 
     fn term_item(&mut self, node: &SyntaxNode) -> Result<Value> {
-        self.reduce(node)
+        // Markdown has no term lists, so the items are kept as they are.
+        if self.feat.format == Format::Markdown {
+            return self.reduce(node);
+        }
+
+        let term_item = node.cast::<ast::TermItem>().unwrap();
+
+        let term = self.convert(term_item.term().to_untyped())?;
+        let description = self.convert(term_item.description().to_untyped())?;
+
+        let s = self.writer.term_item(&term, &description);
+        Ok(Value::Content(s))
     }
 
     fn equation(&mut self, node: &SyntaxNode) -> Result<Value> {
         let equation: ast::Equation = node.cast().unwrap();
 
-        if self.feat.remove_html {
-            return self.to_raw_block(node, !equation.block());
-        }
+        // Other formats have native LaTeX math, and MathML is HTML.
+        let mode = match self.feat.math {
+            _ if self.feat.format != Format::Markdown => MathMode::Latex,
+            MathMode::MathMl if self.feat.remove_html => MathMode::Latex,
+            mode => mode,
+        };
 
-        self.render(node, !equation.block())
+        match mode {
+            MathMode::Latex => {
+                let math = MathTranslator::new(self.world.library()).latex(equation);
+                Ok(Value::Content(self.writer.math(&math, equation.block())))
+            }
+            MathMode::MathMl => {
+                let math = MathTranslator::new(self.world.library()).mathml(equation);
+                Ok(Value::Content(math))
+            }
+            MathMode::Svg if self.feat.remove_html => self.to_raw_block(node, !equation.block()),
+            MathMode::Svg => self.render(node, !equation.block()),
+        }
     }
 
     fn let_binding(&self, node: &SyntaxNode) -> Result<Value> {
@@ -697,7 +779,7 @@ impl TypliteWorker {
     }
 
     fn contextual(&mut self, node: &SyntaxNode) -> Result<Value> {
-        if self.feat.remove_html {
+        if self.feat.remove_html || self.feat.format != Format::Markdown {
             return self.to_raw_block(node, false);
         }
         self.render(node, false)
@@ -6,6 +6,8 @@ use value::*;
 
 mod docstring;
 pub use docstring::docstring_lib;
+mod table;
+pub use table::table;
 
 pub fn library() -> Scopes<Value> {
     let mut scopes = Scopes::new();
@@ -14,6 +16,11 @@ pub fn library() -> Scopes<Value> {
     scopes.define("md-alter", md_alter as RawFunc);
     scopes.define("image", image as RawFunc);
     scopes.define("figure", figure as RawFunc);
+    scopes.define("table", table as RawFunc);
+    scopes.define("grid", table as RawFunc);
+    scopes.define("footnote", footnote as RawFunc);
+    scopes.define("cite", cite as RawFunc);
+    scopes.define("bibliography", bibliography as RawFunc);
     scopes.define("raw", raw as RawFunc);
     scopes.define("pad", pad as RawFunc);
     scopes.define("note-box", note as RawFunc);
@@ -29,7 +36,7 @@ pub fn link(mut args: Args) -> Result<Value> {
     let dest = get_pos_named!(args, dest: EcoString);
     let body = get_pos_named!(args, body: Content);
 
-    Ok(Value::Content(eco_format!("[{body}]({dest})")))
+    Ok(Value::Content(args.vm.writer.link(&dest, &body.0)))
 }
 
 /// Evaluate an image.
@@ -43,20 +50,66 @@ pub fn image(mut args: Args) -> Result<Value> {
 /// Evaluate a figure.
 pub fn figure(mut args: Args) -> Result<Value> {
     let body = get_pos_named!(args, path: Value);
-    let caption = get_named!(args, caption: Option<Value>).map(TypliteWorker::value);
-
-    match (body, caption) {
-        (Value::Image { path, alt }, None) => Ok(Value::Content(eco_format!("![{alt}]({path})"))),
-        (Value::Image { path, alt }, Some(caption)) if args.vm.feat.gfm => Ok(Value::Content(
-            eco_format!("![{caption}, {alt}]({path} {caption:?})"),
-        )),
-        (Value::Image { path, alt }, Some(caption)) => {
-            Ok(Value::Content(eco_format!("![{caption}, {alt}]({path})")))
-        }
-        _ => Err("figure only accepts image as body".into()),
+    let caption = get_named!(args, caption: Option<Value>);
+    let caption = caption.map(|caption| args.vm.render_value(caption));
+
+    let writer = &args.vm.writer;
+    match body {
+        Value::Image { path, alt } => Ok(Value::Content(writer.image(
+            &path,
+            &alt,
+            caption.as_deref(),
+        ))),
+        Value::Table(table) => Ok(Value::Content(writer.table(&table, caption.as_deref()))),
+        _ => Err("figure only accepts image or table as body".into()),
     }
 }
 
+/// Evaluate a footnote.
+pub fn footnote(mut args: Args) -> Result<Value> {
+    let body = get_pos_named!(args, body: Content);
+
+    Ok(Value::Content(args.vm.footnote(body.0)))
+}
+
+/// Evaluate a citation.
+pub fn cite(mut args: Args) -> Result<Value> {
+    let key: &SyntaxNode = get_pos_named!(args, key: &SyntaxNode);
+    let key = key
+        .cast::<ast::Label>()
+        .ok_or("cite expects a label as key")?
+        .get();
+    let supplement = get_named!(args, supplement: Option<Content>).map(|s| s.0);
+
+    match args.vm.cite(key, supplement) {
+        Some(citation) => Ok(Value::Content(citation)),
+        None => Ok(Value::Content(eco_format!("@{key}"))),
+    }
+}
+
+/// Evaluate a bibliography, whose references are rendered at the end of the
+/// document.
+pub fn bibliography(mut args: Args) -> Result<Value> {
+    let title = match args.get_named_("title") {
+        Some(title) => match args.vm.eval(title)? {
+            Value::None => None,
+            title => Some(args.vm.render_value(title)),
+        },
+        None => Some("Bibliography".into()),
+    };
+    let full = match args.get_named_("full") {
+        Some(full) => full.cast::<ast::Bool>().is_some_and(|full| full.get()),
+        None => false,
+    };
+
+    args.vm
+        .notes
+        .lock()
+        .unwrap()
+        .place_bibliography(notes::Bibliography { title, full });
+    Ok(Value::None)
+}
+
 /// Evaluate a raw.
 pub fn raw(mut args: Args) -> Result<Value> {
     let content = get_pos_named!(args, content: EcoString);
@@ -88,48 +141,37 @@ pub fn md_alter(mut args: Args) -> Result<Value> {
 pub fn note(mut args: Args) -> Result<Value> {
     let body = get_pos_named!(args, body: Content);
 
-    Ok(note_box("NOTE", body))
+    Ok(note_box(args.vm, "NOTE", body))
 }
 
 /// Evaluate a tip note box.
 pub fn tip(mut args: Args) -> Result<Value> {
     let body = get_pos_named!(args, body: Content);
 
-    Ok(note_box("TIP", body))
+    Ok(note_box(args.vm, "TIP", body))
 }
 
 /// Create a important note box.
 pub fn important_box(mut args: Args) -> Result<Value> {
     let body = get_pos_named!(args, body: Content);
 
-    Ok(note_box("IMPORTANT", body))
+    Ok(note_box(args.vm, "IMPORTANT", body))
 }
 
 /// Create a warning note box.
 pub fn warning_box(mut args: Args) -> Result<Value> {
     let body = get_pos_named!(args, body: Content);
 
-    Ok(note_box("WARNING", body))
+    Ok(note_box(args.vm, "WARNING", body))
 }
 
 /// Create a caution note box.
 pub fn caution_box(mut args: Args) -> Result<Value> {
     let body = get_pos_named!(args, body: Content);
 
-    Ok(note_box("CAUTION", body))
+    Ok(note_box(args.vm, "CAUTION", body))
 }
 
-fn note_box(title: &str, body: Content) -> Value {
-    let mut res = EcoString::new();
-    res.push_str("> [!");
-    res.push_str(title);
-    res.push_str("]\n");
-    let body = body.0;
-    for line in body.lines() {
-        res.push_str("> ");
-        res.push_str(line);
-        res.push('\n');
-    }
-
-    Value::Content(res)
+fn note_box(vm: &TypliteWorker, title: &str, body: Content) -> Value {
+    Value::Content(vm.writer.admonition(title, &body.0))
 }
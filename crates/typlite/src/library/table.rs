@@ -0,0 +1,276 @@
+use super::*;
+
+/// Evaluate a table or a grid.
+pub fn table(args: Args) -> Result<Value> {
+    let vm = args.vm;
+
+    let mut columns = 1;
+    let mut header = vec![];
+    let mut body = vec![];
+    let mut footer = vec![];
+    for item in args.args.items() {
+        let expr = match item {
+            ast::Arg::Named(named) if named.name().get() == "columns" => {
+                columns = column_count(named.expr());
+                continue;
+            }
+            ast::Arg::Pos(expr) => expr,
+            _ => continue,
+        };
+
+        match table_elem(expr) {
+            Some(("header", call)) => table_cells(vm, call.args(), &mut header)?,
+            Some(("footer", call)) => table_cells(vm, call.args(), &mut footer)?,
+            _ => body.extend(table_cell(vm, expr)?),
+        }
+    }
+
+    Ok(Value::Table(Table::new(
+        columns, header, body, footer, &vm.feat,
+    )))
+}
+
+impl Table {
+    /// Create a table from the converted cells of its header, body and footer.
+    pub fn new(
+        columns: usize,
+        mut header: Vec<TableCell>,
+        mut body: Vec<TableCell>,
+        mut footer: Vec<TableCell>,
+        feat: &TypliteFeat,
+    ) -> Self {
+        // Only markdown tables are written in HTML.
+        let is_html = !feat.remove_html && feat.format == Format::Markdown;
+        let line_break = if is_html { "<br>" } else { " " };
+
+        let cells = header.iter_mut().chain(&mut body).chain(&mut footer);
+        let mut has_span = false;
+        for cell in cells {
+            has_span |= cell.colspan > 1 || cell.rowspan > 1;
+            cell.body = cell_body(&cell.body, line_break);
+        }
+
+        // Each cell takes at least one column, so that a huge count of columns
+        // doesn't allocate rows of empty cells.
+        let cell_count = header.len() + body.len() + footer.len();
+        let columns = columns.min(cell_count).max(1);
+
+        let mut body = layout(body, columns);
+        body.extend(layout(footer, columns));
+
+        Self {
+            columns,
+            header: layout(header, columns),
+            body,
+            // Plain markdown has no tables, and GFM tables cannot span cells.
+            html: is_html && (!feat.gfm || has_span),
+        }
+    }
+
+    /// Render the table, with an optional caption.
+    pub fn render(&self, caption: Option<&str>) -> EcoString {
+        if self.html {
+            self.render_html(caption)
+        } else {
+            self.render_pipe(caption)
+        }
+    }
+
+    fn render_pipe(&self, caption: Option<&str>) -> EcoString {
+        let mut lines = vec![];
+
+        // A pipe table has exactly one header row, so the first row is used as
+        // the header if the table doesn't have one.
+        let mut rows = self.header.iter().chain(&self.body);
+        if let Some(first) = rows.next() {
+            lines.push(pipe_row(first));
+            lines.push(format!("|{}", " --- |".repeat(self.columns)));
+        }
+        lines.extend(rows.map(|row| pipe_row(row)));
+
+        let mut s = EcoString::from(lines.join("\n"));
+        if let Some(caption) = caption {
+            let _ = write!(s, "\n\nTable: {caption}");
+        }
+        s
+    }
+
+    fn render_html(&self, caption: Option<&str>) -> EcoString {
+        let mut s = EcoString::new();
+        s.push_str("<table>\n");
+        if let Some(caption) = caption {
+            let _ = writeln!(s, "<caption>{caption}</caption>");
+        }
+
+        for (section, tag, rows) in [("thead", "th", &self.header), ("tbody", "td", &self.body)] {
+            if rows.is_empty() {
+                continue;
+            }
+
+            let _ = writeln!(s, "<{section}>");
+            for row in rows {
+                s.push_str("<tr>");
+                for cell in row.iter().flatten() {
+                    let _ = write!(s, "<{tag}");
+                    if cell.colspan > 1 {
+                        let _ = write!(s, r#" colspan="{}""#, cell.colspan);
+                    }
+                    if cell.rowspan > 1 {
+                        let _ = write!(s, r#" rowspan="{}""#, cell.rowspan);
+                    }
+                    let _ = write!(s, ">{}</{tag}>", cell.body);
+                }
+                s.push_str("</tr>\n");
+            }
+            let _ = writeln!(s, "</{section}>");
+        }
+
+        s.push_str("</table>");
+        s
+    }
+}
+
+/// Get the element function called by the expression, e.g. `header` in
+/// `table.header(..)`.
+fn table_elem<'a>(expr: ast::Expr<'a>) -> Option<(&'a str, ast::FuncCall<'a>)> {
+    let ast::Expr::FuncCall(call) = expr else {
+        return None;
+    };
+    let ast::Expr::FieldAccess(access) = call.callee() else {
+        return None;
+    };
+    Some((access.field().get().as_str(), call))
+}
+
+/// Evaluate the cells in the arguments of a header or a footer.
+fn table_cells(vm: &mut TypliteWorker, args: ast::Args, cells: &mut Vec<TableCell>) -> Result<()> {
+    for item in args.items() {
+        if let ast::Arg::Pos(expr) = item {
+            cells.extend(table_cell(vm, expr)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Evaluate a cell, which is either a `table.cell(..)` or any content. Lines
+/// are ignored.
+fn table_cell(vm: &mut TypliteWorker, expr: ast::Expr) -> Result<Option<TableCell>> {
+    let call = match table_elem(expr) {
+        Some(("hline" | "vline", _)) => return Ok(None),
+        Some(("cell", call)) => call,
+        _ => {
+            return Ok(Some(TableCell {
+                body: vm.convert(expr.to_untyped())?,
+                colspan: 1,
+                rowspan: 1,
+            }))
+        }
+    };
+
+    let mut cell = TableCell {
+        body: EcoString::new(),
+        colspan: 1,
+        rowspan: 1,
+    };
+    for item in call.args().items() {
+        match item {
+            ast::Arg::Named(named) if named.name().get() == "colspan" => {
+                cell.colspan = span_count(named.expr());
+            }
+            ast::Arg::Named(named) if named.name().get() == "rowspan" => {
+                cell.rowspan = span_count(named.expr());
+            }
+            ast::Arg::Pos(body) => cell.body = vm.convert(body.to_untyped())?,
+            _ => {}
+        }
+    }
+
+    Ok(Some(cell))
+}
+
+/// Lay out the cells in rows. The positions covered by a spanned cell and the
+/// missing cells in the last row are `None`.
+fn layout(cells: Vec<TableCell>, columns: usize) -> Vec<Vec<Option<TableCell>>> {
+    let mut rows: Vec<Vec<Option<TableCell>>> = vec![];
+    let mut occupied: Vec<Vec<bool>> = vec![];
+
+    let (mut row, mut col) = (0, 0);
+    let cell_count = cells.len();
+    for (idx, cell) in cells.into_iter().enumerate() {
+        // Find the next free position.
+        loop {
+            if col >= columns {
+                row += 1;
+                col = 0;
+            }
+            if !occupied.get(row).is_some_and(|cols| cols[col]) {
+                break;
+            }
+            col += 1;
+        }
+
+        let colspan = cell.colspan.min(columns - col);
+        // The remaining cells fill at most one row each.
+        let rowspan = cell.rowspan.min(cell_count - idx);
+        while rows.len() < row + rowspan {
+            rows.push(vec![None; columns]);
+            occupied.push(vec![false; columns]);
+        }
+        for cols in &mut occupied[row..row + rowspan] {
+            cols[col..col + colspan].fill(true);
+        }
+
+        rows[row][col] = Some(TableCell { colspan, ..cell });
+        col += colspan;
+    }
+
+    // Drop the rows only covered by the cells spanning past the last cell.
+    while rows
+        .last()
+        .is_some_and(|cols| cols.iter().all(Option::is_none))
+    {
+        rows.pop();
+    }
+    let row_count = rows.len();
+    for (row, cols) in rows.iter_mut().enumerate() {
+        for cell in cols.iter_mut().flatten() {
+            cell.rowspan = cell.rowspan.min(row_count - row);
+        }
+    }
+
+    rows
+}
+
+fn column_count(expr: ast::Expr) -> usize {
+    match expr {
+        ast::Expr::Int(count) => count.get().max(1) as usize,
+        ast::Expr::Array(columns) => columns.items().count().max(1),
+        _ => 1,
+    }
+}
+
+fn span_count(expr: ast::Expr) -> usize {
+    match expr {
+        ast::Expr::Int(count) => count.get().max(1) as usize,
+        _ => 1,
+    }
+}
+
+/// Fit the body of a cell into a single line.
+fn cell_body(body: &str, line_break: &str) -> EcoString {
+    let lines = body.lines().map(str::trim).filter(|line| !line.is_empty());
+    lines.collect::<Vec<_>>().join(line_break).into()
+}
+
+fn pipe_row(row: &[Option<TableCell>]) -> String {
+    let mut s = String::from("|");
+    for cell in row {
+        let body = cell
+            .as_ref()
+            .map(|cell| cell.body.as_str())
+            .unwrap_or_default();
+        let _ = write!(s, " {} |", body.replace('|', r"\|"));
+    }
+    s
+}
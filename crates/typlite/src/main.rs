@@ -8,7 +8,7 @@ use std::{
 use clap::Parser;
 use ecow::{eco_format, EcoString};
 use typlite::value::*;
-use typlite::{CompileOnceArgs, Typlite};
+use typlite::{CompileOnceArgs, Format, MathMode, Typlite, TypliteFeat};
 
 /// Common arguments of compile, watch, and query.
 #[derive(Debug, Clone, Parser, Default)]
@@ -19,6 +19,19 @@ pub struct CompileArgs {
     /// Path to output file
     #[clap(value_name = "OUTPUT")]
     pub output: Option<String>,
+
+    /// The output format
+    #[clap(long, value_enum, default_value_t = Format::Markdown)]
+    pub format: Format,
+
+    /// The way to convert equations in Markdown
+    #[clap(long, value_enum, default_value_t = MathMode::Svg)]
+    pub math: MathMode,
+
+    /// Evaluate the document and convert the evaluated content, to which
+    /// user functions and show rules are applied
+    #[clap(long)]
+    pub evaluate: bool,
 }
 
 fn main() -> typlite::Result<()> {
@@ -33,13 +46,20 @@ fn main() -> typlite::Result<()> {
     let output = match args.output {
         Some(stdout_path) if stdout_path == "-" => None,
         Some(output_path) => Some(PathBuf::from(output_path)),
-        None => Some(Path::new(input).with_extension("md")),
+        None => Some(Path::new(input).with_extension(args.format.extension())),
     };
 
     let universe = args.compile.resolve().map_err(|err| format!("{err:?}"))?;
     let world = universe.snapshot();
 
-    let converter = Typlite::new(Arc::new(world)).with_library(lib());
+    let converter = Typlite::new(Arc::new(world))
+        .with_library(lib())
+        .with_feature(TypliteFeat {
+            evaluate: args.evaluate,
+            format: args.format,
+            math: args.math,
+            ..Default::default()
+        });
     let conv = converter.convert();
 
     match (conv, output) {
@@ -0,0 +1,742 @@
+//! # Typlite Math
+//!
+//! Translates typst math to LaTeX and MathML, which are rendered natively by
+//! many renderers, instead of compiling equations to images.
+
+use typst::foundations::Value as TypstValue;
+use typst::Library;
+
+use crate::*;
+
+/// The way to convert equations.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "cli", clap(rename_all = "lowercase"))]
+pub enum MathMode {
+    /// Compiles equations to SVG images embedded in HTML.
+    #[default]
+    Svg,
+    /// Translates equations to LaTeX delimited by `$` or `$$`, for renderers
+    /// with KaTeX or MathJax.
+    Latex,
+    /// Translates equations to MathML.
+    MathMl,
+}
+
+/// The functions and symbols written as upright operators in LaTeX.
+const OPERATORS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg", "det", "dim",
+    "exp", "gcd", "hom", "inf", "ker", "lg", "lim", "liminf", "limsup", "ln", "log", "max", "min",
+    "Pr", "sec", "sin", "sinh", "sup", "tan", "tanh",
+];
+
+/// The spacing constants of typst math and their LaTeX equivalents.
+const SPACES: &[(&str, &str, &str)] = &[
+    ("thin", "\\,", "0.1667em"),
+    ("med", "\\:", "0.2222em"),
+    ("thick", "\\;", "0.2778em"),
+    ("quad", "\\quad", "1em"),
+    ("wide", "\\qquad", "2em"),
+];
+
+/// The LaTeX commands whose content is taller than a line, in which case the
+/// surrounding delimiters are scaled.
+const TALL: &[&str] = &["\\frac", "\\binom", "\\sum", "\\prod", "\\int", "\\begin"];
+
+/// The accents and their combining characters in MathML.
+const ACCENTS: &[(&str, &str, char)] = &[
+    ("hat", "\\hat", '^'),
+    ("tilde", "\\tilde", '~'),
+    ("macron", "\\bar", '¯'),
+    ("dot", "\\dot", '˙'),
+    ("dot.double", "\\ddot", '¨'),
+    ("arrow", "\\vec", '→'),
+    ("overline", "\\overline", '¯'),
+    ("overbrace", "\\overbrace", '⏞'),
+];
+
+/// The font variants and their names in LaTeX and MathML.
+const VARIANTS: &[(&str, &str, &str)] = &[
+    ("upright", "\\mathrm", "normal"),
+    ("bold", "\\mathbf", "bold"),
+    ("italic", "\\mathit", "italic"),
+    ("sans", "\\mathsf", "sans-serif"),
+    ("mono", "\\mathtt", "monospace"),
+    ("cal", "\\mathcal", "script"),
+    ("frak", "\\mathfrak", "fraktur"),
+    ("bb", "\\mathbb", "double-struck"),
+];
+
+/// The functions delimiting their argument and their delimiters.
+const FENCES: &[(&str, &str, &str)] = &[
+    ("abs", "|", "|"),
+    ("norm", "‖", "‖"),
+    ("floor", "⌊", "⌋"),
+    ("ceil", "⌈", "⌉"),
+    ("round", "⌊", "⌉"),
+];
+
+/// Translates typst math, resolving the symbols with the standard library.
+pub(crate) struct MathTranslator<'a> {
+    library: &'a Library,
+}
+
+impl<'a> MathTranslator<'a> {
+    /// Create a translator.
+    pub fn new(library: &'a Library) -> Self {
+        Self { library }
+    }
+
+    /// Translate the equation to LaTeX, without delimiters.
+    pub fn latex(&self, equation: ast::Equation) -> EcoString {
+        let mut s = EcoString::new();
+        for expr in equation.body().exprs() {
+            self.latex_expr(&mut s, expr);
+        }
+        s.trim().into()
+    }
+
+    /// Translate the equation to a MathML element.
+    pub fn mathml(&self, equation: ast::Equation) -> EcoString {
+        let display = if equation.block() { "block" } else { "inline" };
+        let body = self.mathml_seq(equation.body());
+        eco_format!(r#"<math display="{display}"><mrow>{body}</mrow></math>"#)
+    }
+
+    fn latex_of(&self, expr: ast::Expr) -> EcoString {
+        let mut s = EcoString::new();
+        self.latex_expr(&mut s, expr);
+        s.trim().into()
+    }
+
+    /// Translate a script or an argument, whose parentheses are only used for
+    /// grouping.
+    fn latex_group(&self, expr: ast::Expr) -> EcoString {
+        match unparen(expr) {
+            Ok(math) => {
+                let mut s = EcoString::new();
+                for expr in math.exprs() {
+                    self.latex_expr(&mut s, expr);
+                }
+                s.trim().into()
+            }
+            Err(expr) => self.latex_of(expr),
+        }
+    }
+
+    fn latex_expr(&self, s: &mut EcoString, expr: ast::Expr) {
+        match expr {
+            ast::Expr::Math(math) => {
+                for expr in math.exprs() {
+                    self.latex_expr(s, expr);
+                }
+            }
+            ast::Expr::Text(text) => {
+                for c in text.get().chars() {
+                    push_latex(s, &latex_char(c));
+                }
+            }
+            ast::Expr::Space(_) => {
+                if !s.ends_with(' ') {
+                    s.push(' ');
+                }
+            }
+            ast::Expr::Linebreak(_) => s.push_str(" \\\\ "),
+            ast::Expr::Escape(escape) => push_latex(s, &latex_char(escape.get())),
+            ast::Expr::Shorthand(shorthand) => push_latex(s, &latex_char(shorthand.get())),
+            ast::Expr::MathShorthand(shorthand) => push_latex(s, &latex_char(shorthand.get())),
+            ast::Expr::MathAlignPoint(_) => s.push('&'),
+            ast::Expr::Str(text) => push_latex(s, &eco_format!("\\text{{{}}}", text.get())),
+            ast::Expr::MathIdent(ident) => push_latex(s, &self.latex_ident(ident.get(), &[])),
+            ast::Expr::FieldAccess(access) => {
+                if let Some((name, fields)) = symbol_path(access) {
+                    push_latex(s, &self.latex_ident(name, &fields));
+                }
+            }
+            ast::Expr::MathDelimited(delimited) => {
+                let open = self.latex_of(delimited.open());
+                let close = self.latex_of(delimited.close());
+                let mut body = EcoString::new();
+                for expr in delimited.body().exprs() {
+                    self.latex_expr(&mut body, expr);
+                }
+                let body = body.trim();
+                if TALL.iter().any(|cmd| body.contains(cmd)) {
+                    push_latex(s, &eco_format!("\\left{open} {body} \\right{close}"));
+                } else {
+                    push_latex(s, &eco_format!("{open}{body}{close}"));
+                }
+            }
+            ast::Expr::MathAttach(attach) => {
+                push_latex(s, &self.latex_of(attach.base()));
+                if let Some(primes) = attach.primes() {
+                    s.push_str(&"'".repeat(primes.count()));
+                }
+                if let Some(bottom) = attach.bottom() {
+                    let _ = write!(s, "_{{{}}}", self.latex_group(bottom));
+                }
+                if let Some(top) = attach.top() {
+                    let _ = write!(s, "^{{{}}}", self.latex_group(top));
+                }
+            }
+            ast::Expr::MathPrimes(primes) => s.push_str(&"'".repeat(primes.count())),
+            ast::Expr::MathFrac(frac) => {
+                let num = self.latex_group(frac.num());
+                let denom = self.latex_group(frac.denom());
+                push_latex(s, &eco_format!("\\frac{{{num}}}{{{denom}}}"));
+            }
+            ast::Expr::MathRoot(root) => {
+                let radicand = self.latex_group(root.radicand());
+                match root.index() {
+                    Some(index) => push_latex(s, &eco_format!("\\sqrt[{index}]{{{radicand}}}")),
+                    None => push_latex(s, &eco_format!("\\sqrt{{{radicand}}}")),
+                }
+            }
+            ast::Expr::FuncCall(call) => {
+                if let Some(latex) = self.latex_call(call) {
+                    push_latex(s, &latex);
+                }
+            }
+            // Embedded code cannot be translated.
+            _ => {}
+        }
+    }
+
+    fn latex_ident(&self, name: &str, fields: &[&str]) -> EcoString {
+        if fields.is_empty() {
+            if OPERATORS.contains(&name) {
+                return eco_format!("\\{name}");
+            }
+            if let Some((_, latex, _)) = SPACES.iter().find(|(space, ..)| *space == name) {
+                return (*latex).into();
+            }
+            if name == "dif" || name == "Dif" {
+                return eco_format!("\\mathrm{{{}}}", &name[..1]);
+            }
+        }
+
+        match self.symbol(name, fields) {
+            Some(c) => latex_char(c),
+            None => eco_format!("\\operatorname{{{name}}}"),
+        }
+    }
+
+    fn latex_call(&self, call: ast::FuncCall) -> Option<EcoString> {
+        let name = callee_name(call)?;
+        let args = pos_args(call);
+        let arg = |idx: usize| match args.get(idx) {
+            Some(arg) => self.latex_group(*arg),
+            None => EcoString::new(),
+        };
+
+        if let Some((_, latex, _)) = VARIANTS.iter().find(|(variant, ..)| name == *variant) {
+            return Some(eco_format!("{latex}{{{}}}", arg(0)));
+        }
+        if let Some((_, latex, _)) = ACCENTS.iter().find(|(accent, ..)| name == *accent) {
+            return Some(eco_format!("{latex}{{{}}}", arg(0)));
+        }
+        if let Some((_, open, close)) = FENCES.iter().find(|(fence, ..)| name == *fence) {
+            let (open, close) = (latex_delim(open), latex_delim(close));
+            return Some(eco_format!("\\left{open} {} \\right{close}", arg(0)));
+        }
+
+        let latex = match name.as_str() {
+            "frac" => eco_format!("\\frac{{{}}}{{{}}}", arg(0), arg(1)),
+            "binom" => eco_format!("\\binom{{{}}}{{{}}}", arg(0), arg(1)),
+            "sqrt" => eco_format!("\\sqrt{{{}}}", arg(0)),
+            "root" => eco_format!("\\sqrt[{}]{{{}}}", arg(0), arg(1)),
+            "underline" => eco_format!("\\underline{{{}}}", arg(0)),
+            "underbrace" => eco_format!("\\underbrace{{{}}}", arg(0)),
+            "op" => match args.first() {
+                Some(ast::Expr::Str(text)) => eco_format!("\\operatorname{{{}}}", text.get()),
+                _ => eco_format!("\\operatorname{{{}}}", arg(0)),
+            },
+            "vec" | "mat" | "cases" => {
+                let rows = matrix_rows(&name, &args);
+                let rows = rows.into_iter().map(|row| {
+                    let cells = row.into_iter().map(|cell| self.latex_group(cell));
+                    cells.collect::<Vec<_>>().join(" & ")
+                });
+                let rows = rows.collect::<Vec<_>>().join(" \\\\ ");
+                let env = if name == "cases" { "cases" } else { "pmatrix" };
+                eco_format!("\\begin{{{env}}} {rows} \\end{{{env}}}")
+            }
+            // The functions only affecting the layout are transparent.
+            "lr" | "mid" | "display" | "inline" | "script" | "sscript" | "limits" | "scripts"
+            | "class" | "cancel" => match args.last() {
+                Some(arg) => self.latex_group(*arg),
+                None => EcoString::new(),
+            },
+            _ => {
+                let args = args.iter().map(|arg| self.latex_group(*arg));
+                let args = args.collect::<Vec<_>>().join(", ");
+                eco_format!("{}({args})", self.latex_ident(&name, &[]))
+            }
+        };
+
+        Some(latex)
+    }
+
+    fn mathml_seq(&self, math: ast::Math) -> EcoString {
+        let mut s = EcoString::new();
+        for expr in math.exprs() {
+            s.push_str(&self.mathml_expr(expr));
+        }
+        s
+    }
+
+    /// Translate a script or an argument, whose parentheses are only used for
+    /// grouping.
+    fn mathml_group(&self, expr: ast::Expr) -> EcoString {
+        match unparen(expr) {
+            Ok(math) => eco_format!("<mrow>{}</mrow>", self.mathml_seq(math)),
+            Err(expr) => self.mathml_expr(expr),
+        }
+    }
+
+    fn mathml_expr(&self, expr: ast::Expr) -> EcoString {
+        match expr {
+            ast::Expr::Math(math) => eco_format!("<mrow>{}</mrow>", self.mathml_seq(math)),
+            ast::Expr::Text(text) => mathml_text(text.get()),
+            ast::Expr::Escape(escape) => mathml_char(escape.get()),
+            ast::Expr::Shorthand(shorthand) => mathml_char(shorthand.get()),
+            ast::Expr::MathShorthand(shorthand) => mathml_char(shorthand.get()),
+            ast::Expr::Str(text) => eco_format!("<mtext>{}</mtext>", escape_xml(&text.get())),
+            ast::Expr::MathIdent(ident) => self.mathml_ident(ident.get(), &[]),
+            ast::Expr::FieldAccess(access) => match symbol_path(access) {
+                Some((name, fields)) => self.mathml_ident(name, &fields),
+                None => EcoString::new(),
+            },
+            ast::Expr::MathDelimited(delimited) => {
+                let open = self.mathml_expr(delimited.open());
+                let body = self.mathml_seq(delimited.body());
+                let close = self.mathml_expr(delimited.close());
+                eco_format!("<mrow>{open}{body}{close}</mrow>")
+            }
+            ast::Expr::MathAttach(attach) => {
+                let mut base = self.mathml_expr(attach.base());
+                if let Some(primes) = attach.primes() {
+                    base = eco_format!("<mrow>{base}{}</mrow>", mathml_primes(primes.count()));
+                }
+                let bottom = attach.bottom().map(|bottom| self.mathml_group(bottom));
+                let top = attach.top().map(|top| self.mathml_group(top));
+                match (bottom, top) {
+                    (Some(bottom), Some(top)) => {
+                        eco_format!("<msubsup>{base}{bottom}{top}</msubsup>")
+                    }
+                    (Some(bottom), None) => eco_format!("<msub>{base}{bottom}</msub>"),
+                    (None, Some(top)) => eco_format!("<msup>{base}{top}</msup>"),
+                    (None, None) => base,
+                }
+            }
+            ast::Expr::MathPrimes(primes) => mathml_primes(primes.count()),
+            ast::Expr::MathFrac(frac) => {
+                let num = self.mathml_group(frac.num());
+                let denom = self.mathml_group(frac.denom());
+                eco_format!("<mfrac>{num}{denom}</mfrac>")
+            }
+            ast::Expr::MathRoot(root) => {
+                let radicand = self.mathml_group(root.radicand());
+                match root.index() {
+                    Some(index) => eco_format!("<mroot>{radicand}<mn>{index}</mn></mroot>"),
+                    None => eco_format!("<msqrt>{radicand}</msqrt>"),
+                }
+            }
+            ast::Expr::FuncCall(call) => self.mathml_call(call).unwrap_or_default(),
+            // Spaces are determined by the renderer, and embedded code cannot
+            // be translated.
+            _ => EcoString::new(),
+        }
+    }
+
+    fn mathml_ident(&self, name: &str, fields: &[&str]) -> EcoString {
+        if fields.is_empty() {
+            if OPERATORS.contains(&name) {
+                return eco_format!("<mi>{name}</mi>");
+            }
+            if let Some((.., width)) = SPACES.iter().find(|(space, ..)| *space == name) {
+                return eco_format!(r#"<mspace width="{width}"/>"#);
+            }
+            if name == "dif" || name == "Dif" {
+                return eco_format!(r#"<mi mathvariant="normal">{}</mi>"#, &name[..1]);
+            }
+        }
+
+        match self.symbol(name, fields) {
+            Some(c) => mathml_char(c),
+            None => eco_format!("<mi>{}</mi>", escape_xml(name)),
+        }
+    }
+
+    fn mathml_call(&self, call: ast::FuncCall) -> Option<EcoString> {
+        let name = callee_name(call)?;
+        let args = pos_args(call);
+        let arg = |idx: usize| match args.get(idx) {
+            Some(arg) => self.mathml_group(*arg),
+            None => "<mrow></mrow>".into(),
+        };
+
+        if let Some((.., variant)) = VARIANTS.iter().find(|(v, ..)| name == *v) {
+            return Some(eco_format!(
+                r#"<mstyle mathvariant="{variant}">{}</mstyle>"#,
+                arg(0)
+            ));
+        }
+        if let Some((.., accent)) = ACCENTS.iter().find(|(a, ..)| name == *a) {
+            let accent = mathml_char(*accent);
+            return Some(eco_format!(
+                r#"<mover accent="true">{}{accent}</mover>"#,
+                arg(0)
+            ));
+        }
+        if let Some((_, open, close)) = FENCES.iter().find(|(fence, ..)| name == *fence) {
+            return Some(eco_format!(
+                "<mrow><mo>{open}</mo>{}<mo>{close}</mo></mrow>",
+                arg(0)
+            ));
+        }
+
+        let mathml = match name.as_str() {
+            "frac" => eco_format!("<mfrac>{}{}</mfrac>", arg(0), arg(1)),
+            "binom" => eco_format!(
+                r#"<mrow><mo>(</mo><mfrac linethickness="0">{}{}</mfrac><mo>)</mo></mrow>"#,
+                arg(0),
+                arg(1)
+            ),
+            "sqrt" => eco_format!("<msqrt>{}</msqrt>", arg(0)),
+            "root" => eco_format!("<mroot>{}{}</mroot>", arg(1), arg(0)),
+            "underline" => eco_format!("<munder>{}<mo>_</mo></munder>", arg(0)),
+            "underbrace" => eco_format!("<munder>{}<mo>⏟</mo></munder>", arg(0)),
+            "op" => match args.first() {
+                Some(ast::Expr::Str(text)) => eco_format!("<mi>{}</mi>", escape_xml(&text.get())),
+                _ => arg(0),
+            },
+            "vec" | "mat" | "cases" => {
+                let rows = matrix_rows(&name, &args);
+                let rows = rows.into_iter().map(|row| {
+                    let cells = row
+                        .into_iter()
+                        .map(|cell| eco_format!("<mtd>{}</mtd>", self.mathml_group(cell)));
+                    eco_format!("<mtr>{}</mtr>", cells.collect::<Vec<_>>().concat())
+                });
+                let table = eco_format!("<mtable>{}</mtable>", rows.collect::<Vec<_>>().concat());
+                if name == "cases" {
+                    eco_format!("<mrow><mo>{{</mo>{table}</mrow>")
+                } else {
+                    eco_format!("<mrow><mo>(</mo>{table}<mo>)</mo></mrow>")
+                }
+            }
+            // The functions only affecting the layout are transparent.
+            "lr" | "mid" | "display" | "inline" | "script" | "sscript" | "limits" | "scripts"
+            | "class" | "cancel" => match args.last() {
+                Some(arg) => self.mathml_group(*arg),
+                None => EcoString::new(),
+            },
+            _ => {
+                let args = args.iter().map(|arg| self.mathml_group(*arg));
+                let args = args.collect::<Vec<_>>().join("<mo>,</mo>");
+                let callee = self.mathml_ident(&name, &[]);
+                eco_format!("<mrow>{callee}<mo>(</mo>{args}<mo>)</mo></mrow>")
+            }
+        };
+
+        Some(mathml)
+    }
+
+    /// Resolve a symbol in the math scope, such as `arrow.r`.
+    fn symbol(&self, name: &str, fields: &[&str]) -> Option<char> {
+        let TypstValue::Symbol(symbol) = self.library.math.scope().get(name)? else {
+            return None;
+        };
+
+        let mut symbol = symbol.clone();
+        for field in fields {
+            symbol = symbol.modified(field).ok()?;
+        }
+        Some(symbol.get())
+    }
+}
+
+/// Get the body of parenthesized math, or the expression itself otherwise.
+fn unparen(expr: ast::Expr) -> std::result::Result<ast::Math, ast::Expr> {
+    if let ast::Expr::MathDelimited(delimited) = expr {
+        let is_paren = |expr: ast::Expr, paren: &str| match expr {
+            ast::Expr::Text(text) => text.get() == paren,
+            _ => false,
+        };
+        if is_paren(delimited.open(), "(") && is_paren(delimited.close(), ")") {
+            return Ok(delimited.body());
+        }
+    }
+
+    Err(expr)
+}
+
+/// Get the name of a function called in math, such as `frac` or `arrow.r`.
+fn callee_name(call: ast::FuncCall) -> Option<EcoString> {
+    match call.callee() {
+        ast::Expr::MathIdent(ident) => Some(ident.get().clone()),
+        ast::Expr::Ident(ident) => Some(ident.get().clone()),
+        ast::Expr::FieldAccess(access) => {
+            let (name, fields) = symbol_path(access)?;
+            let mut path = EcoString::from(name);
+            for field in fields {
+                path.push('.');
+                path.push_str(field);
+            }
+            Some(path)
+        }
+        _ => None,
+    }
+}
+
+fn pos_args(call: ast::FuncCall) -> Vec<ast::Expr> {
+    let args = call.args().items().filter_map(|arg| match arg {
+        ast::Arg::Pos(expr) => Some(expr),
+        _ => None,
+    });
+    args.collect()
+}
+
+/// Get the rows of a matrix, a vector or cases. Semicolons in the arguments
+/// separate the rows of a matrix.
+fn matrix_rows<'a>(name: &str, args: &[ast::Expr<'a>]) -> Vec<Vec<ast::Expr<'a>>> {
+    let has_rows = args.iter().any(|arg| matches!(arg, ast::Expr::Array(..)));
+    if name == "mat" && !has_rows {
+        return vec![args.to_vec()];
+    }
+
+    let rows = args.iter().map(|arg| match arg {
+        ast::Expr::Array(array) => array
+            .items()
+            .filter_map(|item| match item {
+                ast::ArrayItem::Pos(expr) => Some(expr),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![*arg],
+    });
+    rows.collect()
+}
+
+/// Get the name and the fields of a symbol, such as `arrow.r.long`.
+fn symbol_path(access: ast::FieldAccess) -> Option<(&str, Vec<&str>)> {
+    let mut fields = vec![access.field().get().as_str()];
+    let mut target = access.target();
+    loop {
+        match target {
+            ast::Expr::FieldAccess(access) => {
+                fields.push(access.field().get().as_str());
+                target = access.target();
+            }
+            ast::Expr::MathIdent(ident) => {
+                fields.reverse();
+                return Some((ident.get().as_str(), fields));
+            }
+            ast::Expr::Ident(ident) => {
+                fields.reverse();
+                return Some((ident.get().as_str(), fields));
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Push a LaTeX token, separating it from a preceding command.
+fn push_latex(s: &mut EcoString, token: &str) {
+    let letters = s.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let after_command = letters.len() < s.len() && letters.ends_with('\\');
+    if after_command && token.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        s.push(' ');
+    }
+    s.push_str(token);
+}
+
+fn latex_delim(delim: &str) -> EcoString {
+    match delim {
+        "‖" => "\\|".into(),
+        "⌊" => "\\lfloor".into(),
+        "⌋" => "\\rfloor".into(),
+        "⌈" => "\\lceil".into(),
+        "⌉" => "\\rceil".into(),
+        _ => delim.into(),
+    }
+}
+
+/// Translate a character to LaTeX.
+fn latex_char(c: char) -> EcoString {
+    let command = match c {
+        '{' | '}' | '#' | '%' | '&' | '$' | '_' => return eco_format!("\\{c}"),
+        'α' => "alpha",
+        'β' => "beta",
+        'γ' => "gamma",
+        'δ' => "delta",
+        'ε' => "varepsilon",
+        'ϵ' => "epsilon",
+        'ζ' => "zeta",
+        'η' => "eta",
+        'θ' => "theta",
+        'ϑ' => "vartheta",
+        'ι' => "iota",
+        'κ' => "kappa",
+        'λ' => "lambda",
+        'μ' => "mu",
+        'ν' => "nu",
+        'ξ' => "xi",
+        'π' => "pi",
+        'ϖ' => "varpi",
+        'ρ' => "rho",
+        'ϱ' => "varrho",
+        'σ' => "sigma",
+        'ς' => "varsigma",
+        'τ' => "tau",
+        'υ' => "upsilon",
+        'φ' => "varphi",
+        'ϕ' => "phi",
+        'χ' => "chi",
+        'ψ' => "psi",
+        'ω' => "omega",
+        'Γ' => "Gamma",
+        'Δ' => "Delta",
+        'Θ' => "Theta",
+        'Λ' => "Lambda",
+        'Ξ' => "Xi",
+        'Π' => "Pi",
+        'Σ' => "Sigma",
+        'Υ' => "Upsilon",
+        'Φ' => "Phi",
+        'Ψ' => "Psi",
+        'Ω' => "Omega",
+        '≤' => "leq",
+        '≥' => "geq",
+        '≠' => "neq",
+        '≈' => "approx",
+        '≡' => "equiv",
+        '∼' => "sim",
+        '≃' => "simeq",
+        '≅' => "cong",
+        '∝' => "propto",
+        '≪' => "ll",
+        '≫' => "gg",
+        '±' => "pm",
+        '∓' => "mp",
+        '×' => "times",
+        '÷' => "div",
+        '⋅' => "cdot",
+        '∘' => "circ",
+        '∗' => "ast",
+        '⊕' => "oplus",
+        '⊗' => "otimes",
+        '∞' => "infty",
+        '∂' => "partial",
+        '∇' => "nabla",
+        '∑' => "sum",
+        '∏' => "prod",
+        '∐' => "coprod",
+        '∫' => "int",
+        '∬' => "iint",
+        '∭' => "iiint",
+        '∮' => "oint",
+        '∈' => "in",
+        '∉' => "notin",
+        '∋' => "ni",
+        '⊂' => "subset",
+        '⊃' => "supset",
+        '⊆' => "subseteq",
+        '⊇' => "supseteq",
+        '∪' => "cup",
+        '∩' => "cap",
+        '∖' => "setminus",
+        '∅' => "emptyset",
+        '∀' => "forall",
+        '∃' => "exists",
+        '¬' => "neg",
+        '∧' => "wedge",
+        '∨' => "vee",
+        '⊢' => "vdash",
+        '⊨' => "models",
+        '⊥' => "perp",
+        '∥' => "parallel",
+        '∣' => "mid",
+        '∠' => "angle",
+        '→' => "to",
+        '←' => "leftarrow",
+        '↔' => "leftrightarrow",
+        '↑' => "uparrow",
+        '↓' => "downarrow",
+        '⇒' => "Rightarrow",
+        '⇐' => "Leftarrow",
+        '⇔' => "Leftrightarrow",
+        '⟶' => "longrightarrow",
+        '⟹' => "implies",
+        '⟺' => "iff",
+        '↦' => "mapsto",
+        '…' => "ldots",
+        '⋯' => "cdots",
+        '⋮' => "vdots",
+        '⋱' => "ddots",
+        '⟨' => "langle",
+        '⟩' => "rangle",
+        'ℓ' => "ell",
+        'ℏ' => "hbar",
+        'ℝ' => return "\\mathbb{R}".into(),
+        'ℕ' => return "\\mathbb{N}".into(),
+        'ℤ' => return "\\mathbb{Z}".into(),
+        'ℚ' => return "\\mathbb{Q}".into(),
+        'ℂ' => return "\\mathbb{C}".into(),
+        '′' => return "'".into(),
+        // Other characters are supported by KaTeX and MathJax as they are.
+        _ => return c.into(),
+    };
+
+    eco_format!("\\{command}")
+}
+
+fn mathml_text(text: &str) -> EcoString {
+    let mut s = EcoString::new();
+    let mut number = EcoString::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() || (c == '.' && !number.is_empty()) {
+            number.push(c);
+            continue;
+        }
+        if !number.is_empty() {
+            let _ = write!(s, "<mn>{number}</mn>");
+            number.clear();
+        }
+        s.push_str(&mathml_char(c));
+    }
+    if !number.is_empty() {
+        let _ = write!(s, "<mn>{number}</mn>");
+    }
+    s
+}
+
+fn mathml_char(c: char) -> EcoString {
+    let text = escape_xml(c.encode_utf8(&mut [0; 4]));
+    if c.is_ascii_digit() {
+        eco_format!("<mn>{text}</mn>")
+    } else if c.is_alphabetic() {
+        eco_format!("<mi>{text}</mi>")
+    } else {
+        eco_format!("<mo>{text}</mo>")
+    }
+}
+
+fn mathml_primes(count: usize) -> EcoString {
+    eco_format!("<mo>{}</mo>", "′".repeat(count))
+}
+
+fn escape_xml(text: &str) -> EcoString {
+    let mut s = EcoString::new();
+    for c in text.chars() {
+        match c {
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '"' => s.push_str("&quot;"),
+            _ => s.push(c),
+        }
+    }
+    s
+}
@@ -0,0 +1,196 @@
+//! # Typlite Notes
+//!
+//! Footnotes and citations are collected during the conversion and rendered at
+//! the end of the document.
+
+use std::collections::HashSet;
+
+use hayagriva::Entry;
+
+use crate::writer::{ListKind, Writer};
+use crate::*;
+
+/// The footnotes and citations of a document.
+#[derive(Default)]
+pub struct Notes {
+    /// The bodies of the footnotes.
+    footnotes: Vec<EcoString>,
+    /// The entries of the bibliography files.
+    entries: Vec<Entry>,
+    /// The keys of the cited entries, in the order of their first citations.
+    cited: Vec<EcoString>,
+    /// The placed bibliography.
+    bibliography: Option<Bibliography>,
+}
+
+/// A bibliography placed in the document.
+pub struct Bibliography {
+    /// The title of the references section.
+    pub title: Option<EcoString>,
+    /// Whether to list all the entries instead of the cited ones.
+    pub full: bool,
+}
+
+impl Notes {
+    /// Load the bibliography files used by the source and its included files.
+    pub fn load_bibliography(&mut self, world: &LspWorld, src: &Source) {
+        self.scan(world, src, &mut HashSet::new());
+    }
+
+    /// Add a footnote and get its number.
+    pub fn footnote(&mut self, body: EcoString) -> usize {
+        self.footnotes.push(body);
+        self.footnotes.len()
+    }
+
+    /// Cite an entry and get its number, which is assigned by the order of the
+    /// first citations.
+    pub fn cite(&mut self, key: &str) -> Option<usize> {
+        if let Some(idx) = self.cited.iter().position(|cited| cited == key) {
+            return Some(idx + 1);
+        }
+
+        self.entries.iter().find(|entry| entry.key() == key)?;
+        self.cited.push(key.into());
+        Some(self.cited.len())
+    }
+
+    /// Place the bibliography.
+    pub fn place_bibliography(&mut self, bibliography: Bibliography) {
+        self.bibliography = Some(bibliography);
+    }
+
+    /// Render the references section and the footnote definitions.
+    pub fn render(&mut self, s: &mut EcoString, writer: &dyn Writer) {
+        let mut sections = vec![];
+
+        if let Some(bibliography) = self.bibliography.take() {
+            if bibliography.full {
+                let keys = self.entries.iter().map(|entry| entry.key().to_owned());
+                for key in keys.collect::<Vec<_>>() {
+                    self.cite(&key);
+                }
+            }
+
+            let references = self.cited.iter().enumerate().filter_map(|(idx, key)| {
+                let entry = self.entries.iter().find(|entry| entry.key() == key)?;
+                Some(writer.enum_item(Some(idx + 1), &reference(entry, writer)))
+            });
+            let references = references.collect::<Vec<_>>();
+            if !references.is_empty() {
+                let mut section = EcoString::new();
+                if let Some(title) = &bibliography.title {
+                    let _ = writeln!(section, "{}\n", writer.heading(1, title));
+                }
+                section.push_str(&writer.list_begin(ListKind::Numbered));
+                section.push_str(&references.join("\n"));
+                section.push_str(&writer.list_end(ListKind::Numbered));
+                sections.push(section);
+            }
+        }
+
+        let footnotes = self.footnotes.iter().enumerate();
+        let footnotes = footnotes.filter_map(|(idx, body)| writer.footnote_def(idx + 1, body));
+        let footnotes = footnotes.collect::<Vec<_>>();
+        if !footnotes.is_empty() {
+            sections.push(footnotes.join("\n").into());
+        }
+
+        for section in sections {
+            s.push_str("\n\n");
+            s.push_str(&section);
+        }
+    }
+
+    fn scan(&mut self, world: &LspWorld, src: &Source, visited: &mut HashSet<FileId>) {
+        if visited.insert(src.id()) {
+            self.scan_node(world, src.id(), src.root(), visited);
+        }
+    }
+
+    fn scan_node(
+        &mut self,
+        world: &LspWorld,
+        current: FileId,
+        node: &SyntaxNode,
+        visited: &mut HashSet<FileId>,
+    ) {
+        if let Some(call) = node.cast::<ast::FuncCall>() {
+            if matches!(call.callee(), ast::Expr::Ident(callee) if callee.get() == "bibliography") {
+                for path in bibliography_paths(call.args()) {
+                    self.load(world, current.join(&path));
+                }
+            }
+        } else if let Some(include) = node.cast::<ast::ModuleInclude>() {
+            let path = include.source();
+            if let Some(src) = tinymist_analysis::import::find_source_by_expr(world, current, path)
+            {
+                self.scan(world, &src, visited);
+            }
+        }
+
+        for child in node.children() {
+            self.scan_node(world, current, child, visited);
+        }
+    }
+
+    fn load(&mut self, world: &LspWorld, id: FileId) -> Option<()> {
+        let bytes = world.file(id).ok()?;
+        let content = std::str::from_utf8(&bytes).ok()?;
+
+        let ext = id.vpath().as_rootless_path().extension()?.to_str()?;
+        let library = match ext.to_lowercase().as_str() {
+            "yml" | "yaml" => hayagriva::io::from_yaml_str(content).ok()?,
+            "bib" => hayagriva::io::from_biblatex_str(content).ok()?,
+            _ => return None,
+        };
+
+        self.entries.extend(library.iter().cloned());
+        Some(())
+    }
+}
+
+/// Get the paths passed to a `bibliography` call.
+fn bibliography_paths(args: ast::Args) -> Vec<EcoString> {
+    let Some(path) = args.items().find_map(|item| match item {
+        ast::Arg::Pos(expr) => Some(expr),
+        _ => None,
+    }) else {
+        return vec![];
+    };
+
+    match path {
+        ast::Expr::Str(path) => vec![path.get()],
+        ast::Expr::Array(paths) => paths
+            .items()
+            .filter_map(|item| match item {
+                ast::ArrayItem::Pos(ast::Expr::Str(path)) => Some(path.get()),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Render an entry of the references section.
+fn reference(entry: &Entry, writer: &dyn Writer) -> EcoString {
+    let mut parts = vec![];
+    if let Some(authors) = entry.authors() {
+        let authors = authors.iter().map(|person| match &person.given_name {
+            Some(given_name) => writer.text(&eco_format!("{given_name} {}", person.name)),
+            None => writer.text(&person.name),
+        });
+        parts.push(authors.collect::<Vec<_>>().join(", ").into());
+    }
+    if let Some(title) = entry.title() {
+        parts.push(writer.emph(&writer.text(&title.value.to_string())));
+    }
+    if let Some(date) = entry.date() {
+        parts.push(eco_format!("{}", date.year));
+    }
+    if parts.is_empty() {
+        parts.push(writer.text(entry.key()));
+    }
+
+    eco_format!("{}.", parts.join(". "))
+}
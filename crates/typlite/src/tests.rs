@@ -9,7 +9,12 @@ use typst_syntax::Source;
 
 use super::*;
 
-fn conv_(s: &str, for_docs: bool) -> EcoString {
+fn conv_(s: &str, feat: TypliteFeat) -> EcoString {
+    conv_files(s, &[], feat)
+}
+
+/// Converts the source with the files placed next to it, e.g. bibliographies.
+fn conv_files(s: &str, files: &[(&str, &str)], feat: TypliteFeat) -> EcoString {
     static FONT_RESOLVER: LazyLock<Arc<TinymistFontResolver>> = LazyLock::new(|| {
         Arc::new(
             LspUniverseBuilder::resolve_fonts(CompileFontArgs::default())
@@ -29,12 +34,14 @@ fn conv_(s: &str, for_docs: bool) -> EcoString {
     universe
         .map_shadow_by_id(main.id(), Bytes::from(main.text().as_bytes().to_owned()))
         .unwrap();
+    for (path, content) in files {
+        universe
+            .map_shadow(&cwd.join(path), Bytes::from(content.as_bytes().to_owned()))
+            .unwrap();
+    }
     let world = universe.snapshot();
 
-    let converter = Typlite::new(Arc::new(world)).with_feature(TypliteFeat {
-        annotate_elem: for_docs,
-        ..Default::default()
-    });
+    let converter = Typlite::new(Arc::new(world)).with_feature(feat);
     let res = converter.convert().unwrap();
     static REG: OnceLock<Regex> = OnceLock::new();
     let reg = REG.get_or_init(|| Regex::new(r#"data:image/svg\+xml;base64,([^"]+)"#).unwrap());
@@ -46,11 +53,58 @@ fn conv_(s: &str, for_docs: bool) -> EcoString {
 }
 
 fn conv(s: &str) -> EcoString {
-    conv_(s, false)
+    conv_(s, TypliteFeat::default())
+}
+
+fn conv_gfm(s: &str) -> EcoString {
+    conv_(
+        s,
+        TypliteFeat {
+            gfm: true,
+            ..Default::default()
+        },
+    )
 }
 
 fn conv_docs(s: &str) -> EcoString {
-    conv_(s, true)
+    conv_(
+        s,
+        TypliteFeat {
+            annotate_elem: true,
+            ..Default::default()
+        },
+    )
+}
+
+fn conv_eval(s: &str) -> EcoString {
+    conv_(
+        s,
+        TypliteFeat {
+            gfm: true,
+            evaluate: true,
+            ..Default::default()
+        },
+    )
+}
+
+fn conv_math(s: &str, math: MathMode) -> EcoString {
+    conv_(
+        s,
+        TypliteFeat {
+            math,
+            ..Default::default()
+        },
+    )
+}
+
+fn conv_format(s: &str, format: Format) -> EcoString {
+    conv_(
+        s,
+        TypliteFeat {
+            format,
+            ..Default::default()
+        },
+    )
 }
 
 #[test]
@@ -66,6 +120,19 @@ This is a typst document.
 Some inlined raw `a`, ```c b```
         "###), @"Some inlined raw `a`, `b`");
     insta::assert_snapshot!(conv(r###"
+````md
+```rust
+fn main() {}
+```
+````
+        "###), @r"
+    ````md
+    ```rust
+    fn main() {}
+    ```
+    ````
+    ");
+    insta::assert_snapshot!(conv(r###"
 - Some *item*
 - Another _item_
         "###), @r"
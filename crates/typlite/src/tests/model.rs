@@ -39,3 +39,236 @@ fn test_simple_figure() {
 #figure(image("./fig.png", alt: "Content"), caption: "Caption")
             "###), @"![Caption, Content](./fig.png)");
 }
+
+#[test]
+fn test_simple_table() {
+    insta::assert_snapshot!(conv(r###"
+#table(columns: 2, [a], [b], [c], [d])
+            "###), @r"
+    <table>
+    <tbody>
+    <tr><td>a</td><td>b</td></tr>
+    <tr><td>c</td><td>d</td></tr>
+    </tbody>
+    </table>
+    ");
+    insta::assert_snapshot!(conv_gfm(r###"
+#table(
+  columns: 2,
+  table.header[Name][Value],
+  [a], [1],
+  [b | c], [2],
+)
+            "###), @r"
+    | Name | Value |
+    | --- | --- |
+    | a | 1 |
+    | b \| c | 2 |
+    ");
+}
+
+#[test]
+fn test_spanned_table() {
+    insta::assert_snapshot!(conv_gfm(r###"
+#table(columns: 2, table.cell(colspan: 2)[wide], [a], [b])
+            "###), @r#"
+    <table>
+    <tbody>
+    <tr><td colspan="2">wide</td></tr>
+    <tr><td>a</td><td>b</td></tr>
+    </tbody>
+    </table>
+    "#);
+}
+
+#[test]
+fn test_huge_spanned_table() {
+    insta::assert_snapshot!(conv_gfm(r###"
+#table(columns: 100000000, table.cell(colspan: 100000000)[wide], [a])
+            "###), @r#"
+    <table>
+    <tbody>
+    <tr><td colspan="2">wide</td></tr>
+    <tr><td>a</td></tr>
+    </tbody>
+    </table>
+    "#);
+    insta::assert_snapshot!(conv_gfm(r###"
+#table(columns: 2, table.cell(rowspan: 1000000000)[tall], [a], [b])
+            "###), @r#"
+    <table>
+    <tbody>
+    <tr><td rowspan="2">tall</td><td>a</td></tr>
+    <tr><td>b</td></tr>
+    </tbody>
+    </table>
+    "#);
+}
+
+#[test]
+fn test_table_figure() {
+    insta::assert_snapshot!(conv_gfm(r###"
+#figure(table(columns: 2, [a], [b]), caption: [Numbers])
+            "###), @r"
+    | a | b |
+    | --- | --- |
+
+    Table: Numbers
+    ");
+    insta::assert_snapshot!(conv(r###"
+#figure(table(columns: 2, table.header[x][y], [1], [2]), caption: [Numbers])
+            "###), @r"
+    <table>
+    <caption>Numbers</caption>
+    <thead>
+    <tr><th>x</th><th>y</th></tr>
+    </thead>
+    <tbody>
+    <tr><td>1</td><td>2</td></tr>
+    </tbody>
+    </table>
+    ");
+}
+
+#[test]
+fn test_term_list() {
+    insta::assert_snapshot!(conv(r###"/ Term: Description"###), @"/ Term: Description");
+}
+
+#[test]
+fn test_footnote() {
+    insta::assert_snapshot!(conv(r###"Some text#footnote[A note.] and more.#footnote[Another
+note.]"###), @r"
+    Some text[^1] and more.[^2]
+
+    [^1]: A note.
+    [^2]: Another
+        note.
+    ");
+}
+
+#[test]
+fn test_unresolved_citation() {
+    insta::assert_snapshot!(conv(r###"See @intro and #cite(<missing>)."###), @"See @intro and @missing.");
+}
+
+#[test]
+fn test_citation() {
+    let bib = r###"
+@article{smith2020,
+  author = {Smith, John},
+  title = {A Study},
+  year = {2020},
+}
+@book{doe2019,
+  author = {Doe, Jane},
+  title = {The Book},
+  year = {2019},
+}
+"###;
+    insta::assert_snapshot!(conv_files(r###"See @doe2019, @smith2020 and @doe2019 again.#bibliography("refs.bib", title: [References])"###, &[("refs.bib", bib)], Default::default()), @r"
+    See [1], [2] and [1] again.
+
+    # References
+
+    1. Jane Doe. _The Book_. 2019.
+    2. John Smith. _A Study_. 2020.
+    ");
+
+    let yml = r###"
+knuth:
+  type: book
+  title: The Art of Computer Programming
+  author: Knuth, Donald
+  date: 1968
+"###;
+    insta::assert_snapshot!(conv_files(r###"Everything.#bibliography("refs.yml", full: true)"###, &[("refs.yml", yml)], Default::default()), @r"
+    Everything.
+
+    # Bibliography
+
+    1. Donald Knuth. _The Art of Computer Programming_. 1968.
+    ");
+}
+
+#[test]
+fn test_evaluated_template() {
+    insta::assert_snapshot!(conv_eval(r###"
+#let template(title, body) = {
+  heading(title)
+  body
+}
+#show: template.with[Report]
+
+Some *strong* text with a #link("https://typst.app")[link].
+
+- first
+- second
+            "###), @r"
+    # Report
+
+    Some **strong** text with a [link](https://typst.app).
+
+    - first
+    - second
+    ");
+}
+
+#[test]
+fn test_evaluated_show_rule() {
+    insta::assert_snapshot!(conv_eval(r###"
+#show heading: it => heading(level: 2, [Section: ] + it.body)
+#show strong: it => emph(it.body)
+= Intro
+Some *bold* text.
+            "###), @r"
+    ## Section: Intro
+
+    Some _bold_ text.
+    ");
+    insta::assert_snapshot!(conv_eval(r###"
+#let row(..cells) = cells.pos()
+#table(columns: 2, ..row[a][b], ..row[c][d])
+            "###), @r"
+    | a | b |
+    | --- | --- |
+    | c | d |
+    ");
+}
+
+#[test]
+fn test_latex() {
+    insta::assert_snapshot!(conv_format(r###"= Intro
+Some *strong* and _emph_ text with 50% off & `code`.
+- a
+- b"###, Format::Latex), @r"
+    \documentclass{article}
+    \usepackage{amsmath}
+    \usepackage{graphicx}
+    \usepackage{hyperref}
+
+    \begin{document}
+    \section{Intro}
+    Some \textbf{strong} and \emph{emph} text with 50\% off \& \texttt{code}.
+    \begin{itemize}
+    \item a
+    \item b
+    \end{itemize}
+    \end{document}
+    ");
+}
+
+#[test]
+fn test_rst() {
+    insta::assert_snapshot!(conv_format(r###"= Intro
+Some *strong* text with a #link("https://typst.app")[link].
++ one
++ two"###, Format::Rst), @r"
+    Intro
+    =====
+    Some **strong** text with a `link <https://typst.app>`__.
+
+    #. one
+    #. two
+    ");
+}
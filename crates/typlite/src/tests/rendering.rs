@@ -9,3 +9,33 @@ $integral x dif x$
 $ integral x dif x $
         "###), @r#"<p align="center"><picture><source media="(prefers-color-scheme: dark)" srcset="data:image-hash/svg+xml;base64,redacted"><img alt="typst-block" src="data:image-hash/svg+xml;base64,redacted" /></picture></p>"#);
 }
+
+#[test]
+fn test_math_latex() {
+    insta::assert_snapshot!(conv_math(r###"
+$x^2 + y_1 = z$
+        "###, MathMode::Latex), @"$x^{2} + y_{1} = z$");
+    insta::assert_snapshot!(conv_math(r###"
+$ sum_(i=1)^n i = (n(n+1))/2 $
+        "###, MathMode::Latex), @r"
+    $$
+    \sum_{i=1}^{n} i = \frac{n(n+1)}{2}
+    $$
+    ");
+    insta::assert_snapshot!(conv_math(r###"
+$alpha <= beta, f(x) = sin x + arrow.r.long$
+        "###, MathMode::Latex), @r"$\alpha \leq \beta, f(x) = \sin x + \longrightarrow$");
+    insta::assert_snapshot!(conv_math(r###"
+$v = vec(1, 2)$
+        "###, MathMode::Latex), @r"$v = \begin{pmatrix} 1 \\ 2 \end{pmatrix}$");
+}
+
+#[test]
+fn test_math_mathml() {
+    insta::assert_snapshot!(conv_math(r###"
+$x^2$
+        "###, MathMode::MathMl), @r#"<math display="inline"><mrow><msup><mi>x</mi><mn>2</mn></msup></mrow></math>"#);
+    insta::assert_snapshot!(conv_math(r###"
+$ a/b $
+        "###, MathMode::MathMl), @r#"<math display="block"><mrow><mfrac><mi>a</mi><mi>b</mi></mfrac></mrow></math>"#);
+}
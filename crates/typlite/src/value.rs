@@ -13,6 +13,7 @@ pub enum Value {
     Str(EcoString),
     Content(EcoString),
     Image { path: EcoString, alt: EcoString },
+    Table(Table),
 }
 
 impl From<RawFunc> for Value {
@@ -21,6 +22,30 @@ impl From<RawFunc> for Value {
     }
 }
 
+/// A table whose cells are converted and laid out in rows.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Table {
+    /// The number of columns.
+    pub columns: usize,
+    /// The header rows.
+    pub header: Vec<Vec<Option<TableCell>>>,
+    /// The body rows, including the footer rows.
+    pub body: Vec<Vec<Option<TableCell>>>,
+    /// Whether to render the table as HTML.
+    pub html: bool,
+}
+
+/// A cell of a [`Table`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct TableCell {
+    /// The converted content of the cell.
+    pub body: EcoString,
+    /// The number of columns spanned by the cell.
+    pub colspan: usize,
+    /// The number of rows spanned by the cell.
+    pub rowspan: usize,
+}
+
 pub struct Content(pub EcoString);
 
 impl fmt::Display for Content {
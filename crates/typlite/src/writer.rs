@@ -0,0 +1,590 @@
+//! # Typlite Writers
+//!
+//! The converters walk the document once, and the writers produce the syntax
+//! of the output format for each element.
+
+use std::sync::Arc;
+
+use ecow::{eco_format, EcoString};
+
+use crate::value::Table;
+use crate::TypliteFeat;
+
+/// The output format of the conversion.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Format {
+    /// Markdown, with HTML for the elements it lacks.
+    #[default]
+    Markdown,
+    /// A LaTeX document.
+    Latex,
+    /// reStructuredText.
+    Rst,
+}
+
+impl Format {
+    /// The extension of the files in the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Latex => "tex",
+            Format::Rst => "rst",
+        }
+    }
+
+    /// Create the writer of the format.
+    pub fn writer(self, feat: &TypliteFeat) -> Arc<dyn Writer> {
+        match self {
+            Format::Markdown => Arc::new(MarkdownWriter { gfm: feat.gfm }),
+            Format::Latex => Arc::new(LatexWriter),
+            Format::Rst => Arc::new(RstWriter),
+        }
+    }
+}
+
+/// The kind of a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    /// A bullet list.
+    Bullet,
+    /// A numbered list.
+    Numbered,
+    /// A term list.
+    Terms,
+}
+
+/// Writes the elements in the syntax of an output format.
+pub trait Writer {
+    /// Escape plain text.
+    fn text(&self, text: &str) -> EcoString {
+        text.into()
+    }
+
+    /// Write an escaped character, given the source of the escape sequence.
+    fn escape(&self, c: char, source: &str) -> EcoString {
+        let _ = source;
+        self.text(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Write a line break.
+    fn linebreak(&self) -> EcoString {
+        "\n".into()
+    }
+
+    /// Write a heading of the given level, which starts from 1.
+    fn heading(&self, level: usize, body: &str) -> EcoString;
+
+    /// Write strongly emphasized content.
+    fn strong(&self, body: &str) -> EcoString;
+
+    /// Write emphasized content.
+    fn emph(&self, body: &str) -> EcoString;
+
+    /// Write inline raw text.
+    fn raw_inline(&self, text: &str) -> EcoString;
+
+    /// Write a raw block, whose language may be empty.
+    fn raw_block(&self, lang: &str, text: &str) -> EcoString;
+
+    /// Write a link.
+    fn link(&self, dest: &str, body: &str) -> EcoString;
+
+    /// Write a bare URL.
+    fn url(&self, url: &str) -> EcoString {
+        self.link(url, &self.text(url))
+    }
+
+    /// Write an image, with an optional caption.
+    fn image(&self, path: &str, alt: &str, caption: Option<&str>) -> EcoString;
+
+    /// Write a table, with an optional caption.
+    fn table(&self, table: &Table, caption: Option<&str>) -> EcoString;
+
+    /// Write math in LaTeX syntax.
+    fn math(&self, math: &str, block: bool) -> EcoString;
+
+    /// Write the start of a list.
+    fn list_begin(&self, kind: ListKind) -> EcoString {
+        let _ = kind;
+        EcoString::new()
+    }
+
+    /// Write the end of a list.
+    fn list_end(&self, kind: ListKind) -> EcoString {
+        let _ = kind;
+        EcoString::new()
+    }
+
+    /// Write an item of a bullet list.
+    fn list_item(&self, body: &str) -> EcoString;
+
+    /// Write an item of a numbered list, with an optional explicit number.
+    fn enum_item(&self, number: Option<usize>, body: &str) -> EcoString;
+
+    /// Write an item of a term list.
+    fn term_item(&self, term: &str, description: &str) -> EcoString;
+
+    /// Write the reference to a footnote.
+    fn footnote(&self, idx: usize, body: &str) -> EcoString;
+
+    /// Write the definition of a footnote at the end of the document, if the
+    /// format doesn't place footnotes inline.
+    fn footnote_def(&self, idx: usize, body: &str) -> Option<EcoString>;
+
+    /// Write an admonition, such as `NOTE` or `WARNING`.
+    fn admonition(&self, kind: &str, body: &str) -> EcoString;
+
+    /// Wrap the converted body into a document.
+    fn document(&self, body: EcoString) -> EcoString {
+        body
+    }
+}
+
+/// Writes Markdown.
+pub struct MarkdownWriter {
+    /// Whether GFM (GitHub Flavored Markdown) markups are allowed.
+    pub gfm: bool,
+}
+
+impl Writer for MarkdownWriter {
+    fn escape(&self, _c: char, source: &str) -> EcoString {
+        // todo: escape characters
+        source.into()
+    }
+
+    fn heading(&self, level: usize, body: &str) -> EcoString {
+        eco_format!("{} {body}", "#".repeat(level))
+    }
+
+    fn strong(&self, body: &str) -> EcoString {
+        eco_format!("**{body}**")
+    }
+
+    fn emph(&self, body: &str) -> EcoString {
+        eco_format!("_{body}_")
+    }
+
+    fn raw_inline(&self, text: &str) -> EcoString {
+        eco_format!("`{text}`")
+    }
+
+    fn raw_block(&self, lang: &str, text: &str) -> EcoString {
+        // The fence must be longer than any backtick run in the text.
+        let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat((longest + 1).max(3));
+        eco_format!("{fence}{lang}\n{text}\n{fence}")
+    }
+
+    fn link(&self, dest: &str, body: &str) -> EcoString {
+        eco_format!("[{body}]({dest})")
+    }
+
+    fn url(&self, url: &str) -> EcoString {
+        // GFM supports autolinks
+        if self.gfm {
+            return url.into();
+        }
+        self.link(url, url)
+    }
+
+    fn image(&self, path: &str, alt: &str, caption: Option<&str>) -> EcoString {
+        match caption {
+            None => eco_format!("![{alt}]({path})"),
+            Some(caption) if self.gfm => eco_format!("![{caption}, {alt}]({path} {caption:?})"),
+            Some(caption) => eco_format!("![{caption}, {alt}]({path})"),
+        }
+    }
+
+    fn table(&self, table: &Table, caption: Option<&str>) -> EcoString {
+        table.render(caption)
+    }
+
+    fn math(&self, math: &str, block: bool) -> EcoString {
+        if block {
+            eco_format!("$$\n{math}\n$$")
+        } else {
+            eco_format!("${math}$")
+        }
+    }
+
+    fn list_item(&self, body: &str) -> EcoString {
+        eco_format!("- {body}")
+    }
+
+    fn enum_item(&self, number: Option<usize>, body: &str) -> EcoString {
+        eco_format!("{}. {body}", number.unwrap_or(1))
+    }
+
+    fn term_item(&self, term: &str, description: &str) -> EcoString {
+        eco_format!("- **{term}**: {description}")
+    }
+
+    fn footnote(&self, idx: usize, _body: &str) -> EcoString {
+        eco_format!("[^{idx}]")
+    }
+
+    fn footnote_def(&self, idx: usize, body: &str) -> Option<EcoString> {
+        // Continuation lines are indented to stay in the footnote.
+        let body = body.trim().replace('\n', "\n    ");
+        Some(eco_format!("[^{idx}]: {body}"))
+    }
+
+    fn admonition(&self, kind: &str, body: &str) -> EcoString {
+        let mut res = eco_format!("> [!{kind}]\n");
+        for line in body.lines() {
+            res.push_str("> ");
+            res.push_str(line);
+            res.push('\n');
+        }
+        res
+    }
+}
+
+/// Writes a LaTeX document.
+pub struct LatexWriter;
+
+impl LatexWriter {
+    fn env(name: &str, body: &str) -> EcoString {
+        eco_format!("\\begin{{{name}}}\n{body}\n\\end{{{name}}}")
+    }
+}
+
+impl Writer for LatexWriter {
+    fn text(&self, text: &str) -> EcoString {
+        let mut s = EcoString::new();
+        for c in text.chars() {
+            match c {
+                '\\' => s.push_str("\\textbackslash{}"),
+                '~' => s.push_str("\\textasciitilde{}"),
+                '^' => s.push_str("\\textasciicircum{}"),
+                '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                    s.push('\\');
+                    s.push(c);
+                }
+                _ => s.push(c),
+            }
+        }
+        s
+    }
+
+    fn linebreak(&self) -> EcoString {
+        "\\\\\n".into()
+    }
+
+    fn heading(&self, level: usize, body: &str) -> EcoString {
+        let command = match level {
+            1 => "section",
+            2 => "subsection",
+            3 => "subsubsection",
+            4 => "paragraph",
+            _ => "subparagraph",
+        };
+        eco_format!("\\{command}{{{body}}}")
+    }
+
+    fn strong(&self, body: &str) -> EcoString {
+        eco_format!("\\textbf{{{body}}}")
+    }
+
+    fn emph(&self, body: &str) -> EcoString {
+        eco_format!("\\emph{{{body}}}")
+    }
+
+    fn raw_inline(&self, text: &str) -> EcoString {
+        eco_format!("\\texttt{{{}}}", self.text(text))
+    }
+
+    fn raw_block(&self, _lang: &str, text: &str) -> EcoString {
+        Self::env("verbatim", text)
+    }
+
+    fn link(&self, dest: &str, body: &str) -> EcoString {
+        let dest = dest.replace('%', "\\%").replace('#', "\\#");
+        eco_format!("\\href{{{dest}}}{{{body}}}")
+    }
+
+    fn image(&self, path: &str, _alt: &str, caption: Option<&str>) -> EcoString {
+        let image = eco_format!("\\includegraphics{{{path}}}");
+        match caption {
+            Some(caption) => Self::env(
+                "figure",
+                &eco_format!("\\centering\n{image}\n\\caption{{{caption}}}"),
+            ),
+            None => image,
+        }
+    }
+
+    fn table(&self, table: &Table, caption: Option<&str>) -> EcoString {
+        let mut rows = vec![];
+        for row in table.header.iter().chain(&table.body) {
+            let mut cells = vec![];
+            let mut covered = 0;
+            for cell in row {
+                // The positions covered by a spanned cell are skipped.
+                if covered > 0 {
+                    covered -= 1;
+                    continue;
+                }
+                match cell {
+                    Some(cell) if cell.colspan > 1 => {
+                        covered = cell.colspan - 1;
+                        let (colspan, body) = (cell.colspan, &cell.body);
+                        cells.push(eco_format!("\\multicolumn{{{colspan}}}{{l}}{{{body}}}"));
+                    }
+                    Some(cell) => cells.push(cell.body.clone()),
+                    None => cells.push(EcoString::new()),
+                }
+            }
+            rows.push(eco_format!("{} \\\\", cells.join(" & ")));
+        }
+        if !table.header.is_empty() {
+            rows.insert(table.header.len(), "\\hline".into());
+        }
+
+        let spec = vec!["l"; table.columns].join(" ");
+        let rows = rows.join("\n");
+        let tabular = eco_format!("\\begin{{tabular}}{{{spec}}}\n{rows}\n\\end{{tabular}}");
+        match caption {
+            Some(caption) => Self::env(
+                "table",
+                &eco_format!("\\centering\n{tabular}\n\\caption{{{caption}}}"),
+            ),
+            None => tabular,
+        }
+    }
+
+    fn math(&self, math: &str, block: bool) -> EcoString {
+        if block {
+            eco_format!("\\[\n{math}\n\\]")
+        } else {
+            eco_format!("${math}$")
+        }
+    }
+
+    fn list_begin(&self, kind: ListKind) -> EcoString {
+        eco_format!("\\begin{{{}}}\n", latex_list_env(kind))
+    }
+
+    fn list_end(&self, kind: ListKind) -> EcoString {
+        eco_format!("\n\\end{{{}}}", latex_list_env(kind))
+    }
+
+    fn list_item(&self, body: &str) -> EcoString {
+        eco_format!("\\item {body}")
+    }
+
+    fn enum_item(&self, _number: Option<usize>, body: &str) -> EcoString {
+        eco_format!("\\item {body}")
+    }
+
+    fn term_item(&self, term: &str, description: &str) -> EcoString {
+        eco_format!("\\item[{term}] {description}")
+    }
+
+    fn footnote(&self, _idx: usize, body: &str) -> EcoString {
+        eco_format!("\\footnote{{{}}}", body.trim())
+    }
+
+    fn footnote_def(&self, _idx: usize, _body: &str) -> Option<EcoString> {
+        None
+    }
+
+    fn admonition(&self, kind: &str, body: &str) -> EcoString {
+        let title = capitalize(kind);
+        Self::env(
+            "quote",
+            &eco_format!("\\textbf{{{title}.}} {}", body.trim()),
+        )
+    }
+
+    fn document(&self, body: EcoString) -> EcoString {
+        eco_format!(
+            "\\documentclass{{article}}\n\
+             \\usepackage{{amsmath}}\n\
+             \\usepackage{{graphicx}}\n\
+             \\usepackage{{hyperref}}\n\
+             \n\
+             \\begin{{document}}\n\
+             {}\n\
+             \\end{{document}}\n",
+            body.trim()
+        )
+    }
+}
+
+fn latex_list_env(kind: ListKind) -> &'static str {
+    match kind {
+        ListKind::Bullet => "itemize",
+        ListKind::Numbered => "enumerate",
+        ListKind::Terms => "description",
+    }
+}
+
+/// Writes reStructuredText.
+pub struct RstWriter;
+
+impl RstWriter {
+    /// Write a directive, whose content is indented.
+    fn directive(name: &str, argument: &str, options: &[(&str, &str)], body: &str) -> EcoString {
+        let mut s = eco_format!(".. {name}::");
+        if !argument.is_empty() {
+            s.push(' ');
+            s.push_str(argument);
+        }
+        s.push('\n');
+        for (key, value) in options {
+            s.push_str(&eco_format!("   :{key}: {value}\n"));
+        }
+        if !body.is_empty() {
+            s.push('\n');
+            s.push_str(&indent(body, 3));
+        }
+        s.trim_end().into()
+    }
+}
+
+impl Writer for RstWriter {
+    fn text(&self, text: &str) -> EcoString {
+        let mut s = EcoString::new();
+        for c in text.chars() {
+            if matches!(c, '\\' | '*' | '`' | '_' | '|') {
+                s.push('\\');
+            }
+            s.push(c);
+        }
+        s
+    }
+
+    fn heading(&self, level: usize, body: &str) -> EcoString {
+        const UNDERLINES: [char; 5] = ['=', '-', '~', '^', '"'];
+        let underline = UNDERLINES[level.clamp(1, UNDERLINES.len()) - 1];
+        let width = body.chars().count().max(1);
+        eco_format!("{body}\n{}", underline.to_string().repeat(width))
+    }
+
+    fn strong(&self, body: &str) -> EcoString {
+        eco_format!("**{body}**")
+    }
+
+    fn emph(&self, body: &str) -> EcoString {
+        eco_format!("*{body}*")
+    }
+
+    fn raw_inline(&self, text: &str) -> EcoString {
+        eco_format!("``{text}``")
+    }
+
+    fn raw_block(&self, lang: &str, text: &str) -> EcoString {
+        Self::directive("code-block", lang, &[], text)
+    }
+
+    fn link(&self, dest: &str, body: &str) -> EcoString {
+        // Anonymous hyperlinks don't conflict with each other.
+        eco_format!("`{body} <{dest}>`__")
+    }
+
+    fn url(&self, url: &str) -> EcoString {
+        url.into()
+    }
+
+    fn image(&self, path: &str, alt: &str, caption: Option<&str>) -> EcoString {
+        let mut options = vec![];
+        if !alt.is_empty() {
+            options.push(("alt", alt));
+        }
+        match caption {
+            Some(caption) => Self::directive("figure", path, &options, caption),
+            None => Self::directive("image", path, &options, ""),
+        }
+    }
+
+    fn table(&self, table: &Table, caption: Option<&str>) -> EcoString {
+        let mut body = EcoString::new();
+        for row in table.header.iter().chain(&table.body) {
+            for (idx, cell) in row.iter().enumerate() {
+                body.push_str(if idx == 0 { "* -" } else { "  -" });
+                if let Some(cell) = cell.as_ref().filter(|cell| !cell.body.is_empty()) {
+                    body.push(' ');
+                    body.push_str(&cell.body);
+                }
+                body.push('\n');
+            }
+        }
+
+        let header_rows = table.header.len().to_string();
+        let mut options = vec![];
+        if !table.header.is_empty() {
+            options.push(("header-rows", header_rows.as_str()));
+        }
+        Self::directive("list-table", caption.unwrap_or_default(), &options, &body)
+    }
+
+    fn math(&self, math: &str, block: bool) -> EcoString {
+        if block {
+            Self::directive("math", "", &[], math)
+        } else {
+            eco_format!(":math:`{math}`")
+        }
+    }
+
+    fn list_begin(&self, _kind: ListKind) -> EcoString {
+        // Lists are separated from paragraphs by blank lines.
+        "\n".into()
+    }
+
+    fn list_end(&self, _kind: ListKind) -> EcoString {
+        "\n".into()
+    }
+
+    fn list_item(&self, body: &str) -> EcoString {
+        eco_format!("- {body}")
+    }
+
+    fn enum_item(&self, number: Option<usize>, body: &str) -> EcoString {
+        match number {
+            Some(number) => eco_format!("{number}. {body}"),
+            None => eco_format!("#. {body}"),
+        }
+    }
+
+    fn term_item(&self, term: &str, description: &str) -> EcoString {
+        eco_format!("{term}\n{}", indent(description, 3))
+    }
+
+    fn footnote(&self, idx: usize, _body: &str) -> EcoString {
+        // The escaped space separates the reference from the preceding word.
+        eco_format!("\\ [{idx}]_")
+    }
+
+    fn footnote_def(&self, idx: usize, body: &str) -> Option<EcoString> {
+        let body = indent(body.trim(), 3);
+        Some(eco_format!(".. [{idx}] {}", body.trim_start()))
+    }
+
+    fn admonition(&self, kind: &str, body: &str) -> EcoString {
+        Self::directive(&kind.to_lowercase(), "", &[], body.trim())
+    }
+}
+
+/// Indent the non-empty lines of the text.
+fn indent(text: &str, width: usize) -> EcoString {
+    let indent = " ".repeat(width);
+    let lines = text.lines().map(|line| {
+        if line.trim().is_empty() {
+            EcoString::new()
+        } else {
+            eco_format!("{indent}{line}")
+        }
+    });
+    lines.collect::<Vec<_>>().join("\n").into()
+}
+
+fn capitalize(s: &str) -> EcoString {
+    let lower = s.to_lowercase();
+    let mut chars = lower.chars();
+    match chars.next() {
+        Some(first) => eco_format!("{}{}", first.to_uppercase(), chars.as_str()),
+        None => EcoString::new(),
+    }
+}
@@ -6,6 +6,7 @@ use tokio::sync::mpsc;
 
 use crate::debug_loc::{InternQuery, SpanInterner};
 use crate::outline::Outline;
+use crate::presenter::SlideState;
 use crate::{
     actor::typst::TypstActorRequest, ChangeCursorPositionRequest, DocToSrcJumpInfo, MemoryFiles,
     MemoryFilesShort, SrcToDocJumpRequest,
@@ -23,6 +24,12 @@ pub struct PanelScrollByPositionRequest {
     position: DocumentPosition,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ChangeSlideRequest {
+    /// The slide to show, starting from 1.
+    page: usize,
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind", content = "data")]
 pub enum CompileStatus {
@@ -38,6 +45,10 @@ pub enum EditorActorRequest {
     DocToSrcJump(DocToSrcJumpInfo),
     Outline(Outline),
     CompileStatus(CompileStatus),
+    SpeakerNotes(Vec<String>),
+    /// Change the current slide, or resend the state of the current slide if
+    /// it is `None`.
+    ChangeSlide(Option<usize>),
 }
 
 pub struct ControlPlaneTx {
@@ -110,6 +121,11 @@ pub struct EditorActor {
     webview_sender: broadcast::Sender<WebviewActorRequest>,
 
     span_interner: SpanInterner,
+
+    /// The current slide, starting from 1.
+    slide: usize,
+    /// The speaker notes of each slide.
+    notes: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -129,6 +145,18 @@ pub enum ControlPlaneMessage {
     UpdateMemoryFiles(MemoryFiles),
     #[serde(rename = "removeMemoryFiles")]
     RemoveMemoryFiles(MemoryFilesShort),
+    #[serde(rename = "changeSlide")]
+    ChangeSlide(ChangeSlideRequest),
+}
+
+impl ControlPlaneMessage {
+    /// Whether the message changes the files compiled by the previewer.
+    pub fn changes_files(&self) -> bool {
+        matches!(
+            self,
+            Self::SyncMemoryFiles(..) | Self::UpdateMemoryFiles(..) | Self::RemoveMemoryFiles(..)
+        )
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -142,6 +170,8 @@ pub enum ControlPlaneResponse {
     CompileStatus(CompileStatus),
     #[serde(rename = "outline")]
     Outline(Outline),
+    #[serde(rename = "slideState")]
+    SlideState(SlideState),
 }
 
 impl EditorActor {
@@ -159,6 +189,9 @@ impl EditorActor {
             webview_sender,
 
             span_interner,
+
+            slide: 1,
+            notes: vec![],
         }
     }
 
@@ -191,6 +224,16 @@ impl EditorActor {
                         EditorActorRequest::Outline(outline) => {
                             self.editor_conn.resp_ctl_plane("Outline", ControlPlaneResponse::Outline(outline)).await
                         }
+                        EditorActorRequest::SpeakerNotes(notes) => {
+                            if self.notes == notes {
+                                continue;
+                            }
+                            self.notes = notes;
+                            self.change_slide(self.slide).await
+                        }
+                        EditorActorRequest::ChangeSlide(page) => {
+                            self.change_slide(page.unwrap_or(self.slide)).await
+                        }
                     };
 
                     if !sent {
@@ -229,6 +272,10 @@ impl EditorActor {
                             debug!("EditorActor: received message from editor: RemoveMemoryFiles {:?}", &memory_files.files);
                             self.world_sender.send(TypstActorRequest::RemoveMemoryFiles(memory_files)).unwrap();
                         }
+                        ControlPlaneMessage::ChangeSlide(req) => {
+                            debug!("EditorActor: received message from editor: {:?}", req);
+                            self.change_slide(req.page).await;
+                        }
                     };
                 }
             }
@@ -242,6 +289,20 @@ impl EditorActor {
         }
     }
 
+    /// Change the current slide and send the state to the editor and the
+    /// webviews, where the audience follows and the presenter shows the notes.
+    async fn change_slide(&mut self, page: usize) -> bool {
+        let state = SlideState::new(page, &self.notes);
+        self.slide = state.current;
+
+        let _ = self
+            .webview_sender
+            .send(WebviewActorRequest::SlideState(state.clone()));
+        self.editor_conn
+            .resp_ctl_plane("SlideState", ControlPlaneResponse::SlideState(state))
+            .await
+    }
+
     async fn source_scroll_by_span(&mut self, span: String) {
         let jump_info = {
             match self.span_interner.span_by_str(&span).await {
@@ -206,6 +206,11 @@ impl OutlineRenderActor {
                 info!("OutlineRenderActor: outline_sender is dropped");
                 break;
             };
+            let notes = crate::presenter::speaker_notes(&document);
+            let Ok(_) = self.editor_tx.send(EditorActorRequest::SpeakerNotes(notes)) else {
+                info!("OutlineRenderActor: speaker_notes_sender is dropped");
+                break;
+            };
         }
         info!("OutlineRenderActor: exiting")
     }
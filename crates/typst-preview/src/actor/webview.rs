@@ -6,6 +6,7 @@ use tokio::sync::{broadcast, mpsc};
 
 use crate::{
     actor::{editor::DocToSrcJumpResolveRequest, render::ResolveSpanRequest},
+    presenter::SlideState,
     Message, WsError,
 };
 
@@ -20,6 +21,7 @@ pub enum WebviewActorRequest {
     SrcToDocJump(Vec<SrcToDocJumpInfo>),
     // CursorPosition(CursorPosition),
     CursorPaths(Vec<Vec<ElementPoint>>),
+    SlideState(SlideState),
 }
 
 fn position_req(
@@ -112,6 +114,12 @@ impl<
                             self.webview_websocket_conn.send(Message::Binary(msg.into_bytes()))
                             .await.unwrap();
                         }
+                        WebviewActorRequest::SlideState(state) => {
+                            let json = serde_json::to_string(&state).unwrap();
+                            let msg = format!("slide-state,{json}");
+                            self.webview_websocket_conn.send(Message::Binary(msg.into_bytes()))
+                            .await.unwrap();
+                        }
                     }
                 }
                 Some(svg) = self.svg_receiver.recv() => {
@@ -149,6 +157,13 @@ impl<
                         let pos = DocumentPosition { page_no, x, y };
 
                         self.broadcast_sender.send(WebviewActorRequest::ViewportPosition(pos)).unwrap();
+                    } else if msg == "slide-state" {
+                        self.editor_sender.send(EditorActorRequest::ChangeSlide(None)).unwrap();
+                    } else if msg.starts_with("slide,") {
+                        let page = msg.split(',').nth(1).and_then(|s| s.trim().parse().ok());
+                        if let Some(page) = page {
+                            self.editor_sender.send(EditorActorRequest::ChangeSlide(Some(page))).unwrap();
+                        }
                     } else if msg.starts_with("srcpath") {
                         let path = msg.split(' ').nth(1).unwrap();
                         let path = serde_json::from_str(path);
@@ -0,0 +1,516 @@
+//! Compares the previewed document against a baseline document, e.g. a git
+//! revision, a file snapshot or the last export.
+//!
+//! Pages are compared by the hashes of the visual data of their frames, which
+//! exclude the spans and the introspection tags. For a changed page, the
+//! leaf items of both frames are placed at their absolute positions, and the
+//! items that only appear on one side are merged into the changed regions.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+use reflexo_typst::TypstDocument;
+use serde::Serialize;
+use typst::layout::{Abs, Frame, FrameItem, Point, Size};
+use typst::utils::hash128;
+
+use crate::site::{escape_html, render_pages};
+
+/// How a page changed against the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PageChange {
+    /// The page is rendered the same.
+    Unchanged,
+    /// The page is rendered differently.
+    Changed,
+    /// The page does not exist in the baseline.
+    Added,
+    /// The page only exists in the baseline.
+    Removed,
+}
+
+/// A changed region in a page, in points.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Region {
+    fn new(pos: Point, size: Size) -> Self {
+        Self {
+            x: pos.x.to_pt(),
+            y: pos.y.to_pt(),
+            width: size.x.to_pt().max(0.),
+            height: size.y.to_pt().max(0.),
+        }
+    }
+
+    /// Whether the regions overlap when both are expanded by the margin.
+    fn touches(&self, other: &Region, margin: f64) -> bool {
+        self.x - margin <= other.x + other.width
+            && other.x - margin <= self.x + self.width
+            && self.y - margin <= other.y + other.height
+            && other.y - margin <= self.y + self.height
+    }
+
+    fn union(&self, other: &Region) -> Region {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Region {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+/// The difference of a page against the baseline.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageDiff {
+    /// The page number, starting from 1.
+    pub page: usize,
+    pub change: PageChange,
+    /// The changed regions of the page.
+    pub regions: Vec<Region>,
+}
+
+/// The difference of a document against the baseline.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiff {
+    pub pages: Vec<PageDiff>,
+}
+
+impl DocumentDiff {
+    /// The numbers of the pages that are not rendered the same.
+    pub fn changed_pages(&self) -> Vec<usize> {
+        self.pages
+            .iter()
+            .filter(|page| page.change != PageChange::Unchanged)
+            .map(|page| page.page)
+            .collect()
+    }
+}
+
+/// Compare the pages of the document against the baseline.
+pub fn diff_documents(baseline: &TypstDocument, current: &TypstDocument) -> DocumentDiff {
+    let len = baseline.pages.len().max(current.pages.len());
+    let pages = (0..len).map(|idx| {
+        let whole = |frame: &Frame| vec![Region::new(Point::zero(), frame.size())];
+        let (change, regions) = match (baseline.pages.get(idx), current.pages.get(idx)) {
+            (Some(old), Some(new)) if visual_hash(&old.frame) == visual_hash(&new.frame) => {
+                (PageChange::Unchanged, vec![])
+            }
+            (Some(old), Some(new)) => {
+                let mut regions = diff_frames(&old.frame, &new.frame);
+                // The page is changed without any changed item, e.g. resized.
+                if regions.is_empty() {
+                    regions = whole(&new.frame);
+                }
+                (PageChange::Changed, regions)
+            }
+            (None, Some(new)) => (PageChange::Added, whole(&new.frame)),
+            (Some(old), None) => (PageChange::Removed, whole(&old.frame)),
+            (None, None) => unreachable!(),
+        };
+        PageDiff {
+            page: idx + 1,
+            change,
+            regions,
+        }
+    });
+
+    DocumentDiff {
+        pages: pages.collect(),
+    }
+}
+
+/// The margin in points to merge the changed items into one region.
+const MERGE_MARGIN: f64 = 4.;
+
+/// Compute the regions covering the items that only appear in one of the
+/// frames.
+fn diff_frames(old: &Frame, new: &Frame) -> Vec<Region> {
+    let mut old_leaves = vec![];
+    leaves(old, Point::zero(), &mut old_leaves);
+    let mut new_leaves = vec![];
+    leaves(new, Point::zero(), &mut new_leaves);
+
+    let mut counts = HashMap::<_, isize>::new();
+    for leaf in &old_leaves {
+        *counts.entry(leaf.key).or_default() += 1;
+    }
+    for leaf in &new_leaves {
+        *counts.entry(leaf.key).or_default() -= 1;
+    }
+
+    // Items of the baseline without a counterpart are removed, and vice
+    // versa.
+    let mut changed = vec![];
+    let mut take = |leaves: &[Leaf], removed: bool| {
+        for leaf in leaves {
+            let count = counts.get_mut(&leaf.key).unwrap();
+            if (removed && *count > 0) || (!removed && *count < 0) {
+                *count += if removed { -1 } else { 1 };
+                changed.push(leaf.region);
+            }
+        }
+    };
+    take(&old_leaves, true);
+    take(&new_leaves, false);
+
+    merge_regions(changed)
+}
+
+/// A leaf item placed in a page.
+struct Leaf {
+    /// The item and its position, which identifies the same item on both
+    /// sides.
+    key: (u128, i64, i64),
+    region: Region,
+}
+
+/// Collect the leaf items of the frame at their absolute positions.
+fn leaves(frame: &Frame, offset: Point, out: &mut Vec<Leaf>) {
+    for (pos, item) in frame.items() {
+        let pos = offset + *pos;
+        let region = match item {
+            // A transformed group is compared as a whole.
+            FrameItem::Group(group) if group.transform.is_identity() => {
+                leaves(&group.frame, pos, out);
+                continue;
+            }
+            FrameItem::Group(group) => Region::new(pos, group.frame.size()),
+            FrameItem::Text(text) => {
+                // The position is at the baseline of the text.
+                let top = Point::new(pos.x, pos.y - text.size);
+                Region::new(top, Size::new(text.width(), text.size * 1.2))
+            }
+            FrameItem::Shape(shape, _) => Region::new(pos, shape.geometry.bbox_size()),
+            FrameItem::Image(_, size, _) => Region::new(pos, *size),
+            FrameItem::Link(..) | FrameItem::Tag(..) => continue,
+        };
+
+        let coord = |value: Abs| (value.to_pt() * 100.).round() as i64;
+        out.push(Leaf {
+            key: (hash128(&Visual(item)), coord(pos.x), coord(pos.y)),
+            region,
+        });
+    }
+}
+
+/// Hash the visual data of the frame.
+fn visual_hash(frame: &Frame) -> u128 {
+    hash128(&Visual(frame))
+}
+
+/// Hashes the visual data of a frame or an item. The spans and the tags are
+/// skipped, which change with the source without changing the rendering, e.g.
+/// when the spans are renumbered or the baseline is compiled from elsewhere.
+struct Visual<T>(T);
+
+impl Hash for Visual<&Frame> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.size().hash(state);
+        for (pos, item) in self.0.items() {
+            if matches!(item, FrameItem::Link(..) | FrameItem::Tag(..)) {
+                continue;
+            }
+            pos.hash(state);
+            Visual(item).hash(state);
+        }
+    }
+}
+
+impl Hash for Visual<&FrameItem> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self.0).hash(state);
+        match self.0 {
+            FrameItem::Group(group) => {
+                group.transform.hash(state);
+                group.clip_path.hash(state);
+                Visual(&group.frame).hash(state);
+            }
+            FrameItem::Text(text) => {
+                text.font.hash(state);
+                text.size.hash(state);
+                text.fill.hash(state);
+                text.stroke.hash(state);
+                for glyph in &text.glyphs {
+                    glyph.id.hash(state);
+                    glyph.x_advance.hash(state);
+                    glyph.x_offset.hash(state);
+                }
+            }
+            FrameItem::Shape(shape, _) => shape.hash(state),
+            FrameItem::Image(image, size, _) => {
+                image.hash(state);
+                size.hash(state);
+            }
+            FrameItem::Link(..) | FrameItem::Tag(..) => {}
+        }
+    }
+}
+
+/// Merge the nearby regions until they are separated.
+fn merge_regions(mut regions: Vec<Region>) -> Vec<Region> {
+    let mut merged: Vec<Region> = vec![];
+    while let Some(mut region) = regions.pop() {
+        let mut idx = 0;
+        while idx < merged.len() {
+            if merged[idx].touches(&region, MERGE_MARGIN) {
+                region = region.union(&merged.swap_remove(idx));
+                idx = 0;
+            } else {
+                idx += 1;
+            }
+        }
+        merged.push(region);
+    }
+
+    merged.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+    merged
+}
+
+/// Holds the baseline of the visual diff, which is compared against the
+/// latest previewed document.
+#[derive(Clone)]
+pub struct DiffHandle {
+    pub(crate) document: Arc<RwLock<Option<Arc<TypstDocument>>>>,
+    pub(crate) baseline: Arc<RwLock<Option<Arc<TypstDocument>>>>,
+}
+
+impl DiffHandle {
+    /// Set the baseline document, or clear it by `None`.
+    pub fn set_baseline(&self, baseline: Option<Arc<TypstDocument>>) {
+        *self.baseline.write().unwrap() = baseline;
+    }
+
+    /// Take the latest previewed document as the baseline. Returns `false` if
+    /// the document is not ready.
+    pub fn snapshot(&self) -> bool {
+        let document = self.document.read().unwrap().clone();
+        let ready = document.is_some();
+        if ready {
+            self.set_baseline(document);
+        }
+        ready
+    }
+
+    /// Compare the latest previewed document against the baseline.
+    pub fn diff(&self) -> Option<DocumentDiff> {
+        let (baseline, current) = self.documents()?;
+        Some(diff_documents(&baseline, &current))
+    }
+
+    /// Render the visual diff to a html page, with the side-by-side and the
+    /// overlay views.
+    pub fn render(&self) -> String {
+        let Some((baseline, current)) = self.documents() else {
+            return diff_html(
+                "<p>No baseline or document is ready for comparison.</p>",
+                "",
+            );
+        };
+
+        let diff = diff_documents(&baseline, &current);
+        let baseline_pages = render_pages(&baseline);
+        let current_pages = render_pages(&current);
+
+        let changed = diff.changed_pages().len();
+        let summary = format!(
+            "{changed} of {} pages changed against the baseline.",
+            diff.pages.len()
+        );
+
+        let mut pages = String::new();
+        for page in &diff.pages {
+            let idx = page.page - 1;
+            let (label, class) = match page.change {
+                PageChange::Unchanged => ("unchanged", "unchanged"),
+                PageChange::Changed => ("changed", "changed"),
+                PageChange::Added => ("added", "added"),
+                PageChange::Removed => ("removed", "removed"),
+            };
+            let _ = writeln!(
+                pages,
+                r#"<section class="diff-page {class}"><h2>Page {} ({label})</h2><div class="views">"#,
+                page.page
+            );
+            let sides = [
+                ("baseline", baseline.pages.get(idx), baseline_pages.get(idx)),
+                ("current", current.pages.get(idx), current_pages.get(idx)),
+            ];
+            for (side, frame, svg) in sides {
+                let (Some(frame), Some(svg)) = (frame, svg) else {
+                    let _ = writeln!(pages, r#"<div class="frame {side} missing"></div>"#);
+                    continue;
+                };
+                let size = frame.frame.size();
+                let (width, height) = (size.x.to_pt(), size.y.to_pt());
+                let _ = writeln!(
+                    pages,
+                    r#"<div class="frame {side}" style="aspect-ratio: {width:.3} / {height:.3}">{svg}"#
+                );
+                let percent = |value: f64, total: f64| value / total.max(1.) * 100.;
+                for region in &page.regions {
+                    let left = percent(region.x, width);
+                    let top = percent(region.y, height);
+                    let w = percent(region.width, width);
+                    let h = percent(region.height, height);
+                    let _ = writeln!(
+                        pages,
+                        r#"<div class="region" style="left: {left:.3}%; top: {top:.3}%; width: {w:.3}%; height: {h:.3}%"></div>"#
+                    );
+                }
+                pages.push_str("</div>\n");
+            }
+            pages.push_str("</div></section>\n");
+        }
+
+        diff_html(&escape_html(&summary), &pages)
+    }
+
+    fn documents(&self) -> Option<(Arc<TypstDocument>, Arc<TypstDocument>)> {
+        let baseline = self.baseline.read().unwrap().clone()?;
+        let current = self.document.read().unwrap().clone()?;
+        Some((baseline, current))
+    }
+}
+
+const STYLE: &str = r#"
+body { margin: 0; background: #525659; color: #fff; font-family: sans-serif; }
+header { position: sticky; top: 0; z-index: 1; display: flex; gap: 1rem; align-items: center; padding: 0.5rem 1rem; background: #333; }
+header p { flex: 1; margin: 0; }
+main { display: flex; flex-direction: column; gap: 1rem; padding: 1rem; }
+h2 { margin: 0 0 0.5rem; font-size: 1rem; font-weight: normal; }
+.diff-page.unchanged { display: none; }
+body.show-unchanged .diff-page.unchanged { display: block; }
+.views { display: grid; grid-template-columns: 1fr 1fr; gap: 1rem; align-items: start; }
+.frame { position: relative; background: #fff; box-shadow: 0 0 0.5rem rgba(0, 0, 0, 0.5); }
+.frame svg { display: block; width: 100%; height: 100%; }
+.frame.missing { visibility: hidden; }
+.region { position: absolute; box-sizing: border-box; border: 2px solid #e5484d; background: rgba(229, 72, 77, 0.15); pointer-events: none; }
+body.overlay .views { grid-template-columns: 1fr; max-width: 60rem; }
+body.overlay .frame { grid-area: 1 / 1; }
+body.overlay .frame.baseline { z-index: 1; opacity: var(--baseline-opacity, 0.5); }
+body.overlay .frame.current .region { display: none; }
+"#;
+
+const SCRIPT: &str = r#"
+const body = document.body;
+document.getElementById("view").addEventListener("change", (e) => {
+  body.classList.toggle("overlay", e.target.value === "overlay");
+});
+document.getElementById("opacity").addEventListener("input", (e) => {
+  body.style.setProperty("--baseline-opacity", e.target.value / 100);
+});
+document.getElementById("unchanged").addEventListener("change", (e) => {
+  body.classList.toggle("show-unchanged", e.target.checked);
+});
+"#;
+
+fn diff_html(summary: &str, pages: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Visual Diff</title>
+<style>{STYLE}</style>
+</head>
+<body>
+<header>
+<p>{summary}</p>
+<select id="view" title="View">
+<option value="side-by-side">Side by side</option>
+<option value="overlay">Overlay</option>
+</select>
+<label>Baseline opacity <input id="opacity" type="range" min="0" max="100" value="50"></label>
+<label><input id="unchanged" type="checkbox"> Show unchanged pages</label>
+<a href="" style="color: inherit">Refresh</a>
+</header>
+<main>
+{pages}</main>
+<script>{SCRIPT}</script>
+</body>
+</html>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::foundations::{Content, Smart};
+    use typst::introspection::Tag;
+    use typst::layout::{Geometry, Page};
+    use typst::model::Document;
+    use typst::syntax::{FileId, Span, VirtualPath};
+    use typst::text::TextElem;
+    use typst::visualize::Color;
+
+    use super::*;
+
+    fn document(color: Color, span: Span, tagged: bool) -> TypstDocument {
+        let size = Size::new(Abs::pt(20.), Abs::pt(10.));
+        let mut frame = Frame::soft(Size::new(Abs::pt(100.), Abs::pt(100.)));
+        if tagged {
+            frame.push(
+                Point::zero(),
+                FrameItem::Tag(Tag::Start(TextElem::packed("a"))),
+            );
+        }
+        let shape = Geometry::Rect(size).filled(color);
+        frame.push(
+            Point::new(Abs::pt(10.), Abs::pt(10.)),
+            FrameItem::Shape(shape, span),
+        );
+
+        Document {
+            pages: vec![Page {
+                frame,
+                fill: Smart::Auto,
+                numbering: None,
+                supplement: Content::empty(),
+                number: 1,
+            }],
+            ..Document::default()
+        }
+    }
+
+    #[test]
+    fn spans_and_tags() {
+        let id = FileId::new(None, VirtualPath::new("main.typ"));
+        let baseline = document(Color::BLACK, Span::detached(), false);
+        let current = document(Color::BLACK, Span::from_range(id, 0..1), true);
+
+        let diff = diff_documents(&baseline, &current);
+        assert_eq!(diff.pages[0].change, PageChange::Unchanged);
+        assert!(diff.changed_pages().is_empty());
+    }
+
+    #[test]
+    fn changed_shape() {
+        let baseline = document(Color::BLACK, Span::detached(), false);
+        let current = document(Color::WHITE, Span::detached(), false);
+
+        let diff = diff_documents(&baseline, &current);
+        assert_eq!(diff.pages[0].change, PageChange::Changed);
+        let region = Region {
+            x: 10.,
+            y: 10.,
+            width: 20.,
+            height: 10.,
+        };
+        assert_eq!(diff.pages[0].regions, vec![region]);
+    }
+}
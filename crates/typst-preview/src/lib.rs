@@ -1,13 +1,19 @@
 mod actor;
 mod args;
 mod debug_loc;
+mod diff;
 mod outline;
+mod presenter;
+mod site;
 
 pub use actor::editor::{
     CompileStatus, ControlPlaneMessage, ControlPlaneResponse, ControlPlaneRx, ControlPlaneTx,
 };
 pub use args::*;
+pub use diff::{diff_documents, DiffHandle, DocumentDiff, PageChange, PageDiff, Region};
 pub use outline::Outline;
+pub use presenter::SlideState;
+pub use site::export_site;
 
 use std::{collections::HashMap, future::Future, path::PathBuf, pin::Pin, sync::Arc};
 
@@ -60,9 +66,24 @@ pub struct Previewer {
     data_plane_handle: Option<tokio::task::JoinHandle<()>>,
     data_plane_resources: Option<(DataPlane, Option<mpsc::Sender<()>>, mpsc::Receiver<()>)>,
     control_plane_handle: tokio::task::JoinHandle<()>,
+    document: Arc<std::sync::RwLock<Option<Arc<Document>>>>,
+    diff_baseline: Arc<std::sync::RwLock<Option<Arc<Document>>>>,
 }
 
 impl Previewer {
+    /// Get the latest previewed document.
+    pub fn document(&self) -> Option<Arc<Document>> {
+        self.document.read().unwrap().clone()
+    }
+
+    /// Get the handle to compare the previewed document against a baseline.
+    pub fn diff_handle(&self) -> DiffHandle {
+        DiffHandle {
+            document: self.document.clone(),
+            baseline: self.diff_baseline.clone(),
+        }
+    }
+
     /// Send stop requests to preview actors.
     pub async fn stop(&mut self) {
         if let Some(stop) = self.stop.take() {
@@ -283,11 +304,13 @@ impl PreviewBuilder {
             invert_colors: arguments.invert_colors.clone(),
             renderer_tx: renderer_mailbox.0.clone(),
             enable_partial_rendering: arguments.enable_partial_rendering,
-            doc_sender,
+            doc_sender: doc_sender.clone(),
         };
 
         Previewer {
             control_plane_handle,
+            document: doc_sender,
+            diff_baseline: Default::default(),
             data_plane_handle: None,
             data_plane_resources: Some((data_plane, shutdown_tx, shutdown_data_plane_rx)),
             stop: Some(Box::new(move || {
@@ -13,12 +13,12 @@ use crate::debug_loc::SpanInternerImpl;
 /// A heading in the outline panel.
 #[derive(Debug, Clone)]
 pub(crate) struct HeadingNode {
-    body: Content,
+    pub(crate) body: Content,
     span: Span,
-    position: DocumentPosition,
+    pub(crate) position: DocumentPosition,
     level: NonZeroUsize,
     bookmarked: bool,
-    children: Vec<HeadingNode>,
+    pub(crate) children: Vec<HeadingNode>,
 }
 
 /// Construct the outline for the document.
@@ -0,0 +1,139 @@
+//! Speaker notes and slide state for the presenter mode.
+//!
+//! The speaker notes are extracted from the `metadata` elements in the
+//! document:
+//! - labelled `<pdfpc-note>`, whose value is the note.
+//! - labelled `<pdfpc>`, whose value is a `(t: "Notes", v: ..)` dictionary,
+//!   which is emitted by the `pdfpc` helpers of polylux and touying.
+
+use reflexo_typst::TypstDocument;
+use serde::{Deserialize, Serialize};
+use typst::foundations::{Label, Selector, Value};
+use typst::introspection::MetadataElem;
+
+/// The state of the slides shown to the audience, which is sent to the
+/// presenter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlideState {
+    /// The current slide, starting from 1.
+    pub current: usize,
+    /// The next slide, if any.
+    pub next: Option<usize>,
+    /// The number of slides.
+    pub total: usize,
+    /// The speaker notes of the current slide.
+    pub notes: String,
+}
+
+impl SlideState {
+    /// Create the state of the slide, which is clamped to the slides.
+    pub(crate) fn new(current: usize, notes: &[String]) -> Self {
+        let total = notes.len();
+        let current = current.clamp(1, total.max(1));
+        Self {
+            current,
+            next: (current < total).then_some(current + 1),
+            total,
+            notes: notes.get(current - 1).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// Extract the speaker notes of each page of the document.
+pub(crate) fn speaker_notes(document: &TypstDocument) -> Vec<String> {
+    let mut notes = vec![String::new(); document.pages.len()];
+
+    let introspector = &document.introspector;
+    for label in ["pdfpc-note", "pdfpc"] {
+        let elements = introspector.query(&Selector::Label(Label::new(label)));
+        for elem in elements.iter() {
+            let Some(note) = elem.to_packed::<MetadataElem>() else {
+                continue;
+            };
+            let Some(text) = note_text(&note.value, label == "pdfpc") else {
+                continue;
+            };
+            let Some(loc) = elem.location() else {
+                continue;
+            };
+
+            let page = introspector.page(loc).get();
+            if let Some(notes) = notes.get_mut(page - 1) {
+                if !notes.is_empty() {
+                    notes.push_str("\n\n");
+                }
+                notes.push_str(text.trim());
+            }
+        }
+    }
+
+    notes
+}
+
+/// Get the text of a note, which is possibly wrapped in a pdfpc dictionary.
+fn note_text(value: &Value, pdfpc: bool) -> Option<String> {
+    match value {
+        Value::Dict(dict) if pdfpc => {
+            let is_note = matches!(dict.get("t"), Ok(Value::Str(kind)) if kind.as_str() == "Notes");
+            is_note.then(|| note_text(dict.get("v").ok()?, false))?
+        }
+        _ if pdfpc => None,
+        Value::Str(text) => Some(text.to_string()),
+        Value::Content(content) => Some(content.plain_text().to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::foundations::dict;
+    use typst::text::TextElem;
+
+    use super::*;
+
+    #[test]
+    fn slide_state() {
+        let notes = ["first".to_owned(), "second".to_owned()];
+
+        let state = SlideState::new(1, &notes);
+        assert_eq!((state.current, state.next, state.total), (1, Some(2), 2));
+        assert_eq!(state.notes, "first");
+
+        // The last slide has no next one.
+        let state = SlideState::new(2, &notes);
+        assert_eq!((state.current, state.next), (2, None));
+        assert_eq!(state.notes, "second");
+
+        // The slide out of the document is clamped.
+        assert_eq!(SlideState::new(0, &notes).current, 1);
+        assert_eq!(SlideState::new(5, &notes), SlideState::new(2, &notes));
+
+        let state = SlideState::new(3, &[]);
+        assert_eq!((state.current, state.next, state.total), (1, None, 0));
+        assert_eq!(state.notes, "");
+    }
+
+    #[test]
+    fn plain_note() {
+        let text = Value::Str("Hello".into());
+        assert_eq!(note_text(&text, false).as_deref(), Some("Hello"));
+        let content = Value::Content(TextElem::packed("Hello"));
+        assert_eq!(note_text(&content, false).as_deref(), Some("Hello"));
+        assert_eq!(note_text(&Value::Int(1), false), None);
+    }
+
+    #[test]
+    fn pdfpc_note() {
+        let note = Value::Dict(dict! { "t" => "Notes", "v" => "Hello" });
+        assert_eq!(note_text(&note, true).as_deref(), Some("Hello"));
+        let content = Value::Dict(dict! { "t" => "Notes", "v" => TextElem::packed("Hello") });
+        assert_eq!(note_text(&content, true).as_deref(), Some("Hello"));
+
+        // Other pdfpc items, e.g. the durations, are not notes.
+        let duration = Value::Dict(dict! { "t" => "Duration", "v" => "5:00" });
+        assert_eq!(note_text(&duration, true), None);
+        // A pdfpc note must be a dictionary.
+        assert_eq!(note_text(&Value::Str("Hello".into()), true), None);
+    }
+}
@@ -0,0 +1,356 @@
+//! Exports a document to a static site, which looks like the preview but is
+//! viewed without a preview server.
+//!
+//! The site is a directory containing an `index.html` and the pages rendered
+//! to `pages/<page>.svg`. The outline of the document is rendered as the
+//! navigation, and the links in the document jump to their targets.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
+
+use reflexo_typst::TypstDocument;
+use reflexo_vec2svg::{ExportFeature, SvgExporter, SvgText};
+use typst::layout::{Frame, FrameItem, Point, Position, Size, Transform};
+use typst::model::Destination;
+
+use crate::outline::{get_outline, HeadingNode};
+
+struct SiteExportFeature;
+
+impl ExportFeature for SiteExportFeature {
+    const ENABLE_INLINED_SVG: bool = false;
+    const ENABLE_TRACING: bool = false;
+    const SHOULD_ATTACH_DEBUG_INFO: bool = false;
+    const SHOULD_RENDER_TEXT_ELEMENT: bool = false;
+    const USE_STABLE_GLYPH_ID: bool = true;
+    const SHOULD_RASTERIZE_TEXT: bool = false;
+    const WITH_BUILTIN_CSS: bool = true;
+    const WITH_RESPONSIVE_JS: bool = false;
+    const AWARE_HTML_ENTITY: bool = false;
+}
+
+const STYLE: &str = r#"
+body { margin: 0; display: flex; background: #525659; font-family: sans-serif; }
+nav { position: sticky; top: 0; flex: 0 0 16rem; height: 100vh; overflow-y: auto; background: #fff; font-size: 0.9rem; }
+nav ul { list-style: none; margin: 0; padding-left: 1rem; }
+nav li { margin: 0.3rem 0; }
+nav a { color: #333; text-decoration: none; }
+nav a:hover { text-decoration: underline; }
+main { flex: 1; display: flex; flex-direction: column; align-items: center; gap: 1rem; padding: 1rem; }
+.page { position: relative; width: min(100%, 60rem); background: #fff; box-shadow: 0 0 0.5rem rgba(0, 0, 0, 0.5); }
+.page img { display: block; width: 100%; height: 100%; }
+.page a { position: absolute; }
+.page span { position: absolute; left: 0; }
+"#;
+
+/// Export the document to a static site in the directory.
+pub fn export_site(document: &TypstDocument, dir: &Path) -> std::io::Result<()> {
+    let pages_dir = dir.join("pages");
+    std::fs::create_dir_all(&pages_dir)?;
+
+    for (idx, svg) in render_pages(document).into_iter().enumerate() {
+        let path = pages_dir.join(format!("{}.svg", idx + 1));
+        std::fs::write(path, svg)?;
+    }
+
+    let mut site = Site {
+        document,
+        links: vec![vec![]; document.pages.len()],
+        anchors: vec![BTreeSet::new(); document.pages.len()],
+    };
+    for (idx, page) in document.pages.iter().enumerate() {
+        site.collect_links(idx, &page.frame, Transform::identity());
+    }
+    let outline = get_outline(&document.introspector).unwrap_or_default();
+    let mut nav = String::new();
+    site.nav(&outline, &mut nav);
+
+    std::fs::write(dir.join("index.html"), site.html(&nav))
+}
+
+/// Render the pages of the document to standalone SVGs in the same way as the
+/// preview.
+pub(crate) fn render_pages(document: &TypstDocument) -> Vec<String> {
+    type UsingExporter = SvgExporter<SiteExportFeature>;
+    let mut svg_doc = UsingExporter::svg_doc(document);
+    svg_doc.module.prepare_glyphs();
+    svg_doc
+        .pages
+        .iter()
+        .map(|page| {
+            let svg = UsingExporter::render(&svg_doc.module, &[page.clone()], None);
+            SvgText::join(svg)
+        })
+        .collect()
+}
+
+/// A link in a page.
+#[derive(Clone)]
+struct Link {
+    /// The top-left corner of the link.
+    pos: Point,
+    /// The size of the link.
+    size: Size,
+    /// The destination of the link.
+    href: String,
+}
+
+struct Site<'a> {
+    document: &'a TypstDocument,
+    /// The links in each page.
+    links: Vec<Vec<Link>>,
+    /// The anchors of the jump targets in each page, by their vertical
+    /// positions in points.
+    anchors: Vec<BTreeSet<i64>>,
+}
+
+impl Site<'_> {
+    /// Collect the links in a frame, which is placed in the page by the
+    /// transform.
+    fn collect_links(&mut self, page: usize, frame: &Frame, ts: Transform) {
+        for (pos, item) in frame.items() {
+            let ts = ts.pre_concat(Transform::translate(pos.x, pos.y));
+            match item {
+                FrameItem::Group(group) => {
+                    self.collect_links(page, &group.frame, ts.pre_concat(group.transform));
+                }
+                FrameItem::Link(dest, size) => {
+                    let href = match dest {
+                        Destination::Url(url) => url.as_str().to_owned(),
+                        Destination::Position(target) => format!("#{}", self.anchor(*target)),
+                        Destination::Location(loc) => {
+                            let target = self.document.introspector.position(*loc);
+                            format!("#{}", self.anchor(target))
+                        }
+                    };
+                    // A transformed link is covered by its bounding box.
+                    let corners = [
+                        Point::zero(),
+                        Point::with_x(size.x),
+                        Point::with_y(size.y),
+                        size.to_point(),
+                    ]
+                    .map(|corner| corner.transform(ts));
+                    let min = corners.into_iter().reduce(Point::min).unwrap_or_default();
+                    let max = corners.into_iter().reduce(Point::max).unwrap_or_default();
+                    let (pos, size) = (min, (max - min).to_size());
+                    self.links[page].push(Link { pos, size, href });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Place an anchor at the position and get its id.
+    fn anchor(&mut self, pos: Position) -> String {
+        let y = pos.point.y.to_pt().round() as i64;
+        if let Some(anchors) = self.anchors.get_mut(pos.page.get() - 1) {
+            anchors.insert(y);
+        }
+        anchor_id(pos.page.get(), y)
+    }
+
+    /// Render the outline as nested lists.
+    fn nav(&mut self, headings: &[HeadingNode], s: &mut String) {
+        if headings.is_empty() {
+            return;
+        }
+
+        s.push_str("<ul>\n");
+        for heading in headings {
+            let position = heading.position;
+            let y = position.y.round() as i64;
+            if let Some(anchors) = self.anchors.get_mut(position.page_no.saturating_sub(1)) {
+                anchors.insert(y);
+            }
+
+            let id = anchor_id(position.page_no, y);
+            let title = escape_html(heading.body.plain_text().trim());
+            let _ = write!(s, r##"<li><a href="#{id}">{title}</a>"##);
+            self.nav(&heading.children, s);
+            s.push_str("</li>\n");
+        }
+        s.push_str("</ul>\n");
+    }
+
+    fn html(&self, nav: &str) -> String {
+        let title = self.document.info.title.as_deref().unwrap_or("Document");
+        let title = escape_html(title);
+
+        let mut pages = String::new();
+        for (idx, page) in self.document.pages.iter().enumerate() {
+            let page_no = idx + 1;
+            let size = page.frame.size();
+            let (width, height) = (size.x.to_pt(), size.y.to_pt());
+            let _ = writeln!(
+                pages,
+                r#"<div class="page" id="page-{page_no}" style="aspect-ratio: {width:.3} / {height:.3}">"#
+            );
+            let _ = writeln!(
+                pages,
+                r#"<img src="pages/{page_no}.svg" alt="Page {page_no}">"#
+            );
+
+            let percent = |value: f64, total: f64| value / total.max(1.) * 100.;
+            for y in &self.anchors[idx] {
+                let top = percent(*y as f64, height);
+                let id = anchor_id(page_no, *y);
+                let _ = writeln!(pages, r#"<span id="{id}" style="top: {top:.3}%"></span>"#);
+            }
+            for link in &self.links[idx] {
+                let left = percent(link.pos.x.to_pt(), width);
+                let top = percent(link.pos.y.to_pt(), height);
+                let w = percent(link.size.x.to_pt(), width);
+                let h = percent(link.size.y.to_pt(), height);
+                let href = escape_html(&link.href);
+                let _ = writeln!(
+                    pages,
+                    r#"<a href="{href}" style="left: {left:.3}%; top: {top:.3}%; width: {w:.3}%; height: {h:.3}%"></a>"#
+                );
+            }
+            pages.push_str("</div>\n");
+        }
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>{STYLE}</style>
+</head>
+<body>
+<nav>
+{nav}</nav>
+<main>
+{pages}</main>
+</body>
+</html>
+"#
+        )
+    }
+}
+
+fn anchor_id(page_no: usize, y: i64) -> String {
+    format!("p{page_no}-{y}")
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::foundations::{Content, Smart};
+    use typst::layout::{Abs, Angle, GroupItem, Page};
+    use typst::model::{Document, Url};
+
+    use super::*;
+
+    fn pt(x: f64, y: f64) -> Point {
+        Point::new(Abs::pt(x), Abs::pt(y))
+    }
+
+    fn url(href: &str) -> FrameItem {
+        let dest = Destination::Url(Url::new(href).unwrap());
+        FrameItem::Link(dest, Size::new(Abs::pt(20.), Abs::pt(10.)))
+    }
+
+    /// A page having a link in a group rotated by 90 degrees, and a link to a
+    /// position on the page.
+    fn document() -> Document {
+        let mut link = Frame::soft(Size::new(Abs::pt(20.), Abs::pt(10.)));
+        link.push(Point::zero(), url("https://typst.app/"));
+        let mut group = GroupItem::new(link);
+        group.transform = Transform::rotate(Angle::deg(90.));
+
+        let mut frame = Frame::soft(Size::new(Abs::pt(100.), Abs::pt(100.)));
+        frame.push(pt(50., 10.), FrameItem::Group(group));
+        let target = Position {
+            page: std::num::NonZeroUsize::MIN,
+            point: pt(0., 30.),
+        };
+        let size = Size::new(Abs::pt(20.), Abs::pt(10.));
+        frame.push(
+            pt(0., 80.),
+            FrameItem::Link(Destination::Position(target), size),
+        );
+
+        Document {
+            pages: vec![Page {
+                frame,
+                fill: Smart::Auto,
+                numbering: None,
+                supplement: Content::empty(),
+                number: 1,
+            }],
+            ..Document::default()
+        }
+    }
+
+    fn round(point: Point) -> (f64, f64) {
+        (point.x.to_pt().round(), point.y.to_pt().round())
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(
+            escape_html(r#"<a href="x">&</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+        assert_eq!(escape_html("plain text"), "plain text");
+    }
+
+    #[test]
+    fn links() {
+        let document = document();
+        let mut site = Site {
+            document: &document,
+            links: vec![vec![]],
+            anchors: vec![BTreeSet::new()],
+        };
+        site.collect_links(0, &document.pages[0].frame, Transform::identity());
+
+        let links = &site.links[0];
+        assert_eq!(links.len(), 2);
+        // The rotated link covers its rotated box.
+        assert_eq!(links[0].href, "https://typst.app/");
+        assert_eq!(round(links[0].pos), (40., 10.));
+        assert_eq!(round(links[0].size.to_point()), (10., 20.));
+        // The link to a position jumps to the anchor placed there.
+        assert_eq!(links[1].href, "#p1-30");
+        assert_eq!(round(links[1].pos), (0., 80.));
+        assert_eq!(site.anchors[0], BTreeSet::from([30]));
+    }
+
+    #[test]
+    fn export() {
+        let dir = std::env::temp_dir().join(format!("typst-preview-site-{}", std::process::id()));
+        export_site(&document(), &dir).unwrap();
+
+        assert!(dir.join("pages/1.svg").is_file());
+        let index = std::fs::read_to_string(dir.join("index.html")).unwrap();
+        assert!(index.contains("<title>Document</title>"));
+        assert!(index.contains(r#"<span id="p1-30" style="top: 30.000%"></span>"#));
+        assert!(index.contains(
+            r#"<a href="https://typst.app/" style="left: 40.000%; top: 10.000%; width: 10.000%; height: 20.000%"></a>"#
+        ));
+        assert!(index.contains(
+            r##"<a href="#p1-30" style="left: 0.000%; top: 80.000%; width: 20.000%; height: 10.000%"></a>"##
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -10,6 +10,35 @@ To start a language server following the #link("https://microsoft.github.io/lang
 tinymist lsp
 ```
 
+The server communicates over stdio by default. To share a long-running server with browser-based editors or remote containers, it can listen on a TCP socket or a WebSocket instead, and serves the connected clients one after another:
+
+```
+tinymist lsp --listen tcp://127.0.0.1:9257
+tinymist lsp --listen ws://127.0.0.1:9257
+```
+
+Over WebSocket, each text frame carries one JSON-RPC message without the `Content-Length` header.
+
+== Compiling Documents
+
+To compile a document and export it in the same way as the language server, you can use the following command:
+
+```
+tinymist compile path/to/main.typ --format pdf --output '$root/target/$dir/$name'
+```
+
+The `--format` can be `pdf`, `svg`, `png`, `html`, `markdown`, `text` or `query`. The `--output` is a path pattern, which is the same as the `tinymist.outputPath` configuration. Images export the first page by default, and `--pages merged` exports all pages into a single image. Pass `--watch` to export the document again whenever its dependencies change.
+
+== Checking Documents
+
+To check documents in continuous integration with the same diagnostics as the editor, you can use the following command:
+
+```
+tinymist check path/to/main.typ --format github
+```
+
+The `--format` can be `json`, `sarif` or `github`. If no input file is given, every entry declared in the `tinymist.toml` of the root is checked. The command exits with a non-zero code if any error is found, or any warning is found when `--deny-warnings` is passed.
+
 == Starting a Preview Server
 
 To start a preview server, you can use the following command:
@@ -19,3 +48,21 @@ tinymist preview path/to/main.typ
 ```
 
 See #link("https://enter-tainer.github.io/typst-preview/standalone.html")[Arguments].
+
+To preview all the documents in a directory from one server, pass `--serve-dir` instead of an input file:
+
+```
+tinymist preview --serve-dir path/to/workspace --data-plane-host 0.0.0.0:23625
+```
+
+The index page at `/` lists every `.typ` file in the directory, skipping hidden directories, and each document is previewed at `/doc/<path>`. The preview of a document is started on its first visit, and the documents share the fonts, the `--input` values and the package settings.
+
+=== Exposing a Preview Server
+
+The preview servers are bound to `127.0.0.1` without access control by default. To share a preview from a remote machine, require a token and serve the preview over TLS:
+
+```
+tinymist preview path/to/main.typ --data-plane-host 0.0.0.0:23625 --token "$TOKEN" --tls-cert cert.pem --tls-key key.pem
+```
+
+The token can also be passed by the `TINYMIST_PREVIEW_TOKEN` environment variable. Visitors open the preview with the token in the url, e.g. `https://dev-box:23625/?token=<TOKEN>`, which is then kept by a cookie for the websockets and the later visits. The requests without a valid token are rejected. The token is required by the control plane too, where editors connect with the `token` query in the url. Pass `--control-plane-read-only` to reject the requests changing the files from the control plane.
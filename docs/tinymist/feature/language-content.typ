@@ -24,6 +24,9 @@ Language service (LSP) features:
 - #link("https://code.visualstudio.com/api/language-extensions/programmatic-language-features#show-definitions-of-a-symbol")[Goto definitions]
   - Right-click on a symbol and select "Go to Definition".
   - Or ctrl+click on a symbol.
+- #link("https://code.visualstudio.com/api/language-extensions/programmatic-language-features#show-definitions-of-a-symbol")[Goto type definitions and implementations]
+  - "Go to Type Definition" jumps to the function or the dictionary producing the inferred type of a variable.
+  - "Go to Implementations" finds the `show` and `set` rules on an element function, e.g. `heading`.
 - #link("https://code.visualstudio.com/api/language-extensions/programmatic-language-features#find-all-references-to-a-symbol")[References]
   - Right-click on a symbol and select "Go to References" or "Find References".
   - Or ctrl+click on a symbol.
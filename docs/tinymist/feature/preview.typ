@@ -85,6 +85,69 @@ tinymist preview /abs-path/to/main.typ --partial-rendering
   You may seek #link("https://github.com/havarddj/typst-preview.el")[typst-preview.el] for the preview feature.
 ]
 
+=== Presenter Mode
+
+When previewing slides (`--preview-mode=slide`), press `p` in the preview to open a presenter window. The presenter window shows the speaker notes of the current slide, a timer, and the next slide, while the audience window follows the slide changed in the presenter window. Press `t` in the presenter window to reset the timer.
+
+The speaker notes are extracted from the `metadata` elements in the slides, either labelled `<pdfpc-note>`:
+
+```typ
+#metadata("Remember to introduce the team.") <pdfpc-note>
+```
+
+or in the format of pdfpc, which is used by the `speaker-note` functions of polylux and touying:
+
+```typ
+#metadata((t: "Notes", v: "Remember to introduce the team.")) <pdfpc>
+```
+
+The state of the slides is also sent to the editor by the `tinymist/preview/slideState` notification, and the editor can change the current slide by sending a `changeSlide` event with a `page` number on the control plane.
+
+=== Exporting a Static Site
+
+A running preview can be exported to a static site, which can be shared with the people who don't have tinymist installed. The site looks like the preview, with the outline of the document as the navigation and the links in the document jumping to their targets:
+
+```
+/abs-path/to/site/
+├── index.html
+└── pages/
+    ├── 1.svg
+    └── ...
+```
+
+The site is exported by the `tinymist.doExportPreviewSite` command, which takes the task id of the preview and an absolute path to the output directory:
+
+```json
+{
+  "command": "tinymist.doExportPreviewSite",
+  "arguments": ["<task-id>", "/abs-path/to/site"]
+}
+```
+
+=== Visual Diff
+
+The preview can compare the document against a baseline, and show the pages and the regions whose rendering changed at `/diff` of the preview server, e.g. `http://127.0.0.1:23635/diff`. The pages are shown side by side, or overlaid with an adjustable opacity of the baseline. The baseline is one of:
+
+- `snapshot`: the document previewed when the baseline is set.
+- `export`: the document last exported, e.g. to PDF.
+- `git:<rev>`: the document compiled from the files at the git revision, e.g. `git:HEAD`.
+- `file:<path>`: the document compiled with a snapshot of the main file, whose path is relative to the root.
+
+In CLI, the baseline is given by the `--diff-baseline` flag, where `snapshot` is the first compiled document:
+
+```bash
+tinymist preview --diff-baseline git:HEAD path/to/main.typ
+```
+
+In editors, the baseline is set by the `tinymist.doSetPreviewDiffBaseline` command, which responds with the numbers of the changed pages:
+
+```json
+{
+  "command": "tinymist.doSetPreviewDiffBaseline",
+  "arguments": ["<task-id>", { "kind": "git", "rev": "HEAD" }]
+}
+```
+
 === `sys.inputs`
 
 If the document is compiled by lsp, you can use `sys.inputs` to get the preview arguments:
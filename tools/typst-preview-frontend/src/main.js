@@ -6,6 +6,7 @@ import "./styles/toolbar.css";
 import "./styles/layout.css";
 import "./styles/help-panel.css";
 import "./styles/outline.css";
+import "./styles/presenter.css";
 
 import { wsMain, PreviewMode } from './ws';
 import { setupDrag } from './drag';
@@ -48,8 +49,13 @@ function retrieveWsArgs() {
         urlObject.protocol = urlObject.protocol.replace('ws:', 'wss:');
     }
 
+    /// The presenter window of the slides is opened with the `presenter`
+    /// query, which shows the speaker notes and the next slide.
+    const isPresenter = previewMode === PreviewMode.Slide &&
+        new URLSearchParams(window.location.search).has('presenter');
+
     /// Return a `WsArgs` object.
-    return { url: urlObject.href, previewMode, isContentPreview: false };
+    return { url: urlObject.href, previewMode, isContentPreview: false, isPresenter };
 }
 
 /// `buildWs` returns a object, which keeps track of websocket
@@ -72,7 +78,7 @@ function buildWs() {
 
     return { nextWs };
 
-    function resetAppMode({ previewMode: mode, isContentPreview }) {
+    function resetAppMode({ previewMode: mode, isContentPreview, isPresenter }) {
         const app = document.getElementById('typst-container');
 
         /// Set the root css selector to the content preview mode.
@@ -81,6 +87,12 @@ function buildWs() {
             app.classList.add('content-preview');
         }
 
+        /// Set the root css selector to the presenter mode.
+        app.classList.remove('mode-presenter');
+        if (isPresenter) {
+            app.classList.add('mode-presenter');
+        }
+
         /// Set the root css selector to the preview mode.
         app.classList.remove('mode-slide');
         app.classList.remove('mode-doc');
@@ -116,6 +128,7 @@ function setupVscodeChannel(nextWs) {
                     url: message.url,
                     previewMode: PreviewMode[message.mode],
                     isContentPreview: message.isContentPreview,
+                    isPresenter: false,
                 });
                 break;
             }
@@ -0,0 +1,75 @@
+/// The state of the slides sent by the server, see `SlideState` in
+/// `crates/typst-preview/src/presenter.rs`.
+export interface SlideState {
+    /// The current slide, starting from 1.
+    current: number;
+    /// The next slide, if any.
+    next: number | null;
+    /// The number of slides.
+    total: number;
+    /// The speaker notes of the current slide.
+    notes: string;
+}
+
+/// The panel in the presenter window, which shows the speaker notes and a
+/// timer, while the next slide is shown by the document.
+export class PresenterPanel {
+    private elem: HTMLElement;
+    private progressElem: HTMLElement;
+    private timerElem: HTMLElement;
+    private nextElem: HTMLElement;
+    private notesElem: HTMLElement;
+
+    private startTime = Date.now();
+    private timer: ReturnType<typeof setInterval>;
+
+    constructor() {
+        this.elem = document.createElement("div");
+        this.elem.id = "typst-presenter-panel";
+
+        const header = document.createElement("div");
+        header.className = "presenter-header";
+        this.progressElem = document.createElement("span");
+        this.timerElem = document.createElement("span");
+        this.timerElem.className = "presenter-timer";
+        this.timerElem.title = "Click or press t to reset the timer";
+        this.timerElem.addEventListener("click", () => this.resetTimer());
+        header.append(this.progressElem, this.timerElem);
+
+        this.nextElem = document.createElement("div");
+        this.nextElem.className = "presenter-next";
+
+        this.notesElem = document.createElement("div");
+        this.notesElem.className = "presenter-notes";
+
+        this.elem.append(header, this.nextElem, this.notesElem);
+        document.body.appendChild(this.elem);
+
+        this.timer = setInterval(() => this.tick(), 1000);
+        this.tick();
+    }
+
+    update(state: SlideState) {
+        this.progressElem.textContent = `Slide ${state.current} / ${state.total}`;
+        this.nextElem.textContent = state.next ? `Next: slide ${state.next}` : "End of presentation";
+        this.notesElem.textContent = state.notes || "No notes for this slide.";
+        this.notesElem.classList.toggle("empty", !state.notes);
+    }
+
+    resetTimer() {
+        this.startTime = Date.now();
+        this.tick();
+    }
+
+    dispose() {
+        clearInterval(this.timer);
+        this.elem.remove();
+    }
+
+    private tick() {
+        const elapsed = Math.floor((Date.now() - this.startTime) / 1000);
+        const pad = (n: number) => n.toString().padStart(2, "0");
+        const [hours, minutes, seconds] = [Math.floor(elapsed / 3600), Math.floor(elapsed / 60) % 60, elapsed % 60];
+        this.timerElem.textContent = `${pad(hours)}:${pad(minutes)}:${pad(seconds)}`;
+    }
+}
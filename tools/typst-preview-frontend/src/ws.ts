@@ -10,6 +10,7 @@ import renderModule from "@myriaddreamin/typst-ts-renderer/pkg/typst_ts_renderer
 import { RenderSession } from "@myriaddreamin/typst.ts/dist/esm/renderer.mjs";
 import { WebSocketSubject, webSocket } from 'rxjs/webSocket';
 import { Subject, Subscription, buffer, debounceTime, fromEvent, tap } from "rxjs";
+import { PresenterPanel, SlideState } from "./presenter";
 export { PreviewMode } from 'typst-dom/typst-doc.mjs';
 
 // for debug propose
@@ -25,9 +26,10 @@ export interface WsArgs {
     url: string;
     previewMode: PreviewMode;
     isContentPreview: boolean;
+    isPresenter: boolean;
 }
 
-export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
+export async function wsMain({ url, previewMode, isContentPreview, isPresenter }: WsArgs) {
     if (!url) {
         const hookedElem = document.getElementById("typst-app");
         if (hookedElem) {
@@ -40,6 +42,12 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
     let $ws: WebSocketSubject<ArrayBuffer> | undefined = undefined;
     const subsribes: Subscription[] = [];
 
+    /// The state of the slides, which is shared by the audience and the
+    /// presenter windows through the server.
+    let slideState: SlideState | undefined = undefined;
+    let presenterPanel: PresenterPanel | undefined = undefined;
+    const sendSlide = (page: number) => window.typstWebsocket?.send(`slide,${page}`);
+
     function createSvgDocument(kModule: RenderSession) {
         const hookedElem = document.getElementById("typst-app")!;
         if (hookedElem.firstElementChild?.tagName !== "svg") {
@@ -102,7 +110,11 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
                             return;
                         }
                         const page = Number.parseInt(inpPageSelector.value);
-                        svgDoc.setPartialPageNumber(page);
+                        if (isPresenter) {
+                            sendSlide(page);
+                        } else if (svgDoc.setPartialPageNumber(page)) {
+                            sendSlide(page);
+                        }
                     });
                 }
             }
@@ -122,6 +134,15 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
             }
 
             const updateDiff = (diff: number) => () => {
+                // The presenter shows the next slide, and changes the current
+                // slide of the audience through the server.
+                if (isPresenter) {
+                    if (slideState) {
+                        sendSlide(slideState.current + diff);
+                    }
+                    return;
+                }
+
                 const pageSelector = document.getElementById("typst-page-selector") as HTMLSelectElement | undefined;
 
                 if (pageSelector) {
@@ -137,6 +158,7 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
                     if (svgDoc.setPartialPageNumber(page)) {
                         pageSelector.value = page.toString();
                         blurInput();
+                        sendSlide(page);
                     }
                 }
             }
@@ -171,6 +193,20 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
             const helpButton = document.getElementById("typst-top-help-button");
             helpButton?.addEventListener('click', toggleHelp);
 
+            if (isPresenter) {
+                const panel = presenterPanel = new PresenterPanel();
+                subsribes.push(new Subscription(() => {
+                    panel.dispose();
+                    presenterPanel = undefined;
+                }));
+            }
+
+            const openPresenter = () => {
+                const url = new URL(window.location.href);
+                url.searchParams.set("presenter", "");
+                window.open(url.href, "typst-presenter");
+            };
+
             window.addEventListener("keydown", (e) => {
                 let handled = true;
                 switch (e.key) {
@@ -195,6 +231,21 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
                         removeHelp();
                         focusInput();
                         break;
+                    case "p":
+                        if (isPresenter) {
+                            handled = false;
+                            break;
+                        }
+                        removeHelp();
+                        openPresenter();
+                        break;
+                    case "t":
+                        if (!presenterPanel) {
+                            handled = false;
+                            break;
+                        }
+                        presenterPanel.resetTimer();
+                        break;
                     case "Escape":
                         removeHelp();
                         blurInput();
@@ -227,6 +278,9 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
                     window.typstWebsocket = sock as any;
                     svgDoc.reset();
                     window.typstWebsocket.send("current");
+                    if (previewMode === PreviewMode.Slide) {
+                        window.typstWebsocket.send("slide-state");
+                    }
                 }
             },
             closeObserver: {
@@ -304,7 +358,19 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
                 }
             }
 
+            if (message[0] === "slide-state") {
+                slideState = JSON.parse(dec.decode((message[1] as any).buffer)) as SlideState;
+                applySlideState(slideState);
+                return;
+            }
+
             if (message[0] === "jump" || message[0] === "viewport") {
+                // The presenter follows the audience, whose slide is changed by
+                // the jumps.
+                if (isPresenter) {
+                    return;
+                }
+
                 const rootElem =
                     document.getElementById("typst-app")?.firstElementChild;
                
@@ -345,6 +411,7 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
                         if (pageSelector) {
                             pageSelector.value = page.toString();
                         }
+                        sendSlide(page);
                         // pageToJump = 1;
                         // todo: hint location
                         return;
@@ -392,8 +459,36 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
             }
 
             svgDoc.addChangement(message as any);
+            // The pages may be not available until the document is rendered.
+            if (slideState) {
+                applySlideState(slideState);
+            }
         };
 
+        /// Apply the slide state from the server. The audience follows the
+        /// current slide, while the presenter shows the next slide.
+        function applySlideState(state: SlideState) {
+            if (previewMode !== PreviewMode.Slide) {
+                return;
+            }
+
+            if (isPresenter) {
+                presenterPanel?.update(state);
+                document.getElementById("typst-container")?.classList.toggle("presenter-end", !state.next);
+                if (state.next && state.next !== svgDoc.getPartialPageNumber()) {
+                    svgDoc.setPartialPageNumber(state.next);
+                }
+                return;
+            }
+
+            if (state.current !== svgDoc.getPartialPageNumber() && svgDoc.setPartialPageNumber(state.current)) {
+                const pageSelector = document.getElementById("typst-page-selector") as HTMLSelectElement | undefined;
+                if (pageSelector) {
+                    pageSelector.value = state.current.toString();
+                }
+            }
+        }
+
         return dispose;
     }
 